@@ -0,0 +1,78 @@
+//! `cargo bench --bench compare` runs the conformance boundaries and random
+//! samples through this crate, `memcomparable`, `ordcode` and `storekey`, and
+//! prints sizes, encoding times and order disagreements per type.
+
+use index_key::compare::{compare, report_text, Adapter};
+use index_key::conformance::{boundaries, Boundaries, Rng};
+use index_key::IndexKey;
+use serde::Serialize;
+use std::fmt::Debug;
+
+struct Memcomparable;
+
+impl<T: Serialize> Adapter<T> for Memcomparable {
+    fn name(&self) -> &str {
+        "memcomparable"
+    }
+    fn encode(&self, value: &T) -> Vec<u8> {
+        memcomparable::to_vec(value).expect("memcomparable encoding")
+    }
+}
+
+struct Ordcode;
+
+impl<T: Serialize> Adapter<T> for Ordcode {
+    fn name(&self) -> &str {
+        "ordcode"
+    }
+    fn encode(&self, value: &T) -> Vec<u8> {
+        ordcode::ser_to_vec_ordered(value, ordcode::Order::Ascending).expect("ordcode encoding")
+    }
+}
+
+struct Storekey;
+
+impl<T: Serialize> Adapter<T> for Storekey {
+    fn name(&self) -> &str {
+        "storekey"
+    }
+    fn encode(&self, value: &T) -> Vec<u8> {
+        storekey::serialize(value).expect("storekey encoding")
+    }
+}
+
+fn bench<T: IndexKey + Serialize + Clone + Debug>(label: &str, corpus: &[T]) {
+    let report = compare(corpus, &[&Memcomparable, &Ordcode, &Storekey]);
+    println!("{} ({} values)", label, corpus.len());
+    print!("{}", report_text(&report));
+}
+
+fn sampled<T: Boundaries>(rng: &mut Rng, n: usize, sample: impl Fn(&mut Rng) -> T) -> Vec<T> {
+    let mut corpus = boundaries::<T>();
+    corpus.extend((0..n).map(|_| sample(rng)));
+    corpus
+}
+
+fn word(rng: &mut Rng) -> String {
+    (0..rng.below(12))
+        .map(|_| (b'a' + rng.below(26) as u8) as char)
+        .collect()
+}
+
+fn main() {
+    let mut rng = Rng::new(264);
+    bench("u64", &sampled(&mut rng, 500, |r| r.next_u64()));
+    bench("i64", &sampled(&mut rng, 500, |r| r.next_u64() as i64));
+    bench(
+        "f64",
+        &sampled(&mut rng, 500, |r| f64::from_bits(r.next_u64())),
+    );
+    let mut strings = sampled(&mut rng, 500, word);
+    // storekey terminates strings with a bare 0 and cannot hold one.
+    strings.retain(|s| !s.contains('\0'));
+    bench("String", &strings);
+    let pairs: Vec<(u32, String)> = (0..500)
+        .map(|_| (rng.below(4) as u32, word(&mut rng)))
+        .collect();
+    bench("(u32, String)", &pairs);
+}
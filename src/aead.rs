@@ -0,0 +1,261 @@
+//! encrypted values stored under plaintext keys.
+//!
+//! an [`EncryptedEntry`] keeps its key in the ordinary order-preserving
+//! encoding, so the store can still sort and scan it, and seals the value with
+//! an [`Aead`] cipher using the encoded key as associated data. a ciphertext
+//! moved to another key then fails to open instead of decrypting as that key's
+//! value. the cipher is supplied by the caller, typically wrapping AES-GCM or
+//! ChaCha20-Poly1305 with a fresh nonce stored in its output.
+//!
+//! a [`DetEncrypted`] field is encrypted deterministically instead, so equal
+//! values give equal keys and exact lookups still work while range order is
+//! lost. being its own type, it cannot be mistaken for an ordered field.
+
+use crate::value::IndexValue;
+use crate::IndexKey;
+use std::fmt;
+use std::io::{Cursor, Error, ErrorKind, Read, Write};
+use std::marker::PhantomData;
+
+pub trait Aead {
+    /// encrypts and authenticates `plaintext` together with `associated`. the
+    /// output carries whatever `open` needs besides the cipher key, such as
+    /// the nonce.
+    fn seal(&self, plaintext: &[u8], associated: &[u8]) -> Result<Vec<u8>, Error>;
+    /// an error unless `ciphertext` came from `seal` with the same
+    /// `associated` data.
+    fn open(&self, ciphertext: &[u8], associated: &[u8]) -> Result<Vec<u8>, Error>;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EncryptedEntry<K, V> {
+    pub key: K,
+    pub value: V,
+}
+
+fn read_all<T>(
+    bytes: &[u8],
+    read: impl FnOnce(&mut Cursor<&[u8]>) -> Result<T, Error>,
+) -> Result<T, Error> {
+    let mut cursor = Cursor::new(bytes);
+    let value = read(&mut cursor)?;
+    if cursor.position() as usize != bytes.len() {
+        return Err(Error::new(ErrorKind::InvalidData, "trailing bytes"));
+    }
+    Ok(value)
+}
+
+impl<K: IndexKey, V: IndexValue> EncryptedEntry<K, V> {
+    pub fn new(key: K, value: V) -> EncryptedEntry<K, V> {
+        EncryptedEntry { key, value }
+    }
+
+    /// the encoded key and the sealed value.
+    pub fn seal<A: Aead>(self, cipher: &A) -> Result<(Vec<u8>, Vec<u8>), Error> {
+        let mut key = vec![];
+        self.key.to_key(&mut key)?;
+        let mut value = vec![];
+        self.value.to_value(&mut value)?;
+        let sealed = cipher.seal(&value, &key)?;
+        Ok((key, sealed))
+    }
+
+    pub fn open<A: Aead>(
+        key: &[u8],
+        sealed: &[u8],
+        cipher: &A,
+    ) -> Result<EncryptedEntry<K, V>, Error> {
+        let value = cipher.open(sealed, key)?;
+        Ok(EncryptedEntry {
+            key: read_all(key, |r| K::from_key(r))?,
+            value: read_all(&value, |r| V::from_value(r))?,
+        })
+    }
+}
+
+/// encryption where equal plaintexts give equal ciphertexts, such as AES-SIV.
+pub trait DeterministicAead {
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, Error>;
+    fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, Error>;
+}
+
+/// a field holding the deterministic encryption of `T`'s key. use a separate
+/// cipher key per column, or equal values in two columns will match.
+pub struct DetEncrypted<T> {
+    ciphertext: Vec<u8>,
+    plain: PhantomData<fn() -> T>,
+}
+
+impl<T: IndexKey> DetEncrypted<T> {
+    pub fn seal<A: DeterministicAead>(value: T, cipher: &A) -> Result<DetEncrypted<T>, Error> {
+        let mut plaintext = vec![];
+        value.to_key(&mut plaintext)?;
+        Ok(DetEncrypted::from_ciphertext(cipher.encrypt(&plaintext)?))
+    }
+
+    pub fn open<A: DeterministicAead>(&self, cipher: &A) -> Result<T, Error> {
+        read_all(&cipher.decrypt(&self.ciphertext)?, |r| T::from_key(r))
+    }
+}
+
+impl<T> DetEncrypted<T> {
+    pub fn from_ciphertext(ciphertext: Vec<u8>) -> DetEncrypted<T> {
+        DetEncrypted {
+            ciphertext,
+            plain: PhantomData,
+        }
+    }
+
+    pub fn ciphertext(&self) -> &[u8] {
+        &self.ciphertext
+    }
+}
+
+impl<T> Clone for DetEncrypted<T> {
+    fn clone(&self) -> Self {
+        DetEncrypted::from_ciphertext(self.ciphertext.clone())
+    }
+}
+
+impl<T> PartialEq for DetEncrypted<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ciphertext == other.ciphertext
+    }
+}
+
+impl<T> Eq for DetEncrypted<T> {}
+
+impl<T> fmt::Debug for DetEncrypted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DetEncrypted({} bytes)", self.ciphertext.len())
+    }
+}
+
+/// the ciphertext as a byte string.
+impl<T> IndexKey for DetEncrypted<T> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        self.ciphertext.to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        Vec::<u8>::from_key(key).map(DetEncrypted::from_ciphertext)
+    }
+}
+
+#[cfg(feature = "digest")]
+#[test]
+fn test_encrypted_entry() {
+    use crate::digest::sha256;
+    use crate::to_key;
+    // a toy cipher: xor with one byte, then a 4-byte tag. not secure.
+    struct Toy(u8);
+    impl Toy {
+        fn tag(&self, body: &[u8], associated: &[u8]) -> [u8; 4] {
+            let digest = sha256(&[&[self.0][..], body, associated].concat());
+            [digest[0], digest[1], digest[2], digest[3]]
+        }
+    }
+    impl Aead for Toy {
+        fn seal(&self, plaintext: &[u8], associated: &[u8]) -> Result<Vec<u8>, Error> {
+            let mut body: Vec<u8> = plaintext.iter().map(|b| b ^ self.0).collect();
+            let tag = self.tag(&body, associated);
+            body.extend_from_slice(&tag);
+            Ok(body)
+        }
+        fn open(&self, ciphertext: &[u8], associated: &[u8]) -> Result<Vec<u8>, Error> {
+            let split = ciphertext
+                .len()
+                .checked_sub(4)
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, "short ciphertext"))?;
+            let (body, tag) = ciphertext.split_at(split);
+            if self.tag(body, associated) != tag {
+                return Err(Error::new(ErrorKind::InvalidData, "authentication failed"));
+            }
+            Ok(body.iter().map(|b| b ^ self.0).collect())
+        }
+    }
+
+    type Entry = EncryptedEntry<(u32, String), (String, u64)>;
+    let cipher = Toy(0x5a);
+    let alice = Entry::new((1, "alice".to_owned()), ("secret".to_owned(), 9));
+    let bob = Entry::new((1, "bob".to_owned()), ("other".to_owned(), 3));
+    let (alice_key, alice_value) = alice.clone().seal(&cipher).unwrap();
+    let (bob_key, bob_value) = bob.clone().seal(&cipher).unwrap();
+    assert_eq!(alice_key, to_key((1u32, "alice".to_owned())));
+    assert!(alice_key < bob_key);
+    assert!(!alice_value.windows(6).any(|w| w == b"secret"));
+
+    assert_eq!(
+        Entry::open(&alice_key, &alice_value, &cipher).unwrap(),
+        alice
+    );
+    assert_eq!(Entry::open(&bob_key, &bob_value, &cipher).unwrap(), bob);
+    // the value swapped onto another key does not open.
+    assert!(Entry::open(&bob_key, &alice_value, &cipher).is_err());
+    assert!(Entry::open(&alice_key, &alice_value, &Toy(1)).is_err());
+    let mut long_key = alice_key.clone();
+    long_key.push(0);
+    assert!(Entry::open(&long_key, &cipher.seal(&[], &long_key).unwrap(), &cipher).is_err());
+
+    // a key that fails to encode fails the seal.
+    struct Unencodable;
+    impl IndexKey for Unencodable {
+        fn to_key<W: Write>(self, _: &mut W) -> Result<&mut W, Error> {
+            Err(Error::new(ErrorKind::InvalidInput, "unencodable"))
+        }
+        fn from_key<R: Read>(_: &mut R) -> Result<Self, Error> {
+            Ok(Unencodable)
+        }
+    }
+    let err = EncryptedEntry::new(Unencodable, 0u64)
+        .seal(&cipher)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[cfg(feature = "digest")]
+#[test]
+fn test_det_encrypted() {
+    use crate::digest::sha256;
+    use crate::{from_key, to_key};
+    // a toy synthetic-iv cipher: a 4-byte tag of the plaintext, then the
+    // plaintext xored with it. not secure.
+    struct Toy(u8);
+    impl DeterministicAead for Toy {
+        fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+            let digest = sha256(&[&[self.0][..], plaintext].concat());
+            let mut out = digest[..4].to_vec();
+            out.extend(plaintext.iter().enumerate().map(|(i, b)| b ^ digest[i % 4]));
+            Ok(out)
+        }
+        fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+            let invalid = || Error::new(ErrorKind::InvalidData, "authentication failed");
+            let (tag, body) = ciphertext.split_at(4.min(ciphertext.len()));
+            let plain: Vec<u8> = body
+                .iter()
+                .enumerate()
+                .map(|(i, b)| b ^ tag[i % 4])
+                .collect();
+            if tag.len() != 4 || sha256(&[&[self.0][..], &plain].concat())[..4] != *tag {
+                return Err(invalid());
+            }
+            Ok(plain)
+        }
+    }
+
+    let cipher = Toy(3);
+    let email = |s: &str| DetEncrypted::seal(s.to_owned(), &cipher).unwrap();
+    let stored = to_key((7u32, email("a@example.com")));
+    // an equality lookup encrypts the probe the same way.
+    assert_eq!(stored, to_key((7u32, email("a@example.com"))));
+    assert_ne!(stored, to_key((7u32, email("b@example.com"))));
+    assert!(!stored.windows(7).any(|w| w == b"example"));
+
+    let (tenant, field) = from_key::<(u32, DetEncrypted<String>)>(stored).unwrap();
+    assert_eq!(tenant, 7);
+    assert_eq!(field.open(&cipher).unwrap(), "a@example.com");
+    assert!(field.open(&Toy(4)).is_err());
+    assert_ne!(
+        DetEncrypted::seal(1u64, &Toy(4)).unwrap(),
+        DetEncrypted::seal(1u64, &cipher).unwrap()
+    );
+}
@@ -0,0 +1,132 @@
+//! opt-in audit trail of the keys produced through [`to_key`](crate::to_key)
+//! and read through [`from_key`](crate::from_key).
+//!
+//! install one process-wide [`AuditSink`]; it receives an [`AuditRecord`] for
+//! every call with the operation, `std::any::type_name` of the key and its
+//! length. the key bytes themselves are redacted: at most the first
+//! `preview_bytes` given to [`set_sink`] are passed on, in hex, and none by
+//! default.
+
+use std::io::Error;
+use std::sync::OnceLock;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Operation {
+    Encode,
+    Decode,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AuditRecord {
+    pub operation: Operation,
+    pub type_name: &'static str,
+    pub len: usize,
+    /// the leading bytes in hex, with `…` if any were cut.
+    pub preview: Option<String>,
+    /// false for a decode that failed.
+    pub ok: bool,
+}
+
+pub trait AuditSink: Send + Sync {
+    fn record(&self, record: &AuditRecord);
+}
+
+struct Installed {
+    sink: Box<dyn AuditSink>,
+    preview_bytes: usize,
+}
+
+static SINK: OnceLock<Installed> = OnceLock::new();
+
+/// fails if a sink is already installed. `preview_bytes` of 0 sends no
+/// preview at all.
+pub fn set_sink<S: AuditSink + 'static>(sink: S, preview_bytes: usize) -> Result<(), Error> {
+    SINK.set(Installed {
+        sink: Box::new(sink),
+        preview_bytes,
+    })
+    .map_err(|_| Error::other("audit sink already set"))
+}
+
+fn preview(key: &[u8], bytes: usize) -> Option<String> {
+    if bytes == 0 {
+        return None;
+    }
+    let mut text: String = key
+        .iter()
+        .take(bytes)
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    if key.len() > bytes {
+        text.push('…');
+    }
+    Some(text)
+}
+
+fn record<I>(operation: Operation, key: &[u8], ok: bool) {
+    if let Some(installed) = SINK.get() {
+        installed.sink.record(&AuditRecord {
+            operation,
+            type_name: std::any::type_name::<I>(),
+            len: key.len(),
+            preview: preview(key, installed.preview_bytes),
+            ok,
+        });
+    }
+}
+
+pub(crate) fn encoded<I>(key: &[u8]) {
+    record::<I>(Operation::Encode, key, true)
+}
+
+pub(crate) fn decoded<I>(key: &[u8], ok: bool) {
+    record::<I>(Operation::Decode, key, ok)
+}
+
+#[test]
+fn test_audit() {
+    use crate::{from_key, to_key, IndexKey};
+    use std::io::{Read, Write};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug)]
+    struct Audited(String, u16);
+    impl IndexKey for Audited {
+        fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+            (self.0, self.1).to_key(result)
+        }
+        fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+            let (name, id) = <(String, u16)>::from_key(key)?;
+            Ok(Audited(name, id))
+        }
+    }
+
+    type Records = Arc<Mutex<Vec<AuditRecord>>>;
+    struct Collect(Records);
+    impl AuditSink for Collect {
+        fn record(&self, record: &AuditRecord) {
+            if record.type_name.ends_with("Audited") {
+                self.0.lock().unwrap().push(record.clone());
+            }
+        }
+    }
+
+    let records = Records::default();
+    set_sink(Collect(records.clone()), 2).unwrap();
+    assert!(set_sink(Collect(records.clone()), 0).is_err());
+
+    let key = to_key(Audited("ssn 123".to_owned(), 7));
+    from_key::<Audited>(key.clone()).unwrap();
+    from_key::<Audited>(vec![b'a']).unwrap_err();
+    let records = records.lock().unwrap();
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[0].operation, Operation::Encode);
+    assert_eq!(records[0].len, key.len());
+    assert_eq!(records[0].preview.as_deref(), Some("7373…"));
+    assert!(records[0].type_name.ends_with("test_audit::Audited"));
+    assert_eq!(records[1].operation, Operation::Decode);
+    assert!(records[1].ok);
+    assert_eq!((records[2].len, records[2].ok), (1, false));
+    assert_eq!(records[2].preview.as_deref(), Some("61"));
+    assert_eq!(preview(&[1, 2, 3], 0), None);
+}
@@ -0,0 +1,101 @@
+//! audit-log keys along two axes.
+//!
+//! each event is written twice, as `(object, !ts, actor)` and
+//! `(actor, !ts, object)`, so "who touched X" and "what did Y touch" are each
+//! one range scan, newest first. the two layouts go in separate keyspaces.
+
+use crate::range::{prefix_successor, KeyRange};
+use crate::{from_key, to_key, IndexKey};
+use std::io::Error;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AuditEvent<O, A> {
+    pub object: O,
+    pub actor: A,
+    pub ts: u64,
+}
+
+impl<O: IndexKey + Clone, A: IndexKey + Clone> AuditEvent<O, A> {
+    pub fn new(object: O, actor: A, ts: u64) -> AuditEvent<O, A> {
+        AuditEvent { object, actor, ts }
+    }
+
+    /// `(object, !ts, actor)`.
+    pub fn object_key(&self) -> Vec<u8> {
+        to_key((self.object.clone(), !self.ts, self.actor.clone()))
+    }
+
+    /// `(actor, !ts, object)`.
+    pub fn actor_key(&self) -> Vec<u8> {
+        to_key((self.actor.clone(), !self.ts, self.object.clone()))
+    }
+
+    /// the object key and the actor key, to write together.
+    pub fn keys(&self) -> (Vec<u8>, Vec<u8>) {
+        (self.object_key(), self.actor_key())
+    }
+
+    pub fn from_object_key(key: Vec<u8>) -> Result<AuditEvent<O, A>, Error> {
+        let (object, ts, actor): (O, u64, A) = from_key(key)?;
+        Ok(AuditEvent::new(object, actor, !ts))
+    }
+
+    pub fn from_actor_key(key: Vec<u8>) -> Result<AuditEvent<O, A>, Error> {
+        let (actor, ts, object): (A, u64, O) = from_key(key)?;
+        Ok(AuditEvent::new(object, actor, !ts))
+    }
+
+    /// everyone who touched `object`, newest first.
+    pub fn object_range(object: O) -> KeyRange {
+        KeyRange::prefix(to_key(object))
+    }
+
+    /// everything `actor` touched, newest first.
+    pub fn actor_range(actor: A) -> KeyRange {
+        KeyRange::prefix(to_key(actor))
+    }
+
+    /// what `actor` touched at or after `since`, newest first.
+    pub fn actor_since(actor: A, since: u64) -> KeyRange {
+        let start = to_key(actor.clone());
+        let end = match (!since).checked_add(1) {
+            Some(end) => Some(to_key((actor, end))),
+            None => prefix_successor(&start),
+        };
+        KeyRange { start, end }
+    }
+}
+
+#[test]
+fn test_audit_event() {
+    type Event = AuditEvent<String, u32>;
+    let events = [
+        Event::new("doc/1".to_owned(), 7, 100),
+        Event::new("doc/1".to_owned(), 8, 200),
+        Event::new("doc/2".to_owned(), 7, 300),
+    ];
+    let mut by_object: Vec<_> = events.iter().map(Event::object_key).collect();
+    let mut by_actor: Vec<_> = events.iter().map(Event::actor_key).collect();
+    by_object.sort();
+    by_actor.sort();
+
+    let range = Event::object_range("doc/1".to_owned());
+    let who: Vec<_> = by_object
+        .iter()
+        .filter(|k| range.contains(k))
+        .map(|k| Event::from_object_key(k.clone()).unwrap())
+        .collect();
+    assert_eq!(who, vec![events[1].clone(), events[0].clone()]);
+
+    let range = Event::actor_range(7);
+    let what: Vec<_> = by_actor
+        .iter()
+        .filter(|k| range.contains(k))
+        .map(|k| Event::from_actor_key(k.clone()).unwrap())
+        .collect();
+    assert_eq!(what, vec![events[2].clone(), events[0].clone()]);
+
+    let recent = Event::actor_since(7, 300);
+    assert_eq!(by_actor.iter().filter(|k| recent.contains(k)).count(), 1);
+    assert!(Event::actor_since(7, 0).contains(&events[0].actor_key()));
+}
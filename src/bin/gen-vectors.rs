@@ -0,0 +1,18 @@
+//! `gen-vectors [path]` writes the conformance vectors as json to `path`, or
+//! to stdout.
+
+use index_key::spec;
+use std::process::exit;
+
+fn main() {
+    let json = spec::vectors_json(&spec::vectors());
+    match std::env::args().nth(1) {
+        Some(path) => {
+            if let Err(e) = std::fs::write(&path, json) {
+                eprintln!("gen-vectors: {}: {}", path, e);
+                exit(1);
+            }
+        }
+        None => print!("{}", json),
+    }
+}
@@ -0,0 +1,14 @@
+//! `index-key spec` prints the encoding spec as json.
+
+use index_key::spec;
+use std::process::exit;
+
+fn main() {
+    match std::env::args().nth(1).as_deref() {
+        Some("spec") => print!("{}", spec::to_json()),
+        _ => {
+            eprintln!("usage: index-key spec");
+            exit(2);
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! keys sorted by a value derived from the record.
+//!
+//! a [`ByKeyOf<T, F>`] encodes `F::key_of(&value)` as its key, followed by
+//! `value` itself in the [value encoding](crate::value), so records sort by the
+//! derived key and decode back to the whole value. records with equal derived
+//! keys follow in an unspecified but stable order, and the derived key's
+//! encoding alone is a prefix of every record it matches.
+
+use crate::value::IndexValue;
+use crate::IndexKey;
+use std::fmt;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::marker::PhantomData;
+
+/// a key extraction, named by a type so keys can be decoded without an
+/// instance of it.
+pub trait KeyOf<T> {
+    type Key: IndexKey + PartialEq;
+    fn key_of(value: &T) -> Self::Key;
+}
+
+pub struct ByKeyOf<T, F> {
+    pub value: T,
+    extract: PhantomData<fn() -> F>,
+}
+
+impl<T, F> ByKeyOf<T, F> {
+    pub fn new(value: T) -> ByKeyOf<T, F> {
+        ByKeyOf {
+            value,
+            extract: PhantomData,
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T: Clone, F> Clone for ByKeyOf<T, F> {
+    fn clone(&self) -> Self {
+        ByKeyOf::new(self.value.clone())
+    }
+}
+
+impl<T: PartialEq, F> PartialEq for ByKeyOf<T, F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T: Eq, F> Eq for ByKeyOf<T, F> {}
+
+impl<T: fmt::Debug, F> fmt::Debug for ByKeyOf<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("ByKeyOf").field(&self.value).finish()
+    }
+}
+
+impl<T: IndexValue, F: KeyOf<T>> IndexKey for ByKeyOf<T, F> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        F::key_of(&self.value).to_key(result)?;
+        self.value.to_value(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let derived = F::Key::from_key(key)?;
+        let value = T::from_value(key)?;
+        if F::key_of(&value) != derived {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "derived key does not match value",
+            ));
+        }
+        Ok(ByKeyOf::new(value))
+    }
+}
+
+#[test]
+fn test_by_key_of() {
+    use crate::{from_key, to_key, Desc};
+    struct Folded;
+    impl KeyOf<(String, u32)> for Folded {
+        type Key = String;
+        fn key_of(value: &(String, u32)) -> String {
+            value.0.to_lowercase()
+        }
+    }
+    struct Oldest;
+    impl KeyOf<(String, u32)> for Oldest {
+        type Key = Desc<u32>;
+        fn key_of(value: &(String, u32)) -> Desc<u32> {
+            Desc(value.1)
+        }
+    }
+
+    let users = [
+        ("bob".to_owned(), 30),
+        ("Alice".to_owned(), 25),
+        ("carol".to_owned(), 41),
+    ];
+    let mut keys: Vec<Vec<u8>> = users
+        .iter()
+        .map(|u| to_key(ByKeyOf::<_, Folded>::new(u.clone())))
+        .collect();
+    keys.sort();
+    let names: Vec<String> = keys
+        .into_iter()
+        .map(|k| {
+            from_key::<ByKeyOf<(String, u32), Folded>>(k)
+                .unwrap()
+                .value
+                .0
+        })
+        .collect();
+    assert_eq!(names, ["Alice", "bob", "carol"]);
+
+    let mut keys: Vec<Vec<u8>> = users
+        .iter()
+        .map(|u| to_key(ByKeyOf::<_, Oldest>::new(u.clone())))
+        .collect();
+    keys.sort();
+    let ages: Vec<u32> = keys
+        .into_iter()
+        .map(|k| {
+            from_key::<ByKeyOf<(String, u32), Oldest>>(k)
+                .unwrap()
+                .into_inner()
+                .1
+        })
+        .collect();
+    assert_eq!(ages, [41, 30, 25]);
+
+    let key = to_key(ByKeyOf::<_, Folded>::new(("Dave".to_owned(), 7)));
+    assert!(key.starts_with(&to_key("dave".to_owned())));
+    assert_eq!(
+        from_key::<(ByKeyOf<(String, u32), Folded>, u8)>(to_key((
+            ByKeyOf::<_, Folded>::new(("Dave".to_owned(), 7)),
+            3u8
+        )))
+        .unwrap()
+        .1,
+        3
+    );
+    let mut forged = to_key("eve".to_owned());
+    ("Dave".to_owned(), 7u32).to_value(&mut forged).unwrap();
+    assert!(from_key::<ByKeyOf<(String, u32), Folded>>(forged).is_err());
+}
@@ -0,0 +1,136 @@
+//! change events for replicating between stores that share a key format.
+//!
+//! an event is framed as LEB128 `ts`, one op byte, then the LEB128 length of
+//! `key` and its bytes — usually a few bytes more than the key itself. events
+//! order by `(ts, key)`, the order a replica applies them in.
+
+use crate::value::{read_len, write_len, IndexValue};
+use std::cmp::Ordering;
+use std::io::{Error, ErrorKind, Read, Write};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ChangeOp {
+    Put,
+    Delete,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ChangeEvent {
+    pub key: Vec<u8>,
+    pub op: ChangeOp,
+    pub ts: u64,
+}
+
+impl ChangeEvent {
+    pub fn put(key: Vec<u8>, ts: u64) -> ChangeEvent {
+        ChangeEvent {
+            key,
+            op: ChangeOp::Put,
+            ts,
+        }
+    }
+
+    pub fn delete(key: Vec<u8>, ts: u64) -> ChangeEvent {
+        ChangeEvent {
+            key,
+            op: ChangeOp::Delete,
+            ts,
+        }
+    }
+}
+
+/// by `(ts, key)`, then puts before deletes.
+impl Ord for ChangeEvent {
+    fn cmp(&self, other: &ChangeEvent) -> Ordering {
+        (self.ts, &self.key, self.op).cmp(&(other.ts, &other.key, other.op))
+    }
+}
+
+impl PartialOrd for ChangeEvent {
+    fn partial_cmp(&self, other: &ChangeEvent) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl IndexValue for ChangeEvent {
+    fn to_value<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        write_len(self.ts, result)?;
+        result.write_all(&[match self.op {
+            ChangeOp::Put => 0,
+            ChangeOp::Delete => 1,
+        }])?;
+        self.key.to_value(result)
+    }
+    fn from_value<R: Read>(value: &mut R) -> Result<Self, Error> {
+        let ts = read_len(value)?;
+        let mut op = [0];
+        value.read_exact(&mut op)?;
+        let op = match op[0] {
+            0 => ChangeOp::Put,
+            1 => ChangeOp::Delete,
+            _ => return Err(Error::new(ErrorKind::InvalidData, "invalid change op")),
+        };
+        let key = Vec::<u8>::from_value(value)?;
+        Ok(ChangeEvent { key, op, ts })
+    }
+}
+
+/// events back to back, as written by [`to_value`](crate::value::to_value) on
+/// each; stops at the first error.
+pub fn read_events<R: Read>(mut stream: R) -> impl Iterator<Item = Result<ChangeEvent, Error>> {
+    let mut failed = false;
+    std::iter::from_fn(move || {
+        if failed {
+            return None;
+        }
+        // peek one byte to tell a clean end from a truncated event.
+        let mut first = [0];
+        match stream.read(&mut first) {
+            Ok(0) => return None,
+            Ok(_) => {}
+            Err(e) => {
+                failed = true;
+                return Some(Err(e));
+            }
+        }
+        let event = ChangeEvent::from_value(&mut first.chain(&mut stream));
+        failed = event.is_err();
+        Some(event)
+    })
+}
+
+#[test]
+fn test_change_event() {
+    use crate::to_key;
+    use crate::value::{from_value, to_value};
+    let event = ChangeEvent::put(to_key((7u32, "a".to_owned())), 300);
+    let bytes = to_value(event.clone());
+    assert_eq!(bytes.len(), 2 + 1 + 1 + event.key.len());
+    assert_eq!(from_value::<ChangeEvent>(bytes).unwrap(), event);
+
+    let mut events = vec![
+        ChangeEvent::delete(vec![2], 5),
+        ChangeEvent::put(vec![9], 1),
+        ChangeEvent::put(vec![2], 5),
+        ChangeEvent::put(vec![1], 5),
+    ];
+    events.sort();
+    let order: Vec<_> = events.iter().map(|e| (e.ts, e.key[0], e.op)).collect();
+    assert_eq!(
+        order,
+        vec![
+            (1, 9, ChangeOp::Put),
+            (5, 1, ChangeOp::Put),
+            (5, 2, ChangeOp::Put),
+            (5, 2, ChangeOp::Delete),
+        ]
+    );
+
+    let stream: Vec<u8> = events.iter().cloned().flat_map(to_value).collect();
+    let read: Vec<_> = read_events(&stream[..]).map(Result::unwrap).collect();
+    assert_eq!(read, events);
+    let truncated: Vec<_> = read_events(&stream[..stream.len() - 1]).collect();
+    assert_eq!(truncated.len(), 4);
+    assert!(truncated[3].is_err());
+    assert!(from_value::<ChangeEvent>(vec![1, 2, 0]).is_err());
+}
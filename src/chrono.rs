@@ -0,0 +1,130 @@
+//! keys for `chrono` dates and times.
+//!
+//! a `NaiveDate` encodes as `(year: i32, month: u8, day: u8)` and a
+//! `NaiveTime` as `(seconds since midnight: u32, nanos: u32)`, so both sort
+//! chronologically without converting to integers first; a `NaiveDateTime`
+//! is the pair of them. a `DateTime` is normalized to UTC, so instants sort
+//! chronologically whatever offset they were recorded in.
+
+use crate::IndexKey;
+use ::chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc};
+use std::io::{Error, ErrorKind, Read, Write};
+
+impl IndexKey for NaiveDate {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        (self.year(), self.month() as u8, self.day() as u8).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let (year, month, day) = <(i32, u8, u8)>::from_key(key)?;
+        NaiveDate::from_ymd_opt(year, u32::from(month), u32::from(day))
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "invalid date"))
+    }
+}
+
+/// a leap second keeps its nanoseconds of 1_000_000_000 and more, sorting
+/// after the rest of the second.
+impl IndexKey for NaiveTime {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        (self.num_seconds_from_midnight(), self.nanosecond()).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let (secs, nanos) = <(u32, u32)>::from_key(key)?;
+        NaiveTime::from_num_seconds_from_midnight_opt(secs, nanos)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "invalid time of day"))
+    }
+}
+
+impl IndexKey for NaiveDateTime {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        (self.date(), self.time()).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let (date, time) = <(NaiveDate, NaiveTime)>::from_key(key)?;
+        Ok(NaiveDateTime::new(date, time))
+    }
+}
+
+/// the UTC date and time. decoding gives the instant in UTC, converted to
+/// `Tz`.
+impl<Tz: TimeZone> IndexKey for DateTime<Tz>
+where
+    DateTime<Tz>: From<DateTime<Utc>>,
+{
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        self.naive_utc().to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        Ok(NaiveDateTime::from_key(key)?.and_utc().into())
+    }
+}
+
+#[test]
+fn test_naive_date() {
+    use crate::{from_key, to_key};
+    let d = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+    let dates = [
+        d(-1, 12, 31),
+        d(0, 2, 29),
+        d(1969, 12, 31),
+        d(1970, 1, 1),
+        d(2000, 2, 29),
+        d(2024, 10, 5),
+    ];
+    let keys: Vec<_> = dates.iter().map(|d| to_key(*d)).collect();
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    for date in dates {
+        assert_eq!(from_key::<NaiveDate>(to_key(date)).unwrap(), date);
+    }
+    assert_eq!(to_key(d(2024, 2, 29)), to_key((2024i32, 2u8, 29u8)));
+    assert!(from_key::<NaiveDate>(to_key((2023i32, 4u8, 31u8))).is_err());
+    assert!(from_key::<NaiveDate>(to_key((i32::MAX, 1u8, 1u8))).is_err());
+}
+
+#[test]
+fn test_naive_time() {
+    use crate::{from_key, to_key};
+    let t = |h, m, s, n| NaiveTime::from_hms_nano_opt(h, m, s, n).unwrap();
+    let noon = t(12, 0, 0, 0);
+    let later = t(12, 0, 0, 1);
+    let last = t(23, 59, 59, 999_999_999);
+    let leap = t(23, 59, 59, 1_500_000_000);
+    assert!(to_key(NaiveTime::MIN) < to_key(noon));
+    assert!(to_key(noon) < to_key(later));
+    assert!(to_key(later) < to_key(last));
+    assert!(to_key(last) < to_key(leap));
+    assert_eq!(to_key(noon), to_key((43_200u32, 0u32)));
+    assert_eq!(from_key::<NaiveTime>(to_key(last)).unwrap(), last);
+    assert_eq!(from_key::<NaiveTime>(to_key(leap)).unwrap(), leap);
+    assert!(from_key::<NaiveTime>(to_key((86_400u32, 0u32))).is_err());
+}
+
+#[test]
+fn test_date_time() {
+    use crate::{from_key, to_key};
+    use ::chrono::FixedOffset;
+    let day = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+    // 01:30 at +02:00 is 23:30 UTC the day before.
+    let east = FixedOffset::east_opt(7200).unwrap();
+    let early = east
+        .from_local_datetime(&day.and_hms_nano_opt(1, 30, 0, 5).unwrap())
+        .unwrap();
+    let utc = NaiveDate::from_ymd_opt(2024, 2, 29)
+        .unwrap()
+        .and_hms_nano_opt(23, 30, 0, 5)
+        .unwrap();
+    assert_eq!(to_key(early), to_key(utc));
+    assert_eq!(from_key::<NaiveDateTime>(to_key(utc)).unwrap(), utc);
+    // 00:00 at -05:00 is later than 01:30 at +02:00.
+    let west = FixedOffset::west_opt(18_000).unwrap();
+    let later = west
+        .from_local_datetime(&day.and_hms_opt(0, 0, 0).unwrap())
+        .unwrap();
+    assert!(to_key(early) < to_key(later));
+    let decoded = from_key::<DateTime<FixedOffset>>(to_key(early)).unwrap();
+    assert_eq!(decoded, early);
+    assert_eq!(decoded.offset().local_minus_utc(), 0);
+    assert_eq!(from_key::<DateTime<Utc>>(to_key(early)).unwrap(), early);
+
+    let before_epoch = DateTime::<Utc>::from_timestamp(-1, 0).unwrap();
+    assert!(to_key(before_epoch) < to_key(DateTime::<Utc>::UNIX_EPOCH));
+}
@@ -0,0 +1,236 @@
+//! byte-level codec shared by every encoding.
+//!
+//! only `core` is used here: no `std::io`, no allocation, no unsafe code, so
+//! this module builds without the `std` feature and runs under miri.
+
+pub const TERMINATOR: u8 = 0;
+pub const ESCAPE: u8 = 1;
+
+/// fixed-width, order-preserving big-endian encodings.
+pub trait FixedWidth: Sized + Copy {
+    type Bytes: AsRef<[u8]> + AsMut<[u8]> + Default + Copy;
+    fn encode(self) -> Self::Bytes;
+    fn decode(bytes: Self::Bytes) -> Self;
+}
+
+macro_rules! fixed_u {
+    ($($t:ty),+) => {
+        $(
+            impl FixedWidth for $t {
+                type Bytes = [u8; core::mem::size_of::<$t>()];
+                #[inline]
+                fn encode(self) -> Self::Bytes {
+                    self.to_be_bytes()
+                }
+                #[inline]
+                fn decode(bytes: Self::Bytes) -> $t {
+                    <$t>::from_be_bytes(bytes)
+                }
+            }
+        )+
+    };
+}
+
+fixed_u!(u8, u16, u32, u64, u128);
+
+// flipping the sign bit moves negatives below positives.
+macro_rules! fixed_i {
+    ($($t:ty),+) => {
+        $(
+            impl FixedWidth for $t {
+                type Bytes = [u8; core::mem::size_of::<$t>()];
+                #[inline]
+                fn encode(self) -> Self::Bytes {
+                    (self ^ <$t>::MIN).to_be_bytes()
+                }
+                #[inline]
+                fn decode(bytes: Self::Bytes) -> $t {
+                    <$t>::from_be_bytes(bytes) ^ <$t>::MIN
+                }
+            }
+        )+
+    };
+}
+
+fixed_i!(i8, i16, i32, i64, i128);
+
+// positives get the sign bit set, negatives get every bit flipped.
+macro_rules! fixed_f {
+    ($f:ty, $i:ty, $u:ty) => {
+        impl FixedWidth for $f {
+            type Bytes = [u8; core::mem::size_of::<$f>()];
+            #[inline]
+            fn encode(self) -> Self::Bytes {
+                const BITS: u32 = <$i>::BITS - 1;
+                let value = self.to_bits() as $i;
+                (((value >> BITS) | <$i>::MIN) ^ value).to_be_bytes()
+            }
+            #[inline]
+            fn decode(bytes: Self::Bytes) -> $f {
+                const BITS: u32 = <$i>::BITS - 1;
+                let value = <$i>::from_be_bytes(bytes);
+                <$f>::from_bits(((!value >> BITS | <$i>::MIN) ^ value) as $u)
+            }
+        }
+    };
+}
+
+fixed_f!(f32, i32, u32);
+fixed_f!(f64, i64, u64);
+
+impl FixedWidth for bool {
+    type Bytes = [u8; 1];
+    #[inline]
+    fn encode(self) -> [u8; 1] {
+        [self as u8]
+    }
+    #[inline]
+    fn decode(bytes: [u8; 1]) -> bool {
+        bytes[0] != 0
+    }
+}
+
+/// write `src` escaped and terminated, handing `out` the longest unescaped runs.
+pub fn escape<E, F: FnMut(&[u8]) -> Result<(), E>>(src: &[u8], mut out: F) -> Result<(), E> {
+    let mut rest = src;
+    while let Some(pos) = rest.iter().position(|b| *b == TERMINATOR || *b == ESCAPE) {
+        out(&rest[..pos])?;
+        out(&[ESCAPE, rest[pos]])?;
+        rest = &rest[pos + 1..];
+    }
+    out(rest)?;
+    out(&[TERMINATOR])
+}
+
+/// bytes [`escape`] produces for `src`.
+pub fn escaped_len(src: &[u8]) -> usize {
+    src.len() + src.iter().filter(|b| **b <= ESCAPE).count() + 1
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// a decoded payload byte.
+    Byte(u8),
+    /// an escape marker, nothing to emit.
+    Escape,
+    /// the terminator; the field is complete.
+    End,
+}
+
+/// byte-at-a-time inverse of [`escape`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Unescaper {
+    escaped: bool,
+}
+
+impl Unescaper {
+    #[inline]
+    pub fn step(&mut self, byte: u8) -> Step {
+        if self.escaped {
+            self.escaped = false;
+            return Step::Byte(byte);
+        }
+        match byte {
+            TERMINATOR => Step::End,
+            ESCAPE => {
+                self.escaped = true;
+                Step::Escape
+            }
+            _ => Step::Byte(byte),
+        }
+    }
+}
+
+/// decode one escaped field from the front of `src`, returning the bytes
+/// consumed; a missing terminator consumes everything.
+pub fn unescape<E, F: FnMut(u8) -> Result<(), E>>(src: &[u8], mut out: F) -> Result<usize, E> {
+    let mut state = Unescaper::default();
+    for (i, byte) in src.iter().enumerate() {
+        match state.step(*byte) {
+            Step::Byte(b) => out(b)?,
+            Step::Escape => (),
+            Step::End => return Ok(i + 1),
+        }
+    }
+    Ok(src.len())
+}
+
+/// layout of one field in a composite key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FieldWidth {
+    /// a fixed number of bytes, as for integers, floats and bools.
+    Fixed(usize),
+    /// an escaped, terminated byte string, as for `Vec<u8>` and `String`.
+    Escaped,
+}
+
+/// per-field slices of an encoded composite key, still encoded. escaped
+/// fields keep their terminator. a truncated key yields a short final field;
+/// bytes past the last field are not yielded.
+pub fn raw_fields<'a>(
+    key: &'a [u8],
+    widths: &'a [FieldWidth],
+) -> impl Iterator<Item = &'a [u8]> + 'a {
+    let mut rest = key;
+    widths.iter().map_while(move |width| {
+        if rest.is_empty() {
+            return None;
+        }
+        let len = match width {
+            FieldWidth::Fixed(n) => (*n).min(rest.len()),
+            FieldWidth::Escaped => unescape::<(), _>(rest, |_| Ok(())).unwrap_or(rest.len()),
+        };
+        let (field, tail) = rest.split_at(len);
+        rest = tail;
+        Some(field)
+    })
+}
+
+#[test]
+fn test_codec() {
+    let mut buf = [0u8; 16];
+    let mut len = 0;
+    escape::<(), _>(&[2, 0, 3, 1], |chunk| {
+        buf[len..len + chunk.len()].copy_from_slice(chunk);
+        len += chunk.len();
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(&buf[..len], &[2, 1, 0, 3, 1, 1, 0]);
+    assert_eq!(escaped_len(&[2, 0, 3, 1]), len);
+
+    let mut out = [0u8; 16];
+    let mut n = 0;
+    let consumed = unescape::<(), _>(&[2, 1, 0, 3, 1, 1, 0, 9], |b| {
+        out[n] = b;
+        n += 1;
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(consumed, 7);
+    assert_eq!(&out[..n], &[2, 0, 3, 1]);
+
+    assert!((-1i32).encode() < 0i32.encode());
+    assert!((-0.5f64).encode() < 0.25f64.encode());
+    assert_eq!(f32::decode((-3.5f32).encode()), -3.5);
+    assert_eq!(i128::decode(i128::MIN.encode()), i128::MIN);
+}
+
+#[test]
+fn test_raw_fields() {
+    use FieldWidth::{Escaped, Fixed};
+    // (u16 7, "a\0", bool true, b"")
+    let key = [0, 7, b'a', 1, 0, 0, 1, 0];
+    let widths = [Fixed(2), Escaped, Fixed(1), Escaped];
+    let mut fields = [&[][..]; 4];
+    let mut n = 0;
+    for field in raw_fields(&key, &widths) {
+        fields[n] = field;
+        n += 1;
+    }
+    assert_eq!(n, 4);
+    assert_eq!(fields, [&[0, 7][..], &[b'a', 1, 0, 0], &[1], &[0]]);
+
+    assert_eq!(raw_fields(&key[..3], &widths).count(), 2);
+    assert_eq!(raw_fields(&key, &widths[..1]).next(), Some(&[0, 7][..]));
+}
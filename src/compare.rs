@@ -0,0 +1,119 @@
+//! cross-checking against other order-preserving encodings.
+//!
+//! an [`Adapter`] wraps another encoder (`memcomparable`, `ordcode`,
+//! `storekey`, ...) for one value type. [`compare`] runs the same corpus
+//! through this crate and every adapter, counting the value pairs on which an
+//! adapter's byte order disagrees with ours, and timing encoding. this crate
+//! does not depend on the others; `benches/compare.rs` holds adapters for
+//! `memcomparable`, `ordcode` and `storekey` as dev-dependencies.
+
+use crate::{to_key, IndexKey};
+use std::time::{Duration, Instant};
+
+pub trait Adapter<T> {
+    fn name(&self) -> &str;
+    fn encode(&self, value: &T) -> Vec<u8>;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Comparison {
+    pub name: String,
+    /// ordered pairs whose byte order differs from this crate's.
+    pub disagreements: usize,
+    pub comparisons: usize,
+    pub bytes: usize,
+    pub elapsed: Duration,
+}
+
+struct Own;
+
+impl<T: IndexKey + Clone> Adapter<T> for Own {
+    fn name(&self) -> &str {
+        "index_key"
+    }
+    fn encode(&self, value: &T) -> Vec<u8> {
+        to_key(value.clone())
+    }
+}
+
+fn run<T>(
+    corpus: &[T],
+    adapter: &dyn Adapter<T>,
+    reference: Option<&[Vec<u8>]>,
+) -> (Comparison, Vec<Vec<u8>>) {
+    let started = Instant::now();
+    let keys: Vec<Vec<u8>> = corpus.iter().map(|v| adapter.encode(v)).collect();
+    let elapsed = started.elapsed();
+    let mut comparison = Comparison {
+        name: adapter.name().to_owned(),
+        disagreements: 0,
+        comparisons: 0,
+        bytes: keys.iter().map(Vec::len).sum(),
+        elapsed,
+    };
+    if let Some(reference) = reference {
+        for i in 0..keys.len() {
+            for j in 0..keys.len() {
+                comparison.comparisons += 1;
+                if keys[i].cmp(&keys[j]) != reference[i].cmp(&reference[j]) {
+                    comparison.disagreements += 1;
+                }
+            }
+        }
+    }
+    (comparison, keys)
+}
+
+/// this crate first, then every adapter in order.
+pub fn compare<T: IndexKey + Clone>(corpus: &[T], adapters: &[&dyn Adapter<T>]) -> Vec<Comparison> {
+    let (own, reference) = run(corpus, &Own, None);
+    let mut report = vec![own];
+    for adapter in adapters {
+        report.push(run(corpus, *adapter, Some(&reference)).0);
+    }
+    report
+}
+
+/// one line per encoding.
+pub fn report_text(report: &[Comparison]) -> String {
+    report
+        .iter()
+        .map(|c| {
+            format!(
+                "{}: {} bytes, {:?}, {}/{} pairs disagree\n",
+                c.name, c.bytes, c.elapsed, c.disagreements, c.comparisons
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_compare() {
+    struct BigEndian;
+    impl Adapter<u32> for BigEndian {
+        fn name(&self) -> &str {
+            "big-endian"
+        }
+        fn encode(&self, value: &u32) -> Vec<u8> {
+            value.to_be_bytes().to_vec()
+        }
+    }
+    struct LittleEndian;
+    impl Adapter<u32> for LittleEndian {
+        fn name(&self) -> &str {
+            "little-endian"
+        }
+        fn encode(&self, value: &u32) -> Vec<u8> {
+            value.to_le_bytes().to_vec()
+        }
+    }
+    let corpus: Vec<u32> = crate::conformance::boundaries();
+    let report = compare(&corpus, &[&BigEndian, &LittleEndian]);
+    assert_eq!(report.len(), 3);
+    assert_eq!(report[0].name, "index_key");
+    assert_eq!(report[0].bytes, corpus.len() * 4);
+    assert_eq!(report[1].comparisons, corpus.len() * corpus.len());
+    assert_eq!(report[1].disagreements, 0);
+    assert!(report[2].disagreements > 0);
+    assert_eq!(report_text(&report).lines().count(), 3);
+}
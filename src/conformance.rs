@@ -0,0 +1,609 @@
+//! randomized ordering conformance checks.
+//!
+//! every supported type (and a few tuple shapes, wrappers and collections) is
+//! sampled, encoded, and each pair is checked: byte order of the keys must
+//! agree with a reference comparator on the values, and every key must decode
+//! back to its value. the [`Codec`] layouts are checked the same way.
+
+use crate::key::{Desc, Nested, NullsLast, Seq};
+use crate::schema::{Field, Schema};
+use crate::transcode::Codec;
+use crate::{from_key, to_key, IndexKey};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+
+/// splitmix64, deterministic per seed.
+#[derive(Clone, Debug)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// uniform in `0..n`, `n > 0`.
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Failure {
+    pub left: String,
+    pub right: String,
+    pub expected: Ordering,
+    pub actual: Ordering,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CaseReport {
+    pub type_name: &'static str,
+    pub samples: usize,
+    pub comparisons: usize,
+    pub roundtrip_failures: Vec<String>,
+    pub order_failures: Vec<Failure>,
+}
+
+impl CaseReport {
+    pub fn passed(&self) -> bool {
+        self.roundtrip_failures.is_empty() && self.order_failures.is_empty()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConformanceReport {
+    pub seed: u64,
+    pub cases: Vec<CaseReport>,
+}
+
+pub(crate) fn json_str(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+impl ConformanceReport {
+    pub fn passed(&self) -> bool {
+        self.cases.iter().all(CaseReport::passed)
+    }
+
+    pub fn to_json(&self) -> String {
+        let cases: Vec<String> = self
+            .cases
+            .iter()
+            .map(|case| {
+                let failures: Vec<String> = case
+                    .order_failures
+                    .iter()
+                    .map(|f| {
+                        format!(
+                            "{{\"left\":{},\"right\":{},\"expected\":\"{:?}\",\"actual\":\"{:?}\"}}",
+                            json_str(&f.left),
+                            json_str(&f.right),
+                            f.expected,
+                            f.actual
+                        )
+                    })
+                    .collect();
+                let roundtrip: Vec<String> =
+                    case.roundtrip_failures.iter().map(|v| json_str(v)).collect();
+                format!(
+                    "{{\"type\":{},\"samples\":{},\"comparisons\":{},\"passed\":{},\"roundtrip_failures\":[{}],\"order_failures\":[{}]}}",
+                    json_str(case.type_name),
+                    case.samples,
+                    case.comparisons,
+                    case.passed(),
+                    roundtrip.join(","),
+                    failures.join(",")
+                )
+            })
+            .collect();
+        format!(
+            "{{\"seed\":{},\"passed\":{},\"cases\":[{}]}}",
+            self.seed,
+            self.passed(),
+            cases.join(",")
+        )
+    }
+}
+
+/// check every pair of `values` against `cmp`.
+pub fn check<T: IndexKey + Clone + Debug>(
+    type_name: &'static str,
+    values: Vec<T>,
+    cmp: impl Fn(&T, &T) -> Ordering,
+) -> CaseReport {
+    let keys: Vec<Vec<u8>> = values.iter().cloned().map(to_key).collect();
+    let roundtrip_failures = values
+        .iter()
+        .zip(keys.iter())
+        .filter(|(value, key)| match from_key::<T>((*key).clone()) {
+            Ok(decoded) => cmp(&decoded, value) != Ordering::Equal,
+            Err(_) => true,
+        })
+        .map(|(value, _)| format!("{:?}", value))
+        .collect();
+    check_keys(type_name, &values, &keys, roundtrip_failures, cmp)
+}
+
+/// check every pair of `values`, re-encoded by `codec` with `schema`, against
+/// `cmp`. a value that fails to encode counts as a roundtrip failure and is
+/// left out of the pairs.
+pub fn check_codec<T: IndexKey + Clone + Debug>(
+    type_name: &'static str,
+    codec: Codec,
+    schema: &Schema,
+    values: Vec<T>,
+    cmp: impl Fn(&T, &T) -> Ordering,
+) -> CaseReport {
+    let samples = values.len();
+    let mut roundtrip_failures = vec![];
+    let mut encoded = vec![];
+    let mut keys = vec![];
+    for value in values {
+        let canonical = to_key(value.clone());
+        let decoded = codec.encode(&canonical, schema).and_then(|key| {
+            let back = from_key::<T>(codec.decode(&key, schema)?)?;
+            Ok((key, back))
+        });
+        match decoded {
+            Ok((key, back)) => {
+                if cmp(&back, &value) != Ordering::Equal {
+                    roundtrip_failures.push(format!("{:?}", value));
+                }
+                encoded.push(value);
+                keys.push(key);
+            }
+            Err(_) => roundtrip_failures.push(format!("{:?}", value)),
+        }
+    }
+    let mut report = check_keys(type_name, &encoded, &keys, roundtrip_failures, cmp);
+    report.samples = samples;
+    report
+}
+
+fn check_keys<T: Debug>(
+    type_name: &'static str,
+    values: &[T],
+    keys: &[Vec<u8>],
+    roundtrip_failures: Vec<String>,
+    cmp: impl Fn(&T, &T) -> Ordering,
+) -> CaseReport {
+    let mut report = CaseReport {
+        type_name,
+        samples: values.len(),
+        comparisons: 0,
+        roundtrip_failures,
+        order_failures: vec![],
+    };
+    for (i, a) in values.iter().enumerate() {
+        for (j, b) in values.iter().enumerate() {
+            report.comparisons += 1;
+            let expected = cmp(a, b);
+            let actual = keys[i].cmp(&keys[j]);
+            if expected != actual {
+                report.order_failures.push(Failure {
+                    left: format!("{:?}", a),
+                    right: format!("{:?}", b),
+                    expected,
+                    actual,
+                });
+            }
+        }
+    }
+    report
+}
+
+fn gen_bytes(rng: &mut Rng) -> Vec<u8> {
+    let len = rng.below(6);
+    (0..len)
+        .map(|_| match rng.below(4) {
+            0 => 0,
+            1 => 1,
+            2 => 255,
+            _ => rng.next_u64() as u8,
+        })
+        .collect()
+}
+
+fn gen_string(rng: &mut Rng) -> String {
+    const POOL: [char; 8] = ['\0', '\u{1}', 'a', 'b', 'é', '\u{7ff}', '中', '😃'];
+    let len = rng.below(5);
+    (0..len).map(|_| POOL[rng.below(8) as usize]).collect()
+}
+
+/// `None` a quarter of the time.
+fn gen_option<T>(rng: &mut Rng, gen: impl FnOnce(&mut Rng) -> T) -> Option<T> {
+    match rng.below(4) {
+        0 => None,
+        _ => Some(gen(rng)),
+    }
+}
+
+macro_rules! gen_int {
+    ($rng:ident, $t:ty) => {{
+        match $rng.below(4) {
+            0 => <$t>::MIN,
+            1 => <$t>::MAX,
+            2 => ($rng.below(5) as $t).wrapping_sub(2 as $t),
+            _ => (($rng.next_u64() as u128) << 64 | $rng.next_u64() as u128) as $t,
+        }
+    }};
+}
+
+macro_rules! int_cases {
+    ($rng:ident, $samples:ident, $cases:ident, $($t:ty),+) => {
+        $(
+            let values: Vec<$t> = (0..$samples).map(|_| gen_int!($rng, $t)).collect();
+            $cases.push(check(stringify!($t), values, |a, b| a.cmp(b)));
+        )+
+    };
+}
+
+/// edge values of a type: extremes, the neighbourhood of zero and whatever
+/// stresses its encoding. used by the order-matrix test and meant to be reused
+/// by wrappers built on these types.
+pub trait Boundaries: Sized {
+    fn boundaries() -> Vec<Self>;
+}
+
+pub fn boundaries<T: Boundaries>() -> Vec<T> {
+    T::boundaries()
+}
+
+macro_rules! impl_boundaries_u {
+    ($($t:ty),+) => {
+        $(
+            impl Boundaries for $t {
+                fn boundaries() -> Vec<$t> {
+                    vec![0, 1, 2, 0x7f, 0x80, 0xff, <$t>::MAX / 2, <$t>::MAX - 1, <$t>::MAX]
+                }
+            }
+        )+
+    };
+}
+
+macro_rules! impl_boundaries_i {
+    ($($t:ty),+) => {
+        $(
+            impl Boundaries for $t {
+                fn boundaries() -> Vec<$t> {
+                    vec![
+                        <$t>::MIN,
+                        <$t>::MIN + 1,
+                        -0x80,
+                        -2,
+                        -1,
+                        0,
+                        1,
+                        2,
+                        0x7f,
+                        <$t>::MAX - 1,
+                        <$t>::MAX,
+                    ]
+                }
+            }
+        )+
+    };
+}
+
+impl_boundaries_u!(u8, u16, u32, u64, u128);
+impl_boundaries_i!(i8, i16, i32, i64, i128);
+
+macro_rules! impl_boundaries_f {
+    ($($t:ident),+) => {
+        $(
+            impl Boundaries for $t {
+                fn boundaries() -> Vec<$t> {
+                    let subnormal = $t::from_bits(1);
+                    vec![
+                        -$t::NAN,
+                        $t::NEG_INFINITY,
+                        $t::MIN,
+                        -1.0,
+                        -$t::MIN_POSITIVE,
+                        -subnormal,
+                        -0.0,
+                        0.0,
+                        subnormal,
+                        $t::MIN_POSITIVE - subnormal,
+                        $t::MIN_POSITIVE,
+                        $t::EPSILON,
+                        1.0,
+                        1.0 + $t::EPSILON,
+                        $t::MAX,
+                        $t::INFINITY,
+                        $t::NAN,
+                    ]
+                }
+            }
+        )+
+    };
+}
+
+impl_boundaries_f!(f32, f64);
+
+impl Boundaries for bool {
+    fn boundaries() -> Vec<bool> {
+        vec![false, true]
+    }
+}
+
+impl Boundaries for Vec<u8> {
+    fn boundaries() -> Vec<Vec<u8>> {
+        vec![
+            vec![],
+            vec![0],
+            vec![0, 0],
+            vec![0, 1],
+            vec![1],
+            vec![1, 0],
+            vec![1, 1, 1],
+            vec![2],
+            vec![0xff],
+            vec![0xff, 0],
+            vec![0xff; 4],
+        ]
+    }
+}
+
+impl Boundaries for String {
+    fn boundaries() -> Vec<String> {
+        [
+            "",
+            "\0",
+            "\0\0",
+            "\0\u{1}",
+            "\u{1}",
+            "\u{1}\0",
+            "a",
+            "a\0",
+            "aa",
+            "\u{7f}",
+            "\u{80}",
+            "\u{7ff}",
+            "\u{800}",
+            "\u{ffff}",
+            "\u{10000}",
+            "\u{10ffff}",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+    }
+}
+
+impl<A: Boundaries + Clone, B: Boundaries + Clone> Boundaries for (A, B) {
+    /// every combination of the two fields' boundaries.
+    fn boundaries() -> Vec<(A, B)> {
+        let right = B::boundaries();
+        A::boundaries()
+            .into_iter()
+            .flat_map(|a| right.iter().map(move |b| (a.clone(), b.clone())))
+            .collect()
+    }
+}
+
+/// run every case with `samples` values each.
+pub fn run(seed: u64, samples: usize) -> ConformanceReport {
+    let mut rng = Rng::new(seed);
+    let mut cases = vec![];
+    int_cases!(rng, samples, cases, u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+    let values: Vec<f32> = (0..samples)
+        .map(|_| match rng.below(3) {
+            0 => f32::from_bits(rng.next_u64() as u32),
+            1 => (rng.below(9) as f32 - 4.0) / 2.0,
+            _ => [f32::INFINITY, f32::NEG_INFINITY, f32::MIN_POSITIVE, -0.0][rng.below(4) as usize],
+        })
+        .collect();
+    cases.push(check("f32", values, |a, b| a.total_cmp(b)));
+    let values: Vec<f64> = (0..samples)
+        .map(|_| match rng.below(3) {
+            0 => f64::from_bits(rng.next_u64()),
+            1 => (rng.below(9) as f64 - 4.0) / 2.0,
+            _ => [f64::INFINITY, f64::NEG_INFINITY, f64::MIN_POSITIVE, -0.0][rng.below(4) as usize],
+        })
+        .collect();
+    cases.push(check("f64", values, |a, b| a.total_cmp(b)));
+
+    let values: Vec<bool> = (0..samples).map(|_| rng.below(2) == 1).collect();
+    cases.push(check("bool", values, |a, b| a.cmp(b)));
+    let values: Vec<Vec<u8>> = (0..samples).map(|_| gen_bytes(&mut rng)).collect();
+    cases.push(check("Vec<u8>", values, |a, b| a.cmp(b)));
+    let values: Vec<String> = (0..samples).map(|_| gen_string(&mut rng)).collect();
+    cases.push(check("String", values, |a, b| a.cmp(b)));
+
+    let values: Vec<(Vec<u8>, u16)> = (0..samples)
+        .map(|_| (gen_bytes(&mut rng), gen_int!(rng, u16)))
+        .collect();
+    cases.push(check("(Vec<u8>, u16)", values, |a, b| a.cmp(b)));
+    let values: Vec<(String, i32, bool)> = (0..samples)
+        .map(|_| (gen_string(&mut rng), gen_int!(rng, i32), rng.below(2) == 1))
+        .collect();
+    cases.push(check("(String, i32, bool)", values, |a, b| a.cmp(b)));
+    let values: Vec<(f64, Vec<u8>, String)> = (0..samples)
+        .map(|_| {
+            (
+                (rng.below(5) as f64) - 2.0,
+                gen_bytes(&mut rng),
+                gen_string(&mut rng),
+            )
+        })
+        .collect();
+    cases.push(check("(f64, Vec<u8>, String)", values, |a, b| {
+        a.0.total_cmp(&b.0)
+            .then_with(|| a.1.cmp(&b.1))
+            .then_with(|| a.2.cmp(&b.2))
+    }));
+
+    let values: Vec<Option<String>> = (0..samples)
+        .map(|_| gen_option(&mut rng, gen_string))
+        .collect();
+    cases.push(check("Option<String>", values, |a, b| a.cmp(b)));
+    let values: Vec<NullsLast<i16>> = (0..samples)
+        .map(|_| NullsLast(gen_option(&mut rng, |r| gen_int!(r, i16))))
+        .collect();
+    cases.push(check("NullsLast<i16>", values, |a, b| {
+        (a.0.is_none(), a.0).cmp(&(b.0.is_none(), b.0))
+    }));
+    let values: Vec<Desc<String>> = (0..samples).map(|_| Desc(gen_string(&mut rng))).collect();
+    cases.push(check("Desc<String>", values, |a, b| b.0.cmp(&a.0)));
+    let values: Vec<(Desc<i32>, Vec<u8>)> = (0..samples)
+        .map(|_| (Desc(gen_int!(rng, i32)), gen_bytes(&mut rng)))
+        .collect();
+    cases.push(check("(Desc<i32>, Vec<u8>)", values, |a, b| {
+        b.0 .0.cmp(&a.0 .0).then_with(|| a.1.cmp(&b.1))
+    }));
+    let values: Vec<Reverse<(u8, String)>> = (0..samples)
+        .map(|_| Reverse((rng.below(3) as u8, gen_string(&mut rng))))
+        .collect();
+    cases.push(check("Reverse<(u8, String)>", values, |a, b| a.cmp(b)));
+    let values: Vec<(Nested<(String, u8)>, String)> = (0..samples)
+        .map(|_| {
+            (
+                Nested((gen_string(&mut rng), rng.below(3) as u8)),
+                gen_string(&mut rng),
+            )
+        })
+        .collect();
+    cases.push(check("(Nested<(String, u8)>, String)", values, |a, b| {
+        a.cmp(b)
+    }));
+    let values: Vec<Seq<String>> = (0..samples)
+        .map(|_| Seq((0..rng.below(4)).map(|_| gen_string(&mut rng)).collect()))
+        .collect();
+    cases.push(check("Seq<String>", values, |a, b| a.cmp(b)));
+    let values: Vec<BTreeSet<u16>> = (0..samples)
+        .map(|_| (0..rng.below(4)).map(|_| rng.below(8) as u16).collect())
+        .collect();
+    cases.push(check("BTreeSet<u16>", values, |a, b| a.cmp(b)));
+
+    let schema = Schema::new(&[
+        ("id", Field::U32),
+        ("delta", Field::I64),
+        ("name", Field::String),
+    ])
+    .expect("valid schema");
+    let values: Vec<(u32, i64, String)> = (0..samples)
+        .map(|_| {
+            (
+                rng.below(3) as u32,
+                gen_int!(rng, i64),
+                gen_string(&mut rng),
+            )
+        })
+        .collect();
+    for (name, codec) in [
+        ("Escaped (u32, i64, String)", Codec::Escaped),
+        ("DoubleEnded (u32, i64, String)", Codec::DoubleEnded),
+        ("Memcomparable (u32, i64, String)", Codec::Memcomparable),
+        ("Tuple (u32, i64, String)", Codec::Tuple),
+    ] {
+        cases.push(check_codec(name, codec, &schema, values.clone(), |a, b| {
+            a.cmp(b)
+        }));
+    }
+
+    ConformanceReport { seed, cases }
+}
+
+#[test]
+fn test_conformance() {
+    let report = run(0x1d3a, 48);
+    assert!(report.passed(), "{}", report.to_json());
+    assert_eq!(report.cases.len(), 30);
+    assert!(report
+        .to_json()
+        .starts_with("{\"seed\":7482,\"passed\":true,"));
+
+    let broken = check("u8", vec![1u8, 2], |a, b| b.cmp(a));
+    assert!(!broken.passed());
+    assert_eq!(broken.order_failures.len(), 2);
+
+    // a byte string before a fixed field has no double-ended layout.
+    let schema = Schema::new(&[("name", Field::String), ("id", Field::U8)]).unwrap();
+    let values = vec![("a".to_owned(), 1u8), ("b".to_owned(), 0)];
+    let broken = check_codec(
+        "DoubleEnded",
+        Codec::DoubleEnded,
+        &schema,
+        values,
+        |a, b| a.cmp(b),
+    );
+    assert_eq!(broken.samples, 2);
+    assert_eq!(broken.roundtrip_failures.len(), 2);
+    assert_eq!(broken.comparisons, 0);
+}
+
+#[test]
+fn test_boundaries() {
+    macro_rules! matrix {
+        ($($t:ty),+) => {
+            $(
+                let report = check(stringify!($t), boundaries::<$t>(), |a, b| a.cmp(b));
+                assert!(report.passed(), "{:?}", report);
+            )+
+        };
+    }
+    matrix!(
+        u8,
+        u16,
+        u32,
+        u64,
+        u128,
+        i8,
+        i16,
+        i32,
+        i64,
+        i128,
+        bool,
+        Vec<u8>,
+        String
+    );
+    matrix!((u8, bool), (Vec<u8>, u16), (String, Vec<u8>), (i64, String));
+
+    let report = check("f32", boundaries::<f32>(), |a, b| a.total_cmp(b));
+    assert!(report.passed(), "{:?}", report);
+    let report = check("f64", boundaries::<f64>(), |a, b| a.total_cmp(b));
+    assert!(report.passed(), "{:?}", report);
+    let report = check("(f64, String)", boundaries::<(f64, String)>(), |a, b| {
+        a.0.total_cmp(&b.0).then_with(|| a.1.cmp(&b.1))
+    });
+    assert!(report.passed(), "{:?}", report);
+    assert_eq!(boundaries::<(u8, bool)>().len(), 18);
+
+    let values: Vec<Desc<String>> = boundaries::<String>().into_iter().map(Desc).collect();
+    let report = check("Desc<String>", values, |a, b| b.0.cmp(&a.0));
+    assert!(report.passed(), "{:?}", report);
+    let values: Vec<(Option<Vec<u8>>, Nested<i8>)> = boundaries::<(Vec<u8>, i8)>()
+        .into_iter()
+        .flat_map(|(bytes, v)| vec![(None, Nested(v)), (Some(bytes), Nested(v))])
+        .collect();
+    let report = check("(Option<Vec<u8>>, Nested<i8>)", values, |a, b| a.cmp(b));
+    assert!(report.passed(), "{:?}", report);
+    let values: Vec<Seq<u16>> = boundaries::<(u16, u16)>()
+        .into_iter()
+        .map(|(a, b)| Seq(vec![a, b]))
+        .chain(boundaries::<u16>().into_iter().map(|a| Seq(vec![a])))
+        .collect();
+    let report = check("Seq<u16>", values, |a, b| a.cmp(b));
+    assert!(report.passed(), "{:?}", report);
+}
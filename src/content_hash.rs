@@ -0,0 +1,91 @@
+//! content-hash keys.
+
+#[cfg(feature = "digest")]
+use crate::digest::sha256;
+use crate::range::KeyRange;
+use crate::IndexKey;
+use std::io::{Error, Read, Write};
+
+/// a content address (blake3, sha-256, ...), keyed by its raw bytes so hashes
+/// spread evenly and the leading bits double as a shard number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ContentHash<const N: usize>(pub [u8; N]);
+
+#[cfg(feature = "digest")]
+impl ContentHash<32> {
+    pub fn sha256(data: &[u8]) -> ContentHash<32> {
+        ContentHash(sha256(data))
+    }
+}
+
+impl<const N: usize> ContentHash<N> {
+    /// the leading `bits` bits (at most 32) as a shard number.
+    pub fn shard_prefix(&self, bits: u32) -> u32 {
+        assert!(bits <= 32 && bits as usize <= N * 8);
+        let mut head = [0u8; 4];
+        let len = N.min(4);
+        head[..len].copy_from_slice(&self.0[..len]);
+        u32::from_be_bytes(head).checked_shr(32 - bits).unwrap_or(0)
+    }
+
+    /// every hash whose leading `bits` bits equal `shard`, which must be below
+    /// `1 << bits`.
+    pub fn shard_range(shard: u32, bits: u32) -> KeyRange {
+        assert!(bits <= 32 && bits as usize <= N * 8);
+        assert!((shard as u64) < 1u64 << bits, "shard out of range");
+        let bytes = (bits as usize).div_ceil(8);
+        let start = |s: u64| (s << (32 - bits)) as u32;
+        let prefix = |s: u64| start(s).to_be_bytes()[..bytes].to_vec();
+        let next = shard as u64 + 1;
+        KeyRange {
+            start: prefix(shard as u64),
+            end: if next < 1u64 << bits {
+                Some(prefix(next))
+            } else {
+                None
+            },
+        }
+    }
+}
+
+impl<const N: usize> IndexKey for ContentHash<N> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        result.write_all(&self.0)?;
+        Ok(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let mut bytes = [0; N];
+        key.read_exact(&mut bytes)?;
+        Ok(ContentHash(bytes))
+    }
+}
+
+#[test]
+fn test_content_hash() {
+    use crate::{from_key, to_key};
+    let mut bytes = [0u8; 32];
+    bytes[..4].copy_from_slice(&[0xba, 0x78, 0x16, 0xbf]);
+    let hash = ContentHash(bytes);
+    #[cfg(feature = "digest")]
+    assert_eq!(ContentHash::sha256(b"abc").0[..4], bytes[..4]);
+    assert_eq!(to_key(hash), hash.0.to_vec());
+    assert_eq!(from_key::<ContentHash<32>>(to_key(hash)).unwrap(), hash);
+
+    assert_eq!(hash.shard_prefix(0), 0);
+    assert_eq!(hash.shard_prefix(4), 0xb);
+    assert_eq!(hash.shard_prefix(12), 0xba7);
+    assert_eq!(hash.shard_prefix(32), 0xba7816bf);
+    assert_eq!(ContentHash([0xffu8; 2]).shard_prefix(16), 0xffff);
+
+    for bits in [0u32, 3, 8, 12] {
+        let shard = hash.shard_prefix(bits);
+        assert!(ContentHash::<32>::shard_range(shard, bits).contains(&to_key(hash)));
+        if shard > 0 {
+            assert!(!ContentHash::<32>::shard_range(shard - 1, bits).contains(&to_key(hash)));
+        }
+    }
+    assert_eq!(ContentHash::<32>::shard_range(0, 0), KeyRange::all());
+    assert_eq!(ContentHash::<32>::shard_range(15, 4).end, None);
+    assert!(std::panic::catch_unwind(|| ContentHash::<32>::shard_range(16, 4)).is_err());
+    assert!(std::panic::catch_unwind(|| ContentHash::<32>::shard_range(1, 0)).is_err());
+}
@@ -0,0 +1,187 @@
+//! dotted-version keys for crdt operation logs.
+//!
+//! an operation is identified by its [`Dot`], keyed `(actor, counter)`: each
+//! actor's operations sit together in the order the actor made them, so
+//! catching up with an actor is one range scan from the last counter seen
+//! ([`Dot::after`]). a [`VersionVector`] records the highest counter seen per
+//! actor, and [`VersionVector::missing`] turns two vectors into exactly the
+//! scans a merge has to do.
+//!
+//! a version vector encodes as its `(actor, counter)` pairs in actor order,
+//! so equal vectors have equal keys. key order between vectors is
+//! lexicographic, not causal; compare causality with
+//! [`VersionVector::dominates`].
+
+use crate::range::{prefix_successor, KeyRange};
+use crate::{to_key, IndexKey, Seq};
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind, Read, Write};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Dot {
+    pub actor: u64,
+    pub counter: u64,
+}
+
+impl Dot {
+    pub fn new(actor: u64, counter: u64) -> Dot {
+        Dot { actor, counter }
+    }
+
+    /// the operations of `actor` with a counter above `counter`.
+    pub fn after(actor: u64, counter: u64) -> KeyRange {
+        let end = prefix_successor(&to_key(actor));
+        match counter.checked_add(1) {
+            Some(next) => KeyRange {
+                start: to_key(Dot::new(actor, next)),
+                end,
+            },
+            None => KeyRange::new(vec![], vec![]),
+        }
+    }
+}
+
+impl IndexKey for Dot {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        (self.actor, self.counter).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let (actor, counter) = <(u64, u64)>::from_key(key)?;
+        Ok(Dot { actor, counter })
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VersionVector {
+    /// actors never seen are absent rather than 0.
+    seen: BTreeMap<u64, u64>,
+}
+
+impl VersionVector {
+    pub fn new() -> VersionVector {
+        VersionVector::default()
+    }
+
+    /// the highest counter seen from `actor`, 0 if none.
+    pub fn get(&self, actor: u64) -> u64 {
+        self.seen.get(&actor).copied().unwrap_or(0)
+    }
+
+    pub fn contains(&self, dot: Dot) -> bool {
+        dot.counter <= self.get(dot.actor)
+    }
+
+    /// a counter of 0 means nothing seen, so it leaves the vector unchanged.
+    pub fn observe(&mut self, dot: Dot) {
+        if dot.counter == 0 {
+            return;
+        }
+        let counter = self.seen.entry(dot.actor).or_insert(0);
+        *counter = (*counter).max(dot.counter);
+    }
+
+    /// the next dot for `actor`, recorded as seen. an error once the
+    /// actor's counter is at `u64::MAX`.
+    pub fn increment(&mut self, actor: u64) -> Result<Dot, Error> {
+        let counter = self
+            .get(actor)
+            .checked_add(1)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "counter overflow"))?;
+        let dot = Dot::new(actor, counter);
+        self.observe(dot);
+        Ok(dot)
+    }
+
+    pub fn merge(&mut self, other: &VersionVector) {
+        for (actor, counter) in &other.seen {
+            self.observe(Dot::new(*actor, *counter));
+        }
+    }
+
+    /// whether every operation seen by `other` was seen by `self`.
+    pub fn dominates(&self, other: &VersionVector) -> bool {
+        other.seen.iter().all(|(a, c)| *c <= self.get(*a))
+    }
+
+    /// the log ranges holding operations `other` has seen and `self` has
+    /// not, one per actor, in actor order.
+    pub fn missing(&self, other: &VersionVector) -> Vec<KeyRange> {
+        other
+            .seen
+            .iter()
+            .filter(|(a, c)| **c > self.get(**a))
+            .map(|(a, _)| Dot::after(*a, self.get(*a)))
+            .collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Dot> + '_ {
+        self.seen.iter().map(|(a, c)| Dot::new(*a, *c))
+    }
+}
+
+impl IndexKey for VersionVector {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        Seq(self.iter().collect()).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let dots = Seq::<Dot>::from_key(key)?.0;
+        if !dots.windows(2).all(|w| w[0].actor < w[1].actor) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "version vector actors out of order",
+            ));
+        }
+        if dots.iter().any(|d| d.counter == 0) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "zero counter in version vector",
+            ));
+        }
+        Ok(VersionVector {
+            seen: dots.into_iter().map(|d| (d.actor, d.counter)).collect(),
+        })
+    }
+}
+
+#[test]
+fn test_dot_version_vector() {
+    use crate::from_key;
+    let mut log: Vec<Vec<u8>> = vec![];
+    let mut a = VersionVector::new();
+    let mut b = VersionVector::new();
+    for _ in 0..3 {
+        log.push(to_key(a.increment(1).unwrap()));
+    }
+    for _ in 0..2 {
+        log.push(to_key(b.increment(2).unwrap()));
+    }
+    b.observe(Dot::new(1, 1));
+    log.sort();
+
+    let missing = b.missing(&a);
+    assert_eq!(missing.len(), 1);
+    let fetched: Vec<Dot> = log
+        .iter()
+        .filter(|k| missing[0].contains(k))
+        .map(|k| from_key(k.clone()).unwrap())
+        .collect();
+    assert_eq!(fetched, vec![Dot::new(1, 2), Dot::new(1, 3)]);
+    assert!(!b.dominates(&a) && !a.dominates(&b));
+
+    b.merge(&a);
+    assert!(b.dominates(&a) && b.missing(&a).is_empty());
+    assert!(b.contains(Dot::new(2, 2)) && !b.contains(Dot::new(2, 3)));
+    assert_eq!(from_key::<VersionVector>(to_key(b.clone())).unwrap(), b);
+    assert!(Dot::after(1, u64::MAX).is_empty());
+    assert!(from_key::<VersionVector>(to_key(Seq(vec![Dot::new(2, 1), Dot::new(1, 1)]))).is_err());
+
+    // observing counter 0 records nothing, so the key matches the empty vector.
+    let mut c = VersionVector::new();
+    c.observe(Dot::new(5, 0));
+    assert_eq!(c, VersionVector::new());
+    assert_eq!(to_key(c.clone()), to_key(VersionVector::new()));
+    assert!(from_key::<VersionVector>(to_key(Seq(vec![Dot::new(5, 0)]))).is_err());
+    c.observe(Dot::new(5, u64::MAX));
+    assert!(c.increment(5).is_err());
+    assert_eq!(c.get(5), u64::MAX);
+}
@@ -0,0 +1,120 @@
+//! order-sensitive digests over key streams.
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+#[test]
+fn test_sha256() {
+    fn hex(v: &[u8]) -> String {
+        v.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+    assert_eq!(
+        hex(&sha256(b"")),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+    assert_eq!(
+        hex(&sha256(b"abc")),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    assert_eq!(
+        hex(&sha256(
+            b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+        )),
+        "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+    );
+}
+
+/// HMAC-SHA-256 (RFC 2104) of `data` under `key`.
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac takes keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+#[test]
+fn test_hmac_sha256() {
+    fn hex(v: &[u8]) -> String {
+        v.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+    // rfc 4231 test cases 2 and 6.
+    assert_eq!(
+        hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+    assert_eq!(
+        hex(&hmac_sha256(
+            &[0xaa; 131],
+            b"Test Using Larger Than Block-Size Key - Hash Key First"
+        )),
+        "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+    );
+}
+
+/// incremental form of [`key_digest`]: each key is chained into the running
+/// state as `sha256(state || len || key)`, so both content and order matter.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DigestBuilder {
+    state: [u8; 32],
+    count: u64,
+}
+
+impl DigestBuilder {
+    pub fn new() -> DigestBuilder {
+        DigestBuilder::default()
+    }
+
+    pub fn push(&mut self, key: &[u8]) -> &mut DigestBuilder {
+        self.state = Sha256::new()
+            .chain_update(self.state)
+            .chain_update((key.len() as u64).to_be_bytes())
+            .chain_update(key)
+            .finalize()
+            .into();
+        self.count += 1;
+        self
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn finish(&self) -> [u8; 32] {
+        self.state
+    }
+}
+
+pub fn key_digest<K: AsRef<[u8]>, I: IntoIterator<Item = K>>(iter: I) -> [u8; 32] {
+    let mut builder = DigestBuilder::new();
+    for key in iter {
+        builder.push(key.as_ref());
+    }
+    builder.finish()
+}
+
+#[test]
+fn test_key_digest() {
+    use crate::to_key;
+    let keys: Vec<Vec<u8>> = (0u32..10).map(to_key).collect();
+    let digest = key_digest(&keys);
+    assert_eq!(digest, key_digest(keys.clone()));
+
+    let mut builder = DigestBuilder::new();
+    keys.iter().for_each(|k| {
+        builder.push(k);
+    });
+    assert_eq!(builder.finish(), digest);
+    assert_eq!(builder.count(), 10);
+
+    let mut swapped = keys.clone();
+    swapped.swap(0, 1);
+    assert_ne!(key_digest(&swapped), digest);
+    assert_ne!(key_digest(&keys[..9]), digest);
+    assert_ne!(
+        key_digest(vec![vec![1u8], vec![2]]),
+        key_digest(vec![vec![1u8, 2]])
+    );
+}
@@ -0,0 +1,314 @@
+//! the byte layout a key type produces.
+//!
+//! [`explain`] lists the segments of a type's key in order: where each field
+//! sits, how wide it is, and how it is encoded, so a composite key can be
+//! reviewed before it is shipped. nested fields are named by their path, such
+//! as `1.0` for the first element of a tuple's second element.
+
+use crate::schema::{Field, Schema};
+use crate::{Desc, Nested, NullsFirst, NullsLast, Seq};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Width {
+    Fixed(usize),
+    Variable,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Segment {
+    pub path: String,
+    pub type_name: String,
+    pub width: Width,
+    pub encoding: String,
+    /// absent or repeated depending on the value, as for an `Option`
+    /// payload or `Seq` elements; later offsets then vary.
+    pub conditional: bool,
+}
+
+pub trait Explain {
+    fn explain_into(path: &str, out: &mut Vec<Segment>);
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Explanation {
+    pub segments: Vec<Segment>,
+}
+
+impl Explanation {
+    /// the key length when every segment is fixed-width.
+    pub fn fixed_len(&self) -> Option<usize> {
+        self.segments
+            .iter()
+            .map(|s| match s.width {
+                Width::Fixed(n) if !s.conditional => Some(n),
+                _ => None,
+            })
+            .sum()
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut offset = Some(0);
+        for segment in &self.segments {
+            let at = match offset {
+                Some(offset) => offset.to_string(),
+                None => "?".to_owned(),
+            };
+            let width = match segment.width {
+                Width::Fixed(n) => format!("{} bytes", n),
+                Width::Variable => "variable".to_owned(),
+            };
+            writeln!(
+                f,
+                "{:>4}  {:<8} {:<10} {:<10} {}",
+                at, segment.path, segment.type_name, width, segment.encoding
+            )?;
+            offset = match (offset, segment.width) {
+                (Some(offset), Width::Fixed(n)) if !segment.conditional => Some(offset + n),
+                _ => None,
+            };
+        }
+        Ok(())
+    }
+}
+
+pub fn explain<T: Explain>() -> Explanation {
+    let mut segments = vec![];
+    T::explain_into("", &mut segments);
+    Explanation { segments }
+}
+
+/// the layout of keys of `schema`, with fields named as in it. types declared
+/// with [`key_struct!`](crate::key_struct) explain as their schema.
+pub fn explain_schema(schema: &Schema) -> Explanation {
+    let mut segments = vec![];
+    explain_schema_into("", schema, &mut segments);
+    Explanation { segments }
+}
+
+pub fn explain_schema_into(path: &str, schema: &Schema, out: &mut Vec<Segment>) {
+    for (name, field) in schema.fields() {
+        out.push(field_segment(&join(path, name), *field));
+    }
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+const ESCAPED: &str = "escaped, 0 as 1 0 and 1 as 1 1, terminated by 0";
+
+fn field_segment(path: &str, field: Field) -> Segment {
+    let (width, encoding) = match field {
+        Field::Bytes | Field::String => (Width::Variable, ESCAPED),
+        Field::Bool => (Width::Fixed(1), "0 or 1"),
+        Field::U8 | Field::U16 | Field::U32 | Field::U64 | Field::U128 => {
+            (Width::Fixed(field.width()), "big-endian")
+        }
+        Field::I8 | Field::I16 | Field::I32 | Field::I64 | Field::I128 => {
+            (Width::Fixed(field.width()), "big-endian, sign bit flipped")
+        }
+        Field::F32 | Field::F64 => (
+            Width::Fixed(field.width()),
+            "big-endian, sign bit set if positive, all bits flipped if negative",
+        ),
+    };
+    Segment {
+        path: path.to_owned(),
+        type_name: field.type_name().to_owned(),
+        width,
+        encoding: encoding.to_owned(),
+        conditional: false,
+    }
+}
+
+macro_rules! explain_field {
+    ($($t:ty => $field:ident),+) => {
+        $(
+            impl Explain for $t {
+                fn explain_into(path: &str, out: &mut Vec<Segment>) {
+                    out.push(field_segment(path, Field::$field));
+                }
+            }
+        )+
+    };
+}
+
+explain_field!(
+    Vec<u8> => Bytes, String => String, bool => Bool,
+    u8 => U8, u16 => U16, u32 => U32, u64 => U64, u128 => U128,
+    i8 => I8, i16 => I16, i32 => I32, i64 => I64, i128 => I128,
+    f32 => F32, f64 => F64
+);
+
+impl Explain for char {
+    fn explain_into(path: &str, out: &mut Vec<Segment>) {
+        out.push(Segment {
+            path: path.to_owned(),
+            type_name: "char".to_owned(),
+            width: Width::Variable,
+            encoding: "utf-8, 1 to 4 bytes, unterminated".to_owned(),
+            conditional: false,
+        });
+    }
+}
+
+impl Explain for () {
+    fn explain_into(_: &str, _: &mut Vec<Segment>) {}
+}
+
+fn tag(path: &str, type_name: &str, encoding: &str) -> Segment {
+    Segment {
+        path: path.to_owned(),
+        type_name: type_name.to_owned(),
+        width: Width::Fixed(1),
+        encoding: encoding.to_owned(),
+        conditional: false,
+    }
+}
+
+/// the inner segments are only present for `Some`.
+fn explain_option<T: Explain>(path: &str, type_name: &str, tags: &str, out: &mut Vec<Segment>) {
+    out.push(tag(path, type_name, tags));
+    let start = out.len();
+    T::explain_into(&join(path, "some"), out);
+    for segment in &mut out[start..] {
+        segment.encoding += ", only if present";
+        segment.conditional = true;
+    }
+}
+
+impl<T: Explain> Explain for Option<T> {
+    fn explain_into(path: &str, out: &mut Vec<Segment>) {
+        explain_option::<T>(path, "Option", "0 for None, 1 for Some", out);
+    }
+}
+
+impl<T: Explain> Explain for NullsFirst<T> {
+    fn explain_into(path: &str, out: &mut Vec<Segment>) {
+        explain_option::<T>(path, "NullsFirst", "0 for None, 1 for Some", out);
+    }
+}
+
+impl<T: Explain> Explain for NullsLast<T> {
+    fn explain_into(path: &str, out: &mut Vec<Segment>) {
+        explain_option::<T>(path, "NullsLast", "0 for Some, 1 for None", out);
+    }
+}
+
+impl<T: Explain> Explain for Desc<T> {
+    fn explain_into(path: &str, out: &mut Vec<Segment>) {
+        let start = out.len();
+        T::explain_into(path, out);
+        for segment in &mut out[start..] {
+            segment.encoding += ", every bit inverted";
+        }
+    }
+}
+
+impl<T: Explain> Explain for std::cmp::Reverse<T> {
+    fn explain_into(path: &str, out: &mut Vec<Segment>) {
+        Desc::<T>::explain_into(path, out)
+    }
+}
+
+impl<T: Explain> Explain for Nested<T> {
+    fn explain_into(path: &str, out: &mut Vec<Segment>) {
+        let mut inner = vec![];
+        T::explain_into(path, &mut inner);
+        let inner: Vec<_> = inner.iter().map(|s| s.type_name.as_str()).collect();
+        out.push(Segment {
+            path: path.to_owned(),
+            type_name: format!("Nested({})", inner.join(", ")),
+            width: Width::Variable,
+            encoding: format!("inner key {}", ESCAPED),
+            conditional: false,
+        });
+    }
+}
+
+impl<T: Explain> Explain for Seq<T> {
+    fn explain_into(path: &str, out: &mut Vec<Segment>) {
+        out.push(tag(path, "Seq", "1 before each element, 0 after the last"));
+        out.last_mut().unwrap().conditional = true;
+        let start = out.len();
+        T::explain_into(&join(path, "[]"), out);
+        for segment in &mut out[start..] {
+            segment.encoding += ", repeated";
+            segment.conditional = true;
+        }
+    }
+}
+
+impl<T: Explain, const N: usize> Explain for [T; N] {
+    fn explain_into(path: &str, out: &mut Vec<Segment>) {
+        for i in 0..N {
+            T::explain_into(&join(path, &i.to_string()), out);
+        }
+    }
+}
+
+macro_rules! explain_tuple {
+    ($($t:ident $i:tt),+) => {
+        impl<$($t: Explain),+> Explain for ($($t,)+) {
+            fn explain_into(path: &str, out: &mut Vec<Segment>) {
+                $($t::explain_into(&join(path, stringify!($i)), out);)+
+            }
+        }
+    };
+}
+
+explain_tuple!(T1 0, T2 1);
+explain_tuple!(T1 0, T2 1, T3 2);
+explain_tuple!(T1 0, T2 1, T3 2, T4 3);
+explain_tuple!(T1 0, T2 1, T3 2, T4 3, T5 4);
+explain_tuple!(T1 0, T2 1, T3 2, T4 3, T5 4, T6 5);
+explain_tuple!(T1 0, T2 1, T3 2, T4 3, T5 4, T6 5, T7 6);
+explain_tuple!(T1 0, T2 1, T3 2, T4 3, T5 4, T6 5, T7 6, T8 7);
+
+#[test]
+fn test_explain() {
+    let plan = explain::<(u64, Option<String>, Desc<i16>)>();
+    let paths: Vec<&str> = plan.segments.iter().map(|s| s.path.as_str()).collect();
+    assert_eq!(paths, vec!["0", "1", "1.some", "2"]);
+    assert_eq!(plan.segments[0].width, Width::Fixed(8));
+    assert_eq!(plan.segments[1].width, Width::Fixed(1));
+    assert_eq!(plan.segments[2].width, Width::Variable);
+    assert!(plan.segments[2].encoding.ends_with("only if present"));
+    assert!(plan.segments[3].encoding.ends_with("every bit inverted"));
+    assert_eq!(plan.fixed_len(), None);
+    let text = plan.to_string();
+    assert_eq!(text.lines().count(), 4);
+    assert!(text.lines().nth(2).unwrap().trim_start().starts_with("9 "));
+    assert!(text.lines().nth(3).unwrap().trim_start().starts_with("? "));
+
+    assert_eq!(explain::<([u8; 16], u32)>().fixed_len(), Some(20));
+    assert_eq!(explain::<((u8, u8), ())>().segments.len(), 2);
+    assert_eq!(explain::<((u8, u8), u8)>().segments[1].path, "0.1");
+    let nested = explain::<(Nested<(u8, String)>, Seq<u16>)>();
+    assert_eq!(nested.segments[0].type_name, "Nested(u8, String)");
+    assert_eq!(nested.segments[2].path, "1.[]");
+    assert_eq!(explain::<Option<u64>>().fixed_len(), None);
+    assert_eq!(explain::<Seq<u8>>().fixed_len(), None);
+
+    let schema = Schema::new(&[("tenant", Field::U32), ("name", Field::String)]).unwrap();
+    let plan = explain_schema(&schema);
+    assert_eq!(plan.segments[1].path, "name");
+    assert_eq!(plan.segments[1].type_name, "String");
+
+    struct User {
+        tenant: u32,
+        name: String,
+    }
+    crate::key_struct!(User {
+        tenant: U32,
+        name: String
+    });
+    assert_eq!(explain::<(User, u8)>().segments[1].path, "0.name");
+}
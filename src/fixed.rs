@@ -0,0 +1,113 @@
+//! escape-free codec for schemas made only of fixed-width fields.
+//!
+//! only types implementing [`FixedKey`] — fixed-width scalars and tuples of
+//! them — can use it, so choosing it is checked at compile time. keys are
+//! exactly `T::WIDTH` bytes and field `i` always sits at
+//! `T::OFFSETS[i]..T::OFFSETS[i + 1]`.
+
+use crate::codec::FixedWidth;
+use std::io::{Error, ErrorKind};
+
+pub trait FixedKey: Sized {
+    /// encoded size in bytes.
+    const WIDTH: usize;
+    /// start of every field, then `WIDTH`.
+    const OFFSETS: &'static [usize];
+    /// `out` is exactly `WIDTH` bytes.
+    fn write_fixed(self, out: &mut [u8]);
+    /// `bytes` is exactly `WIDTH` bytes.
+    fn read_fixed(bytes: &[u8]) -> Self;
+}
+
+impl<T: FixedWidth> FixedKey for T {
+    const WIDTH: usize = std::mem::size_of::<T::Bytes>();
+    const OFFSETS: &'static [usize] = &[0, Self::WIDTH];
+    fn write_fixed(self, out: &mut [u8]) {
+        out.copy_from_slice(self.encode().as_ref());
+    }
+    fn read_fixed(bytes: &[u8]) -> T {
+        let mut raw = T::Bytes::default();
+        raw.as_mut().copy_from_slice(bytes);
+        T::decode(raw)
+    }
+}
+
+/// running sums of the field widths, starting at 0.
+macro_rules! offsets {
+    ([$($done:expr),*] [$at:expr]) => { &[$($done),*] };
+    ([$($done:expr),*] [$at:expr] $head:ident $($tail:ident)*) => {
+        offsets!([$($done,)* $at + $head::WIDTH] [$at + $head::WIDTH] $($tail)*)
+    };
+}
+
+macro_rules! impl_fixed_tuple {
+    ( $( $v:ident $i:tt ),+ ) => {
+        impl< $( $v: FixedKey ),+ > FixedKey for ( $( $v, )+ ) {
+            const WIDTH: usize = 0 $( + $v::WIDTH )+;
+            const OFFSETS: &'static [usize] = offsets!([0] [0] $( $v )+);
+            fn write_fixed(self, out: &mut [u8]) {
+                $(
+                    self.$i.write_fixed(&mut out[Self::OFFSETS[$i]..Self::OFFSETS[$i + 1]]);
+                )+
+            }
+            fn read_fixed(bytes: &[u8]) -> Self {
+                ( $(
+                    $v::read_fixed(&bytes[Self::OFFSETS[$i]..Self::OFFSETS[$i + 1]]),
+                )+ )
+            }
+        }
+    };
+}
+
+impl_fixed_tuple!(T1 0);
+impl_fixed_tuple!(T1 0, T2 1);
+impl_fixed_tuple!(T1 0, T2 1, T3 2);
+impl_fixed_tuple!(T1 0, T2 1, T3 2, T4 3);
+impl_fixed_tuple!(T1 0, T2 1, T3 2, T4 3, T5 4);
+impl_fixed_tuple!(T1 0, T2 1, T3 2, T4 3, T5 4, T6 5);
+impl_fixed_tuple!(T1 0, T2 1, T3 2, T4 3, T5 4, T6 5, T7 6);
+impl_fixed_tuple!(T1 0, T2 1, T3 2, T4 3, T5 4, T6 5, T7 6, T8 7);
+
+pub fn to_fixed_key<T: FixedKey>(value: T) -> Vec<u8> {
+    let mut key = vec![0; T::WIDTH];
+    value.write_fixed(&mut key);
+    key
+}
+
+/// `key` must be exactly `T::WIDTH` bytes.
+pub fn from_fixed_key<T: FixedKey>(key: &[u8]) -> Result<T, Error> {
+    if key.len() != T::WIDTH {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "fixed key has wrong length",
+        ));
+    }
+    Ok(T::read_fixed(key))
+}
+
+/// the encoded bytes of field `i`, without decoding the rest.
+pub fn fixed_field<T: FixedKey>(key: &[u8], i: usize) -> &[u8] {
+    &key[T::OFFSETS[i]..T::OFFSETS[i + 1]]
+}
+
+#[test]
+fn test_fixed_key() {
+    use crate::odd_int::U24;
+    use crate::to_key;
+    type Row = (u32, i8, f64, bool, U24);
+    assert_eq!(Row::WIDTH, 4 + 1 + 8 + 1 + 3);
+    assert_eq!(Row::OFFSETS, &[0, 4, 5, 13, 14, 17]);
+    assert_eq!(<u16>::OFFSETS, &[0, 2]);
+
+    let row: Row = (7, -1, 2.5, true, U24::MAX);
+    let key = to_fixed_key(row);
+    assert_eq!(key, to_key(row));
+    assert_eq!(from_fixed_key::<Row>(&key).unwrap(), row);
+    assert_eq!(fixed_field::<Row>(&key, 1), &[0x7f]);
+    assert_eq!(fixed_field::<Row>(&key, 4), &[0xff; 3]);
+    assert!(from_fixed_key::<Row>(&key[1..]).is_err());
+
+    let nested: ((u8, u16), u8) = ((1, 2), 3);
+    assert_eq!(to_fixed_key(nested), vec![1, 0, 2, 3]);
+    assert!(to_fixed_key((1u8, -1i32)) < to_fixed_key((1u8, 0i32)));
+}
@@ -0,0 +1,69 @@
+//! feature-flag configuration keys.
+//!
+//! rules are keyed `(flag, env, segment)`, so everything about one flag is
+//! contiguous, and within it everything for one environment.
+
+use crate::range::KeyRange;
+use crate::{to_key, IndexKey};
+use std::io::{Error, Read, Write};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FlagKey {
+    pub flag: String,
+    pub env: String,
+    pub segment: String,
+}
+
+impl FlagKey {
+    pub fn new(flag: &str, env: &str, segment: &str) -> FlagKey {
+        FlagKey {
+            flag: flag.to_owned(),
+            env: env.to_owned(),
+            segment: segment.to_owned(),
+        }
+    }
+
+    /// every environment and segment of `flag`.
+    pub fn flag_range(flag: &str) -> KeyRange {
+        KeyRange::prefix(to_key(flag.to_owned()))
+    }
+
+    /// every segment of `flag` in `env`.
+    pub fn env_range(flag: &str, env: &str) -> KeyRange {
+        KeyRange::prefix(to_key((flag.to_owned(), env.to_owned())))
+    }
+}
+
+impl IndexKey for FlagKey {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        (self.flag, self.env, self.segment).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let (flag, env, segment) = <(String, String, String)>::from_key(key)?;
+        Ok(FlagKey { flag, env, segment })
+    }
+}
+
+#[test]
+fn test_flag_key() {
+    use crate::from_key;
+    let keys: Vec<_> = [
+        FlagKey::new("dark-mode", "prod", "beta"),
+        FlagKey::new("dark-mode", "prod", "all"),
+        FlagKey::new("dark-mode", "prod-eu", "all"),
+        FlagKey::new("dark-mode", "dev", "all"),
+        FlagKey::new("dark-mode-v2", "prod", "all"),
+    ]
+    .iter()
+    .map(|k| to_key(k.clone()))
+    .collect();
+    let env = FlagKey::env_range("dark-mode", "prod");
+    let segments: Vec<String> = keys
+        .iter()
+        .filter(|k| env.contains(k))
+        .map(|k| from_key::<FlagKey>(k.clone()).unwrap().segment)
+        .collect();
+    assert_eq!(segments, vec!["beta", "all"]);
+    let flag = FlagKey::flag_range("dark-mode");
+    assert_eq!(keys.iter().filter(|k| flag.contains(k)).count(), 4);
+}
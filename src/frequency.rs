@@ -0,0 +1,103 @@
+//! keys grouped by how often their entity is accessed.
+//!
+//! a [`FrequencyBucketed<T>`] prefixes the key with a [`FrequencyClass`] byte
+//! chosen by the caller, so hot entities sit together in their own range and
+//! can be cached, split or compacted apart from the cold bulk. the class is
+//! part of the key: reclassifying an entity moves it, so the old key must be
+//! deleted and the new one written.
+
+use crate::range::KeyRange;
+use crate::{to_key, IndexKey};
+use std::io::{Error, ErrorKind, Read, Write};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FrequencyClass {
+    Hot = 0,
+    Warm = 1,
+    Cold = 2,
+}
+
+impl IndexKey for FrequencyClass {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        (self as u8).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        match u8::from_key(key)? {
+            0 => Ok(FrequencyClass::Hot),
+            1 => Ok(FrequencyClass::Warm),
+            2 => Ok(FrequencyClass::Cold),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                "invalid frequency class",
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FrequencyBucketed<T> {
+    pub class: FrequencyClass,
+    pub value: T,
+}
+
+impl<T> FrequencyBucketed<T> {
+    /// classifies `value` with `classify`, typically a lookup in access
+    /// statistics.
+    pub fn new(value: T, classify: impl FnOnce(&T) -> FrequencyClass) -> FrequencyBucketed<T> {
+        let class = classify(&value);
+        FrequencyBucketed { class, value }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T: IndexKey> IndexKey for FrequencyBucketed<T> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        (self.class, self.value).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let (class, value) = <(FrequencyClass, T)>::from_key(key)?;
+        Ok(FrequencyBucketed { class, value })
+    }
+}
+
+/// every key of `class`.
+pub fn class_range(class: FrequencyClass) -> KeyRange {
+    KeyRange::prefix(to_key(class))
+}
+
+#[test]
+fn test_frequency_bucketed() {
+    use crate::from_key;
+    let hot = [7u64, 42];
+    let classify = |id: &u64| {
+        if hot.contains(id) {
+            FrequencyClass::Hot
+        } else if *id < 10 {
+            FrequencyClass::Warm
+        } else {
+            FrequencyClass::Cold
+        }
+    };
+    let mut keys: Vec<Vec<u8>> = [1u64, 7, 42, 100, 3]
+        .iter()
+        .map(|id| to_key(FrequencyBucketed::new(*id, classify)))
+        .collect();
+    keys.sort();
+    let ids: Vec<u64> = keys
+        .iter()
+        .map(|k| {
+            from_key::<FrequencyBucketed<u64>>(k.clone())
+                .unwrap()
+                .into_inner()
+        })
+        .collect();
+    assert_eq!(ids, [7, 42, 1, 3, 100]);
+
+    let hot_range = class_range(FrequencyClass::Hot);
+    assert_eq!(keys.iter().filter(|k| hot_range.contains(k)).count(), 2);
+    assert!(class_range(FrequencyClass::Cold).contains(&keys[4]));
+    assert!(from_key::<FrequencyBucketed<u64>>(to_key((3u8, 1u64))).is_err());
+}
@@ -0,0 +1,204 @@
+//! golden-file snapshots of key encodings.
+//!
+//! a [`Golden`] set names one encoded value per case. its bytes are checked in
+//! and compared on every test run, so an encoding change cannot ship unnoticed.
+//! [`representative`] covers this crate's own types; downstream crates can
+//! build a set for their wrappers the same way.
+//!
+//! the fixture is a list of `u32` big-endian length prefixed `name`, `key`
+//! pairs. to accept intended changes, rerun the tests with
+//! `INDEX_KEY_BLESS=1` to rewrite `fixtures/golden.bin`.
+
+use crate::{to_key, IndexKey};
+use std::convert::TryInto;
+use std::io::{Error, ErrorKind};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Golden {
+    cases: Vec<(String, Vec<u8>)>,
+}
+
+fn read_chunk<'a>(bytes: &mut &'a [u8]) -> Result<&'a [u8], Error> {
+    let invalid = || Error::new(ErrorKind::InvalidData, "truncated golden fixture");
+    if bytes.len() < 4 {
+        return Err(invalid());
+    }
+    let len = u32::from_be_bytes(bytes[..4].try_into().unwrap()) as usize;
+    let rest = &bytes[4..];
+    if rest.len() < len {
+        return Err(invalid());
+    }
+    *bytes = &rest[len..];
+    Ok(&rest[..len])
+}
+
+impl Golden {
+    pub fn new() -> Golden {
+        Golden::default()
+    }
+
+    /// names must be unique within a set.
+    pub fn case<I: IndexKey>(&mut self, name: &str, value: I) -> &mut Golden {
+        assert!(
+            self.cases.iter().all(|(n, _)| n != name),
+            "duplicate golden case {}",
+            name
+        );
+        self.cases.push((name.to_owned(), to_key(value)));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.cases.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cases.is_empty()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut result = vec![];
+        for (name, key) in &self.cases {
+            for chunk in [name.as_bytes(), &key[..]] {
+                result.extend_from_slice(&(chunk.len() as u32).to_be_bytes());
+                result.extend_from_slice(chunk);
+            }
+        }
+        result
+    }
+
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Golden, Error> {
+        let mut cases = vec![];
+        while !bytes.is_empty() {
+            let name = String::from_utf8(read_chunk(&mut bytes)?.to_vec())
+                .map_err(|_| Error::new(ErrorKind::InvalidData, "invalid golden case name"))?;
+            cases.push((name, read_chunk(&mut bytes)?.to_vec()));
+        }
+        Ok(Golden { cases })
+    }
+
+    /// one line per case that changed, vanished or is not in `fixture` yet.
+    pub fn check(&self, fixture: &[u8]) -> Result<(), Vec<String>> {
+        let expected = Golden::from_bytes(fixture).map_err(|e| vec![e.to_string()])?;
+        let mut problems = vec![];
+        for (name, want) in &expected.cases {
+            match self.cases.iter().find(|(n, _)| n == name) {
+                Some((_, got)) if got != want => problems.push(format!(
+                    "{}: encoding changed from {:02x?} to {:02x?}",
+                    name, want, got
+                )),
+                Some(_) => (),
+                None => problems.push(format!("{}: case removed", name)),
+            }
+        }
+        for (name, _) in &self.cases {
+            if expected.cases.iter().all(|(n, _)| n != name) {
+                problems.push(format!("{}: not in fixture", name));
+            }
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+}
+
+/// representative values of every key type this crate encodes under `std`.
+pub fn representative() -> Golden {
+    #[cfg(feature = "digest")]
+    use crate::content_hash::ContentHash;
+    use crate::locale::{CountryCode, LanguageTag};
+    use crate::net::MacAddr;
+    use crate::numeric::{Bucketted, FixedPoint, Money};
+    use crate::odd_int::{I24, I48, U24, U48};
+    use crate::sparse::SparseTuple;
+    #[cfg(feature = "digest")]
+    use crate::surrogate::SurrogateOf;
+    use crate::tombstone::MaybeDeleted;
+    use crate::versioned::Versioned;
+    use crate::Nested;
+
+    let mut golden = Golden::new();
+    golden
+        .case("string/empty", String::new())
+        .case("string/ascii", "index".to_owned())
+        .case("string/escapes", "\0\x01\x02".to_owned())
+        .case("string/unicode", "ключ😃".to_owned())
+        .case("bytes/empty", Vec::<u8>::new())
+        .case("bytes/escapes", vec![0u8, 1, 2, 255])
+        .case("bool/false", false)
+        .case("bool/true", true)
+        .case("u8/max", u8::MAX)
+        .case("u16/mid", 0x1234u16)
+        .case("u32/max", u32::MAX)
+        .case("u64/one", 1u64)
+        .case("u128/max", u128::MAX)
+        .case("i8/min", i8::MIN)
+        .case("i16/neg", -2i16)
+        .case("i32/zero", 0i32)
+        .case("i64/max", i64::MAX)
+        .case("i128/min", i128::MIN)
+        .case("f32/neg", -1.5f32)
+        .case("f32/neg_zero", -0.0f32)
+        .case("f32/inf", f32::INFINITY)
+        .case("f64/pos", 1.5f64)
+        .case("f64/min_positive", f64::MIN_POSITIVE)
+        .case("f64/neg_inf", f64::NEG_INFINITY)
+        .case("tuple/2", (1u8, "a".to_owned()))
+        .case(
+            "tuple/11",
+            (
+                1u8, 2u16, 3u32, 4u64, 5u128, -1i8, -2i16, -3i32, -4i64, -5i128, true,
+            ),
+        )
+        .case("nested", (Nested((1u8, 2u8)), 3u8))
+        .case(
+            "sparse",
+            SparseTuple((Some(7u16), None::<String>, Some(false))),
+        )
+        .case("odd_int/u24", U24::MAX)
+        .case("odd_int/i24", I24::MIN)
+        .case("odd_int/u48", U48::new(1).unwrap())
+        .case("odd_int/i48", I48::new(-1).unwrap())
+        .case("fixed_point", FixedPoint::<16>::from_int(-3).unwrap())
+        .case("bucketted", Bucketted::<f64, 3>::new(1.23999))
+        .case("money", Money::new("eur", -250).unwrap())
+        .case("country", CountryCode::new("de").unwrap())
+        .case("language", LanguageTag::new("zh-Hant-TW").unwrap())
+        .case("mac", MacAddr([0, 0x1a, 0x2b, 0x3c, 0x4d, 0x5e]));
+    #[cfg(feature = "digest")]
+    golden
+        .case("content_hash", ContentHash::<32>::sha256(b"golden"))
+        .case("surrogate", SurrogateOf::<String>::from_id(42));
+    golden
+        .case("versioned", Versioned::new("k".to_owned(), 3))
+        .case("tombstone", MaybeDeleted::deleted(5u32));
+    golden
+}
+
+/// the fixture holds every case, including those behind `digest`.
+#[cfg(feature = "digest")]
+#[test]
+fn test_golden() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/golden.bin");
+    let current = representative();
+    if std::env::var_os("INDEX_KEY_BLESS").is_some() {
+        std::fs::write(path, current.to_bytes()).unwrap();
+    }
+    let fixture = std::fs::read(path).unwrap();
+    if let Err(problems) = current.check(&fixture) {
+        panic!("golden encodings changed:\n{}", problems.join("\n"));
+    }
+
+    assert_eq!(Golden::from_bytes(&current.to_bytes()).unwrap(), current);
+    let mut changed = Golden::new();
+    changed.case("u8/max", 0u8).case("extra", 1u8);
+    let problems = changed.check(&current.to_bytes()).unwrap_err();
+    assert!(problems[0].starts_with("string/empty: case removed"));
+    assert!(problems
+        .iter()
+        .any(|p| p.starts_with("u8/max: encoding changed")));
+    assert!(problems.iter().any(|p| p == "extra: not in fixture"));
+    assert!(Golden::from_bytes(&[0, 0, 0, 9, 1]).is_err());
+}
@@ -0,0 +1,114 @@
+//! equi-depth histograms over encoded keys, for estimating scan sizes.
+//!
+//! [`Histogram::build`] sorts a sample of keys and keeps `buckets + 1` bounds
+//! so every bucket holds the same share of rows. within a bucket keys are
+//! assumed spread evenly, reading the eight bytes after the bounds' common
+//! prefix as a number.
+
+use crate::range::KeyRange;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Histogram {
+    /// ascending; bucket `i` is `(bounds[i], bounds[i + 1]]`.
+    bounds: Vec<Vec<u8>>,
+    rows: u64,
+}
+
+/// where `key` sits between `lo` and `hi`, from 0 to 1.
+fn interpolate(lo: &[u8], hi: &[u8], key: &[u8]) -> f64 {
+    let common = lo.iter().zip(hi).take_while(|(a, b)| a == b).count();
+    let number = |bytes: &[u8]| {
+        let mut raw = [0u8; 8];
+        for (r, b) in raw.iter_mut().zip(bytes.iter().skip(common)) {
+            *r = *b;
+        }
+        u64::from_be_bytes(raw) as f64
+    };
+    let (lo, hi, key) = (number(lo), number(hi), number(key));
+    if hi <= lo {
+        return 1.0;
+    }
+    ((key - lo) / (hi - lo)).clamp(0.0, 1.0)
+}
+
+impl Histogram {
+    /// `sample` need not be sorted; `rows` is the size of the whole index.
+    pub fn build(mut sample: Vec<Vec<u8>>, buckets: usize, rows: u64) -> Histogram {
+        if sample.is_empty() {
+            return Histogram {
+                bounds: vec![],
+                rows,
+            };
+        }
+        sample.sort();
+        let buckets = buckets.clamp(1, sample.len());
+        let last = sample.len() - 1;
+        let bounds = (0..=buckets)
+            .map(|i| sample[i * last / buckets].clone())
+            .collect();
+        Histogram { bounds, rows }
+    }
+
+    pub fn buckets(&self) -> usize {
+        self.bounds.len().saturating_sub(1)
+    }
+
+    pub fn rows(&self) -> u64 {
+        self.rows
+    }
+
+    /// how many buckets' worth of keys sort before `key`.
+    fn position(&self, key: &[u8]) -> f64 {
+        let below = self.bounds.partition_point(|b| &b[..] < key);
+        if below == 0 {
+            return 0.0;
+        }
+        if below == self.bounds.len() {
+            return self.buckets() as f64;
+        }
+        (below - 1) as f64 + interpolate(&self.bounds[below - 1], &self.bounds[below], key)
+    }
+
+    /// expected rows inside `range`.
+    pub fn estimate_rows(&self, range: &KeyRange) -> f64 {
+        if self.bounds.is_empty() || range.is_empty() {
+            return 0.0;
+        }
+        let buckets = self.buckets() as f64;
+        let start = self.position(&range.start);
+        let end = range.end.as_ref().map_or(buckets, |end| self.position(end));
+        (end - start).max(0.0) / buckets * self.rows as f64
+    }
+}
+
+#[test]
+fn test_histogram() {
+    use crate::to_key;
+    let sample: Vec<Vec<u8>> = (0..1000u32).rev().map(|i| to_key(i * 10)).collect();
+    let histogram = Histogram::build(sample, 20, 100_000);
+    assert_eq!(histogram.buckets(), 20);
+    let estimate =
+        |start: u32, end: u32| histogram.estimate_rows(&KeyRange::new(to_key(start), to_key(end)));
+    assert!((estimate(0, 5000) - 50_000.0).abs() < 1000.0);
+    assert!((estimate(2500, 3500) - 10_000.0).abs() < 1000.0);
+    assert_eq!(estimate(20_000, 30_000), 0.0);
+    assert_eq!(estimate(10, 10), 0.0);
+    assert_eq!(histogram.estimate_rows(&KeyRange::all()), 100_000.0);
+
+    // skewed: half the rows share one value.
+    let sample: Vec<Vec<u8>> = (0..100u8)
+        .map(|i| to_key((if i < 50 { 7u8 } else { i }, i)))
+        .collect();
+    let histogram = Histogram::build(sample, 10, 1000);
+    let hot = histogram.estimate_rows(&KeyRange::prefix(to_key(7u8)));
+    let cold = histogram.estimate_rows(&KeyRange::prefix(to_key(60u8)));
+    assert!(hot > 400.0 && cold < 100.0, "{} {}", hot, cold);
+
+    assert_eq!(
+        Histogram::build(vec![], 4, 10).estimate_rows(&KeyRange::all()),
+        0.0
+    );
+    let single = Histogram::build(vec![vec![5]], 4, 10);
+    assert_eq!(single.estimate_rows(&KeyRange::all()), 10.0);
+    assert_eq!(single.estimate_rows(&KeyRange::from(vec![6])), 0.0);
+}
@@ -0,0 +1,100 @@
+//! idempotency keys for API servers.
+//!
+//! an [`IdempotencyKey`] encodes as `(hash(request), bucketed_expiry)`, so a
+//! retried request finds its earlier response with a prefix scan on the hash.
+//! sweeping expired entries needs expiry order, so each entry also gets an
+//! [`expiry_key`](IdempotencyKey::expiry_key) `(bucketed_expiry, hash)` in a
+//! companion keyspace, which [`expired_before`] ranges over.
+
+use crate::content_hash::ContentHash;
+use crate::range::KeyRange;
+use crate::{to_key, IndexKey};
+use std::io::{Error, ErrorKind, Read, Write};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IdempotencyKey {
+    pub hash: ContentHash<32>,
+    /// a multiple of the bucket width, never before the requested expiry.
+    pub expires: u64,
+}
+
+/// `ts` rounded up to a multiple of `bucket`; a `bucket` of 0 keeps `ts`.
+/// an error if the rounded value does not fit in a `u64`.
+fn bucketed(ts: u64, bucket: u64) -> Result<u64, Error> {
+    match bucket {
+        0 => Ok(ts),
+        _ => ts
+            .div_ceil(bucket)
+            .checked_mul(bucket)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "bucketed expiry overflows")),
+    }
+}
+
+impl IdempotencyKey {
+    /// `request` is whatever identifies a retry, e.g. the client's key plus
+    /// the request body. bucketing the expiry makes sweeps touch fewer,
+    /// larger runs of keys.
+    pub fn new(request: &[u8], expires: u64, bucket: u64) -> Result<IdempotencyKey, Error> {
+        Ok(IdempotencyKey {
+            hash: ContentHash::sha256(request),
+            expires: bucketed(expires, bucket)?,
+        })
+    }
+
+    /// every entry for `request`, whatever its expiry.
+    pub fn lookup_range(request: &[u8]) -> KeyRange {
+        KeyRange::prefix(to_key(ContentHash::sha256(request)))
+    }
+
+    /// the companion key ordered by expiry.
+    pub fn expiry_key(&self) -> Vec<u8> {
+        to_key((self.expires, self.hash))
+    }
+
+    /// an entry is live through its expiry instant and expired after it,
+    /// matching [`expired_before`].
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires < now
+    }
+}
+
+/// expiry keys of entries that expired before `ts`.
+pub fn expired_before(ts: u64) -> KeyRange {
+    KeyRange::new(vec![], to_key(ts))
+}
+
+impl IndexKey for IdempotencyKey {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        (self.hash, self.expires).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let (hash, expires) = <(ContentHash<32>, u64)>::from_key(key)?;
+        Ok(IdempotencyKey { hash, expires })
+    }
+}
+
+#[test]
+fn test_idempotency_key() {
+    use crate::from_key;
+    let key = IdempotencyKey::new(b"client-1:POST /orders", 1_001, 60).unwrap();
+    assert_eq!(key.expires, 1_020);
+    assert_eq!(IdempotencyKey::new(b"x", 1_020, 60).unwrap().expires, 1_020);
+    assert_eq!(IdempotencyKey::new(b"x", 7, 0).unwrap().expires, 7);
+    assert_eq!(
+        IdempotencyKey::new(b"x", u64::MAX, 0).unwrap().expires,
+        u64::MAX
+    );
+    assert!(IdempotencyKey::new(b"x", u64::MAX, 60).is_err());
+    assert_eq!(from_key::<IdempotencyKey>(to_key(key)).unwrap(), key);
+
+    assert!(IdempotencyKey::lookup_range(b"client-1:POST /orders").contains(&to_key(key)));
+    assert!(!IdempotencyKey::lookup_range(b"client-2:POST /orders").contains(&to_key(key)));
+
+    for now in 1_019..=1_021 {
+        assert_eq!(
+            expired_before(now).contains(&key.expiry_key()),
+            key.is_expired(now)
+        );
+    }
+    assert!(!key.is_expired(1_020) && key.is_expired(1_021));
+}
@@ -0,0 +1,138 @@
+//! intersecting index scans on their primary keys.
+//!
+//! index keys are `(value, pk)`. within an equality scan the value is fixed,
+//! so keys come out ordered by the encoded primary key, and several such scans
+//! can be intersected by leapfrogging: hold one key per stream, skip every
+//! stream forward to the largest primary key seen, emit when all agree. memory
+//! stays at one key per stream.
+
+use crate::IndexKey;
+use std::io::{Cursor, Error, ErrorKind};
+use std::marker::PhantomData;
+
+pub struct Intersect<V, I> {
+    streams: Vec<I>,
+    /// last primary key read from each stream.
+    heads: Vec<Vec<u8>>,
+    started: bool,
+    done: bool,
+    value: PhantomData<fn() -> V>,
+}
+
+/// the encoded primary keys present in every stream, ascending. each stream
+/// must yield `(value, pk)` keys ordered by `pk`, as an equality scan does; a
+/// stream going backwards is an `InvalidData` error, after which iteration
+/// stops. no streams intersect to nothing.
+pub fn intersect_by_primary_key<V, I>(streams: Vec<I>) -> Intersect<V, I>
+where
+    V: IndexKey,
+    I: Iterator<Item = Vec<u8>>,
+{
+    Intersect {
+        heads: vec![vec![]; streams.len()],
+        done: streams.is_empty(),
+        streams,
+        started: false,
+        value: PhantomData,
+    }
+}
+
+impl<V: IndexKey, I: Iterator<Item = Vec<u8>>> Intersect<V, I> {
+    /// reads stream `i` until its primary key is at least `target`.
+    fn seek(&mut self, i: usize, target: Option<&[u8]>) -> Result<bool, Error> {
+        loop {
+            let key = match self.streams[i].next() {
+                Some(key) => key,
+                None => return Ok(false),
+            };
+            let mut cursor = Cursor::new(&key[..]);
+            V::from_key(&mut cursor)?;
+            let pk = &key[cursor.position() as usize..];
+            if self.started && pk <= &self.heads[i][..] {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "stream not ordered by primary key",
+                ));
+            }
+            self.heads[i] = pk.to_vec();
+            if target.is_none_or(|t| pk >= t) {
+                return Ok(true);
+            }
+        }
+    }
+
+    fn step(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        for i in 0..self.streams.len() {
+            if !self.seek(i, None)? {
+                return Ok(None);
+            }
+        }
+        self.started = true;
+        loop {
+            let target = self.heads.iter().max().unwrap().clone();
+            let mut agreed = true;
+            for i in 0..self.streams.len() {
+                if self.heads[i] < target {
+                    if !self.seek(i, Some(&target))? {
+                        return Ok(None);
+                    }
+                    agreed &= self.heads[i] == target;
+                }
+            }
+            if agreed {
+                return Ok(Some(target));
+            }
+        }
+    }
+}
+
+impl<V: IndexKey, I: Iterator<Item = Vec<u8>>> Iterator for Intersect<V, I> {
+    type Item = Result<Vec<u8>, Error>;
+
+    fn next(&mut self) -> Option<Result<Vec<u8>, Error>> {
+        if self.done {
+            return None;
+        }
+        let result = self.step();
+        if !matches!(result, Ok(Some(_))) {
+            self.done = true;
+        }
+        result.transpose()
+    }
+}
+
+#[test]
+fn test_intersect_by_primary_key() {
+    use crate::{from_key, to_key};
+    let scan = |value: &str, pks: &[u32]| -> Vec<Vec<u8>> {
+        pks.iter()
+            .map(|pk| to_key((value.to_owned(), *pk)))
+            .collect()
+    };
+    let streams = vec![
+        scan("red", &[1, 3, 4, 7, 9, 12]).into_iter(),
+        scan("large", &[2, 3, 7, 8, 12, 20]).into_iter(),
+        scan("in_stock", &[3, 5, 7, 11, 12]).into_iter(),
+    ];
+    let pks: Vec<u32> = intersect_by_primary_key::<String, _>(streams)
+        .map(|pk| from_key(pk.unwrap()).unwrap())
+        .collect();
+    assert_eq!(pks, vec![3, 7, 12]);
+
+    let streams = vec![scan("a", &[1, 2]).into_iter(), scan("b", &[]).into_iter()];
+    assert_eq!(intersect_by_primary_key::<String, _>(streams).count(), 0);
+    let none: Vec<std::vec::IntoIter<Vec<u8>>> = vec![];
+    assert_eq!(intersect_by_primary_key::<String, _>(none).count(), 0);
+
+    let streams = vec![
+        scan("a", &[1, 5, 2]).into_iter(),
+        scan("b", &[1, 2, 5]).into_iter(),
+    ];
+    let results: Vec<_> = intersect_by_primary_key::<String, _>(streams).collect();
+    assert_eq!(results.len(), 3);
+    assert!(results[0].is_ok() && results[1].is_ok());
+    assert_eq!(
+        results[2].as_ref().unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+}
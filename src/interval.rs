@@ -0,0 +1,209 @@
+//! interval tree over encoded key ranges.
+//!
+//! entries are kept sorted by start; an implicit balanced tree over that order
+//! stores the greatest end of each subtree so queries skip whole subtrees.
+
+use crate::range::KeyRange;
+use std::cmp::Ordering;
+use std::iter::FromIterator;
+
+#[derive(Clone, Debug)]
+pub struct IntervalTree<V> {
+    entries: Vec<(KeyRange, V)>,
+    // per subtree root, the index of the entry with the greatest end below it.
+    max_end: Vec<usize>,
+}
+
+impl<V> Default for IntervalTree<V> {
+    fn default() -> Self {
+        IntervalTree {
+            entries: vec![],
+            max_end: vec![],
+        }
+    }
+}
+
+// `None` is the unbounded end.
+fn cmp_end(a: Option<&[u8]>, b: Option<&[u8]>) -> Ordering {
+    match (a, b) {
+        (None, None) => Ordering::Equal,
+        (None, _) => Ordering::Greater,
+        (_, None) => Ordering::Less,
+        (Some(a), Some(b)) => a.cmp(b),
+    }
+}
+
+impl<V> IntervalTree<V> {
+    pub fn new() -> IntervalTree<V> {
+        IntervalTree::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&KeyRange, &V)> {
+        self.entries.iter().map(|(r, v)| (r, v))
+    }
+
+    /// O(n) as the subtree maxima are rebuilt; load many ranges at once with
+    /// `collect` or `extend`, which sort and rebuild only once.
+    pub fn insert(&mut self, range: KeyRange, value: V) {
+        let index = self
+            .entries
+            .partition_point(|(r, _)| r.start <= range.start);
+        self.entries.insert(index, (range, value));
+        self.rebuild();
+    }
+
+    fn end(&self, index: usize) -> Option<&[u8]> {
+        self.entries[index].0.end.as_deref()
+    }
+
+    fn rebuild(&mut self) {
+        self.max_end = (0..self.entries.len()).collect();
+        self.build(0, self.entries.len());
+    }
+
+    fn build(&mut self, lo: usize, hi: usize) -> Option<usize> {
+        if lo >= hi {
+            return None;
+        }
+        let mid = (lo + hi) / 2;
+        let mut max = mid;
+        for child in [self.build(lo, mid), self.build(mid + 1, hi)]
+            .iter()
+            .flatten()
+        {
+            let candidate = self.max_end[*child];
+            if cmp_end(self.end(candidate), self.end(max)) == Ordering::Greater {
+                max = candidate;
+            }
+        }
+        self.max_end[mid] = max;
+        Some(mid)
+    }
+
+    /// entries whose range contains `key`.
+    pub fn stabbing_query(&self, key: &[u8]) -> Vec<(&KeyRange, &V)> {
+        let mut end = key.to_vec();
+        end.push(0);
+        self.overlap_query(&KeyRange::new(key.to_vec(), end))
+    }
+
+    /// entries whose range shares at least one key with `range`, by start.
+    pub fn overlap_query(&self, range: &KeyRange) -> Vec<(&KeyRange, &V)> {
+        let mut result = vec![];
+        if !range.is_empty() {
+            self.collect(0, self.entries.len(), range, &mut result);
+        }
+        result
+    }
+
+    fn collect<'a>(
+        &'a self,
+        lo: usize,
+        hi: usize,
+        range: &KeyRange,
+        result: &mut Vec<(&'a KeyRange, &'a V)>,
+    ) {
+        if lo >= hi {
+            return;
+        }
+        let mid = (lo + hi) / 2;
+        if cmp_end(self.end(self.max_end[mid]), Some(&range.start)) != Ordering::Greater {
+            return;
+        }
+        self.collect(lo, mid, range, result);
+        let (entry, value) = &self.entries[mid];
+        if range.end.as_ref().is_some_and(|end| entry.start >= *end) {
+            return;
+        }
+        if entry.overlaps(range) {
+            result.push((entry, value));
+        }
+        self.collect(mid + 1, hi, range, result);
+    }
+}
+
+impl<V> Extend<(KeyRange, V)> for IntervalTree<V> {
+    fn extend<I: IntoIterator<Item = (KeyRange, V)>>(&mut self, iter: I) {
+        self.entries.extend(iter);
+        // stable, so equal starts keep insertion order as with `insert`.
+        self.entries.sort_by(|(a, _), (b, _)| a.start.cmp(&b.start));
+        self.rebuild();
+    }
+}
+
+impl<V> FromIterator<(KeyRange, V)> for IntervalTree<V> {
+    fn from_iter<I: IntoIterator<Item = (KeyRange, V)>>(iter: I) -> Self {
+        let mut tree = IntervalTree::new();
+        tree.extend(iter);
+        tree
+    }
+}
+
+#[test]
+fn test_interval_tree() {
+    let mut tree = IntervalTree::new();
+    tree.insert(KeyRange::new(vec![10], vec![20]), "a");
+    tree.insert(KeyRange::new(vec![0], vec![5]), "b");
+    tree.insert(KeyRange::from(vec![15]), "c");
+    tree.insert(KeyRange::new(vec![12], vec![13]), "d");
+    tree.insert(KeyRange::new(vec![30], vec![30]), "empty");
+    tree.insert(KeyRange::new(vec![1], vec![40]), "e");
+    assert_eq!(tree.len(), 6);
+
+    fn values<'a>(hits: Vec<(&KeyRange, &&'a str)>) -> Vec<&'a str> {
+        hits.into_iter().map(|(_, v)| *v).collect()
+    }
+    assert_eq!(values(tree.stabbing_query(&[0])), vec!["b"]);
+    assert_eq!(values(tree.stabbing_query(&[12, 5])), vec!["e", "a", "d"]);
+    assert_eq!(values(tree.stabbing_query(&[15])), vec!["e", "a", "c"]);
+    assert_eq!(values(tree.stabbing_query(&[30])), vec!["e", "c"]);
+    assert_eq!(values(tree.stabbing_query(&[200])), vec!["c"]);
+    assert_eq!(
+        values(tree.overlap_query(&KeyRange::new(vec![5], vec![10]))),
+        vec!["e"]
+    );
+    assert_eq!(
+        values(tree.overlap_query(&KeyRange::new(vec![4], vec![11]))),
+        vec!["b", "e", "a"]
+    );
+    assert!(tree
+        .overlap_query(&KeyRange::new(vec![9], vec![9]))
+        .is_empty());
+
+    for probe in 0u8..=255 {
+        assert_eq!(
+            tree.stabbing_query(&[probe]).len(),
+            tree.iter().filter(|(r, _)| r.contains(&[probe])).count()
+        );
+    }
+
+    let bulk: IntervalTree<&str> = tree.iter().map(|(r, v)| (r.clone(), *v)).collect();
+    assert_eq!(
+        bulk.iter().collect::<Vec<_>>(),
+        tree.iter().collect::<Vec<_>>()
+    );
+    let mut more = bulk.clone();
+    more.extend([(KeyRange::new(vec![12], vec![14]), "f")]);
+    assert_eq!(
+        values(more.stabbing_query(&[12, 5])),
+        vec!["e", "a", "d", "f"]
+    );
+
+    let many: IntervalTree<u32> = (0..20_000u32)
+        .rev()
+        .map(|i| (KeyRange::new(crate::to_key(i), crate::to_key(i + 2)), i))
+        .collect();
+    let hits = many.stabbing_query(&crate::to_key(500u32));
+    assert_eq!(
+        hits.iter().map(|(_, v)| **v).collect::<Vec<_>>(),
+        [499, 500]
+    );
+}
@@ -0,0 +1,1713 @@
+//! the `std::io` based [`IndexKey`] api.
+
+use crate::codec::{self, FixedWidth, Step, Unescaper};
+use std::cell::Cell;
+use std::convert::TryFrom;
+use std::io::Cursor;
+use std::io::Error;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Write;
+use std::ops::{Bound, RangeInclusive};
+
+pub trait IndexKey: Sized {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error>;
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error>;
+}
+
+impl IndexKey for String {
+    #[inline]
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        self.into_bytes().to_key(result)
+    }
+    #[inline]
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        Ok(String::from_utf8_lossy(&Vec::<u8>::from_key(key)?).to_string())
+    }
+}
+
+#[test]
+fn test_string() {
+    let s: String = "123".into();
+    assert_eq!(from_key::<String>(to_key(s.clone())).unwrap(), s);
+
+    for c in '\0' as u32..('😃' as u32) {
+        let a = std::char::from_u32(c);
+        let b = std::char::from_u32(c + 1);
+        if a.is_none() {
+            continue;
+        }
+        if b.is_none() {
+            continue;
+        }
+        assert!(to_key(a.unwrap().to_string()) < to_key(b.unwrap().to_string()));
+    }
+}
+
+/// the char's utf-8 bytes, unterminated: the lead byte gives the length, and
+/// utf-8 byte order is scalar value order, as for `String`.
+impl IndexKey for char {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        result.write_all(self.encode_utf8(&mut [0; 4]).as_bytes())?;
+        Ok(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let mut bytes = [0; 4];
+        key.read_exact(&mut bytes[..1])?;
+        let len = match bytes[0] {
+            0x00..=0x7f => 1,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            _ => 4,
+        };
+        key.read_exact(&mut bytes[1..len])?;
+        std::str::from_utf8(&bytes[..len])
+            .ok()
+            .and_then(|s| s.chars().next())
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "invalid utf-8 char"))
+    }
+}
+
+#[test]
+fn test_char() {
+    let chars = ['\0', 'a', 'é', '\u{ffff}', '😃', char::MAX];
+    let keys: Vec<_> = chars.iter().map(|c| to_key((*c, 0u8))).collect();
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    for c in chars {
+        assert_eq!(from_key::<char>(to_key(c)).unwrap(), c);
+    }
+    assert_eq!(to_key('a'), vec![b'a']);
+    assert!(from_key::<char>(vec![0xff, 0, 0, 0]).is_err());
+    assert!(from_key::<char>(vec![0xc3]).is_err());
+}
+
+impl IndexKey for Vec<u8> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        codec::escape(&self, |chunk| result.write_all(chunk))?;
+        Ok(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let mut result = vec![];
+        escape_decode(key, &mut result)?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+struct VecRange(Vec<u8>, usize);
+
+#[cfg(test)]
+impl Iterator for VecRange {
+    type Item = (Vec<u8>, Vec<u8>);
+    fn next(&mut self) -> Option<(Vec<u8>, Vec<u8>)> {
+        let mut state = true;
+        let max_len = self.1;
+        let old = self.0.clone();
+        loop {
+            if self.0.is_empty() {
+                if state {
+                    self.0.push(0);
+                    break;
+                } else {
+                    return None;
+                }
+            }
+            if state && (self.0.len() < max_len) {
+                self.0.push(0);
+                break;
+            }
+            if *self.0.last().unwrap() == 255 {
+                self.0.pop();
+                state = false;
+                continue;
+            }
+            *self.0.last_mut().unwrap() += 1;
+            break;
+        }
+        Some((old, self.0.clone()))
+    }
+}
+
+#[test]
+fn test_vec_u8() {
+    let v = vec![1u8, 2, 3, 4];
+    assert_eq!(from_key::<Vec<u8>>(to_key(v.clone())).unwrap(), v);
+    let it = VecRange(vec![], 2);
+    for (old_v, new_v) in it {
+        assert!(to_key(new_v.clone()) > to_key(old_v.clone()));
+    }
+}
+
+/// encodes borrowed bytes in place, as `Vec<u8>`; decodes owned.
+impl IndexKey for std::borrow::Cow<'_, [u8]> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        codec::escape(&self, |chunk| result.write_all(chunk))?;
+        Ok(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        Vec::<u8>::from_key(key).map(std::borrow::Cow::Owned)
+    }
+}
+
+/// encodes a borrowed string in place, as `String`; decodes owned.
+impl IndexKey for std::borrow::Cow<'_, str> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        codec::escape(self.as_bytes(), |chunk| result.write_all(chunk))?;
+        Ok(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        String::from_key(key).map(std::borrow::Cow::Owned)
+    }
+}
+
+#[test]
+fn test_cow() {
+    use std::borrow::Cow;
+    let name = "tenant/ä";
+    assert_eq!(to_key(Cow::Borrowed(name)), to_key(name.to_owned()));
+    assert_eq!(
+        to_key((Cow::Borrowed(&[0u8, 1][..]), 3u8)),
+        to_key((vec![0u8, 1], 3u8))
+    );
+    let decoded = from_key::<(Cow<str>, Cow<[u8]>)>(to_key((
+        Cow::Borrowed(name),
+        Cow::<[u8]>::Owned(vec![0u8, 255]),
+    )))
+    .unwrap();
+    assert_eq!(
+        decoded,
+        (Cow::Borrowed(name), Cow::Borrowed(&[0u8, 255][..]))
+    );
+    assert!(matches!(decoded.0, Cow::Owned(_)));
+}
+
+#[inline]
+pub(crate) fn write_fixed<T: FixedWidth, W: Write>(
+    value: T,
+    result: &mut W,
+) -> Result<&mut W, Error> {
+    result.write_all(value.encode().as_ref())?;
+    Ok(result)
+}
+
+#[inline]
+pub(crate) fn read_fixed<T: FixedWidth, R: Read>(key: &mut R) -> Result<T, Error> {
+    let mut bytes = T::Bytes::default();
+    key.read_exact(bytes.as_mut())?;
+    Ok(T::decode(bytes))
+}
+
+macro_rules! impl_u {
+    ($t:ident) => {
+        impl IndexKey for $t {
+            fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+                write_fixed(self, result)
+            }
+            fn from_key<R: Read>(key: &mut R) -> Result<$t, Error> {
+                read_fixed(key)
+            }
+        }
+        #[test]
+        fn $t() {
+            const MAX: $t = <$t>::MAX;
+            let mut list = vec![MAX, 1, 2, 0];
+            list.sort_by_key(|value| {
+                assert_eq!(from_key::<$t>(to_key(*value)).unwrap(), *value);
+                to_key(*value)
+            });
+            assert_eq!(list, vec![0, 1, 2, MAX]);
+        }
+    };
+}
+
+impl_u!(u8);
+impl_u!(u16);
+impl_u!(u32);
+impl_u!(u64);
+impl_u!(u128);
+
+macro_rules! impl_i {
+    ($t:ident) => {
+        impl IndexKey for $t {
+            fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+                write_fixed(self, result)
+            }
+            fn from_key<R: Read>(key: &mut R) -> Result<$t, Error> {
+                read_fixed(key)
+            }
+        }
+        #[test]
+        fn $t() {
+            const MAX: $t = <$t>::MAX;
+            const MIN: $t = <$t>::MIN;
+            let mut list = vec![MAX, MIN, 1, 2, -1, -2, 0];
+            list.sort_by_key(|value| {
+                assert_eq!(from_key::<$t>(to_key(*value)).unwrap(), *value);
+                to_key(*value)
+            });
+            assert_eq!(list, vec![MIN, -2, -1, 0, 1, 2, MAX]);
+        }
+    };
+}
+
+impl_i!(i8);
+impl_i!(i16);
+impl_i!(i32);
+impl_i!(i64);
+impl_i!(i128);
+
+macro_rules! impl_f {
+    ($f:ty,$fi:ident) => {
+        impl IndexKey for $f {
+            fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+                write_fixed(self, result)
+            }
+            fn from_key<R: Read>(key: &mut R) -> Result<$f, Error> {
+                read_fixed(key)
+            }
+        }
+        #[test]
+        fn $fi() {
+            const INFINITY: $f = <$f>::INFINITY;
+            const NEG_INFINITY: $f = <$f>::NEG_INFINITY;
+            const NAN: $f = <$f>::NAN;
+            const MAX: $f = <$f>::MAX;
+            const MIN: $f = <$f>::MIN;
+            let mut list: Vec<$f> = vec![
+                0.0,
+                -0.0,
+                1.0,
+                -1.0,
+                1.1,
+                -1.1,
+                0.001,
+                -0.001,
+                INFINITY,
+                MAX,
+                MIN,
+                NEG_INFINITY,
+            ];
+            list.sort_by_key(|value| {
+                assert_eq!(from_key::<$f>(to_key(*value)).unwrap(), *value);
+                to_key(*value)
+            });
+            assert_eq!(
+                list,
+                vec![
+                    NEG_INFINITY,
+                    MIN,
+                    -1.1,
+                    -1.0,
+                    -0.001,
+                    -0.0,
+                    0.0,
+                    0.001,
+                    1.0,
+                    1.1,
+                    MAX,
+                    INFINITY,
+                ]
+            );
+            assert!(to_key(NAN) > to_key(INFINITY))
+        }
+    };
+}
+
+impl_f!(f32, f32);
+impl_f!(f64, f64);
+
+impl IndexKey for bool {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        write_fixed(self, result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<bool, Error> {
+        read_fixed(key)
+    }
+}
+
+#[test]
+fn test_bool() {
+    assert!(from_key::<bool>(to_key(true)).unwrap());
+    assert_eq!(to_key(true), vec![1]);
+}
+
+/// zero bytes.
+impl IndexKey for () {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        Ok(result)
+    }
+    fn from_key<R: Read>(_: &mut R) -> Result<Self, Error> {
+        Ok(())
+    }
+}
+
+/// zero bytes.
+impl<T> IndexKey for std::marker::PhantomData<T> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        Ok(result)
+    }
+    fn from_key<R: Read>(_: &mut R) -> Result<Self, Error> {
+        Ok(std::marker::PhantomData)
+    }
+}
+
+#[test]
+fn test_unit() {
+    use std::marker::PhantomData;
+    assert_eq!(to_key(()), vec![]);
+    assert_eq!(to_key((1u8, (), PhantomData::<String>, 2u8)), vec![1, 2]);
+    assert_eq!(
+        from_key::<(u8, (), PhantomData<String>, u8)>(vec![1, 2]).unwrap(),
+        (1, (), PhantomData, 2)
+    );
+}
+
+/// `(secs, nanos)` as big-endian `u64` and `u32`.
+impl IndexKey for std::time::Duration {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        (self.as_secs(), self.subsec_nanos()).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let (secs, nanos) = <(u64, u32)>::from_key(key)?;
+        if nanos >= 1_000_000_000 {
+            return Err(Error::new(ErrorKind::InvalidData, "invalid duration nanos"));
+        }
+        Ok(std::time::Duration::new(secs, nanos))
+    }
+}
+
+#[test]
+fn test_duration() {
+    use std::time::Duration;
+    let durations = [
+        Duration::ZERO,
+        Duration::from_nanos(1),
+        Duration::from_millis(999),
+        Duration::from_secs(1),
+        Duration::new(1, 1),
+        Duration::MAX,
+    ];
+    let keys: Vec<_> = durations.iter().map(|d| to_key(*d)).collect();
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    for d in durations {
+        assert_eq!(from_key::<Duration>(to_key(d)).unwrap(), d);
+    }
+    assert_eq!(keys[0].len(), 12);
+    assert!(from_key::<Duration>(to_key((0u64, 1_000_000_000u32))).is_err());
+}
+
+/// `(secs, nanos)` since `UNIX_EPOCH` as `i64` and `u32`, with `secs` floored
+/// so times before 1970 sort below later ones.
+impl IndexKey for std::time::SystemTime {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        let too_far = || Error::new(ErrorKind::InvalidInput, "time out of range");
+        let (secs, nanos) = match self.duration_since(std::time::UNIX_EPOCH) {
+            Ok(after) => (
+                i64::try_from(after.as_secs()).map_err(|_| too_far())?,
+                after.subsec_nanos(),
+            ),
+            Err(before) => {
+                let before = before.duration();
+                let secs = i64::try_from(before.as_secs()).map_err(|_| too_far())?;
+                match before.subsec_nanos() {
+                    0 => (-secs, 0),
+                    nanos => (-secs - 1, 1_000_000_000 - nanos),
+                }
+            }
+        };
+        (secs, nanos).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        use std::time::{Duration, UNIX_EPOCH};
+        let (secs, nanos) = <(i64, u32)>::from_key(key)?;
+        if nanos >= 1_000_000_000 {
+            return Err(Error::new(ErrorKind::InvalidData, "invalid time nanos"));
+        }
+        let time = if secs >= 0 {
+            UNIX_EPOCH.checked_add(Duration::new(secs as u64, nanos))
+        } else {
+            UNIX_EPOCH
+                .checked_sub(Duration::from_secs(secs.unsigned_abs()))
+                .and_then(|t| t.checked_add(Duration::from_nanos(nanos.into())))
+        };
+        time.ok_or_else(|| Error::new(ErrorKind::InvalidData, "time out of range"))
+    }
+}
+
+#[test]
+fn test_system_time() {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    let times = [
+        UNIX_EPOCH - Duration::new(86_400 * 365 * 100, 5),
+        UNIX_EPOCH - Duration::new(1, 0),
+        UNIX_EPOCH - Duration::from_nanos(1),
+        UNIX_EPOCH,
+        UNIX_EPOCH + Duration::from_nanos(1),
+        UNIX_EPOCH + Duration::new(1_700_000_000, 123),
+    ];
+    let keys: Vec<_> = times.iter().map(|t| to_key(*t)).collect();
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    for t in times {
+        assert_eq!(from_key::<SystemTime>(to_key(t)).unwrap(), t);
+    }
+    assert_eq!(
+        to_key(UNIX_EPOCH - Duration::from_nanos(1)),
+        to_key((-1i64, 999_999_999u32))
+    );
+    assert!(from_key::<SystemTime>(to_key((0i64, 1_000_000_000u32))).is_err());
+}
+
+/// the native form of an os string: the raw bytes on unix, the utf-16 code
+/// units big-endian on windows, and utf-8 elsewhere.
+pub(crate) fn os_bytes(value: &std::ffi::OsStr) -> Vec<u8> {
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStrExt;
+        value.encode_wide().flat_map(u16::to_be_bytes).collect()
+    }
+    #[cfg(not(windows))]
+    {
+        value.as_encoded_bytes().to_vec()
+    }
+}
+
+pub(crate) fn os_string(bytes: Vec<u8>) -> Result<std::ffi::OsString, Error> {
+    #[cfg(unix)]
+    {
+        Ok(std::os::unix::ffi::OsStringExt::from_vec(bytes))
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStringExt;
+        if bytes.len() % 2 != 0 {
+            return Err(Error::new(ErrorKind::InvalidData, "odd utf-16 length"));
+        }
+        let units: Vec<u16> = bytes
+            .chunks(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]))
+            .collect();
+        Ok(std::ffi::OsString::from_wide(&units))
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        String::from_utf8(bytes)
+            .map(std::ffi::OsString::from)
+            .map_err(|_| Error::new(ErrorKind::InvalidData, "invalid utf-8 os string"))
+    }
+}
+
+/// the native form as a byte string: lossless on unix and windows, ordered by
+/// byte on unix and by utf-16 code unit on windows.
+impl IndexKey for std::ffi::OsString {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        os_bytes(&self).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        os_string(Vec::<u8>::from_key(key)?)
+    }
+}
+
+#[test]
+fn test_os_string() {
+    use std::ffi::OsString;
+    let names = ["", "a", "a b", "ab", "b", "\u{e9}"];
+    let keys: Vec<_> = names.iter().map(|n| to_key(OsString::from(n))).collect();
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    for n in names {
+        assert_eq!(from_key::<OsString>(to_key(OsString::from(n))).unwrap(), n);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+        let raw = OsString::from_vec(vec![b'f', 0xff, 0, b'o']);
+        assert_eq!(to_key(raw.clone()), to_key(vec![b'f', 0xff, 0, b'o']));
+        assert_eq!(
+            from_key::<(OsString, u8)>(to_key((raw.clone(), 1u8))).unwrap(),
+            (raw, 1)
+        );
+    }
+}
+
+/// the bytes without the trailing nul; a decoded nul inside is an error.
+impl IndexKey for std::ffi::CString {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        self.into_bytes().to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        std::ffi::CString::new(Vec::<u8>::from_key(key)?)
+            .map_err(|_| Error::new(ErrorKind::InvalidData, "nul inside c string"))
+    }
+}
+
+#[test]
+fn test_c_string() {
+    use std::ffi::CString;
+    let names = ["", "a", "ab", "b"];
+    let keys: Vec<_> = names
+        .iter()
+        .map(|n| to_key(CString::new(*n).unwrap()))
+        .collect();
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(keys[2], to_key(b"ab".to_vec()));
+    let name = CString::new("päth").unwrap();
+    assert_eq!(
+        from_key::<(CString, u8)>(to_key((name.clone(), 2u8))).unwrap(),
+        (name, 2)
+    );
+    assert!(from_key::<CString>(to_key(b"a\0b".to_vec())).is_err());
+}
+
+/// the components as a [`Seq`] of byte strings, so `/a/b` sorts before both
+/// `/a/b/c` and `/a/bc`. the root is the component `/`; like `Path`'s own
+/// equality, `a//b/./c/` and `a/b/c` are the same key.
+impl IndexKey for std::path::PathBuf {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        let parts = self.components().map(|c| os_bytes(c.as_os_str()));
+        Seq(parts.collect()).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let Seq(parts) = Seq::<Vec<u8>>::from_key(key)?;
+        let mut path = std::path::PathBuf::new();
+        for part in &parts {
+            path.push(os_string(part.clone())?);
+        }
+        // a separator or `.` inside a component would not survive the push.
+        if !path.components().map(|c| os_bytes(c.as_os_str())).eq(parts) {
+            return Err(Error::new(ErrorKind::InvalidData, "invalid path component"));
+        }
+        Ok(path)
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_path_buf() {
+    use std::path::PathBuf;
+    let paths = [
+        "/", "/a", "/a/b", "/a/b/c", "/a/bc", "/a!", "/b", "a", "a/b",
+    ];
+    let keys: Vec<_> = paths.iter().map(|p| to_key(PathBuf::from(p))).collect();
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    // byte order gets these wrong: '!' sorts before '/'.
+    assert!("/a!" < "/a/b");
+    for p in paths {
+        assert_eq!(
+            from_key::<PathBuf>(to_key(PathBuf::from(p))).unwrap(),
+            PathBuf::from(p)
+        );
+    }
+    assert_eq!(
+        to_key(PathBuf::from("a//b/./c/")),
+        to_key(PathBuf::from("a/b/c"))
+    );
+    assert_eq!(
+        from_key::<(PathBuf, u8)>(to_key((PathBuf::from("/x/../y"), 7u8))).unwrap(),
+        (PathBuf::from("/x/../y"), 7)
+    );
+    assert!(from_key::<PathBuf>(to_key(Seq(vec![b"a/b".to_vec()]))).is_err());
+    assert!(from_key::<PathBuf>(to_key(Seq(vec![b"a".to_vec(), b".".to_vec()]))).is_err());
+}
+
+/// a presence byte, 0 for `None` and 1 for `Some`, so `None` sorts first.
+impl<T: IndexKey> IndexKey for Option<T> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        match self {
+            None => result.write_all(&[0])?,
+            Some(value) => {
+                result.write_all(&[1])?;
+                value.to_key(result)?;
+            }
+        }
+        Ok(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let mut tag = [0];
+        key.read_exact(&mut tag)?;
+        match tag[0] {
+            0 => Ok(None),
+            1 => Ok(Some(T::from_key(key)?)),
+            _ => Err(Error::new(
+                std::io::ErrorKind::InvalidData,
+                "invalid option tag",
+            )),
+        }
+    }
+}
+
+#[test]
+fn test_option() {
+    let keys: Vec<_> = [None, Some(i32::MIN), Some(-1), Some(0), Some(i32::MAX)]
+        .iter()
+        .map(|v| to_key((*v, "x".to_owned())))
+        .collect();
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(to_key(None::<u8>), vec![0]);
+    let value = (Some("a".to_owned()), None::<u8>, Some(Some(false)));
+    assert_eq!(
+        from_key::<(Option<String>, Option<u8>, Option<Option<bool>>)>(to_key(value.clone()))
+            .unwrap(),
+        value
+    );
+    assert!(to_key(Some(None::<u8>)) > to_key(None::<Option<u8>>));
+    assert!(from_key::<Option<u8>>(vec![2, 0]).is_err());
+}
+
+/// `None` before every value, like a plain `Option` and SQL `NULLS FIRST`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NullsFirst<T>(pub Option<T>);
+
+/// `None` after every value, like SQL `NULLS LAST`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct NullsLast<T>(pub Option<T>);
+
+impl<T: IndexKey> IndexKey for NullsFirst<T> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        self.0.to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        Ok(NullsFirst(Option::from_key(key)?))
+    }
+}
+
+/// a presence byte, 0 for `Some` and 1 for `None`.
+impl<T: IndexKey> IndexKey for NullsLast<T> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        match self.0 {
+            Some(value) => {
+                result.write_all(&[0])?;
+                value.to_key(result)?;
+            }
+            None => result.write_all(&[1])?,
+        }
+        Ok(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let mut tag = [0];
+        key.read_exact(&mut tag)?;
+        match tag[0] {
+            0 => Ok(NullsLast(Some(T::from_key(key)?))),
+            1 => Ok(NullsLast(None)),
+            _ => Err(Error::new(
+                std::io::ErrorKind::InvalidData,
+                "invalid option tag",
+            )),
+        }
+    }
+}
+
+#[test]
+fn test_nulls_first_last() {
+    let values = [None, Some(-1i16), Some(0), Some(7)];
+    let first: Vec<_> = values.iter().map(|v| to_key(NullsFirst(*v))).collect();
+    assert!(first.windows(2).all(|w| w[0] < w[1]));
+    let mut last: Vec<_> = values
+        .iter()
+        .map(|v| to_key((NullsLast(*v), 0u8)))
+        .collect();
+    last.rotate_left(1);
+    assert!(last.windows(2).all(|w| w[0] < w[1]));
+    for v in values {
+        assert_eq!(
+            from_key::<NullsFirst<i16>>(to_key(NullsFirst(v)))
+                .unwrap()
+                .0,
+            v
+        );
+        assert_eq!(
+            from_key::<NullsLast<i16>>(to_key(NullsLast(v))).unwrap().0,
+            v
+        );
+    }
+    assert!(from_key::<NullsLast<u8>>(vec![2]).is_err());
+}
+
+/// read as a lower bound: `Unbounded` first, then by value with `Included(x)`
+/// before `Excluded(x)`, which starts just after `x`. `Unbounded` is `0` and
+/// the others are `1`, the value, then `0` for included or `1` for excluded.
+impl<T: IndexKey> IndexKey for Bound<T> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        let (value, excluded) = match self {
+            Bound::Unbounded => {
+                result.write_all(&[0])?;
+                return Ok(result);
+            }
+            Bound::Included(value) => (value, false),
+            Bound::Excluded(value) => (value, true),
+        };
+        result.write_all(&[1])?;
+        value.to_key(result)?;
+        result.write_all(&[excluded as u8])?;
+        Ok(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        match <Option<(T, u8)>>::from_key(key)? {
+            None => Ok(Bound::Unbounded),
+            Some((value, 0)) => Ok(Bound::Included(value)),
+            Some((value, 1)) => Ok(Bound::Excluded(value)),
+            Some(_) => Err(Error::new(ErrorKind::InvalidData, "invalid bound tag")),
+        }
+    }
+}
+
+/// a bound read as an upper bound: by value with `Excluded(x)` before
+/// `Included(x)`, and `Unbounded` last.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct UpperBound<T>(pub Bound<T>);
+
+impl<T: IndexKey> IndexKey for UpperBound<T> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        let (value, included) = match self.0 {
+            Bound::Unbounded => {
+                result.write_all(&[1])?;
+                return Ok(result);
+            }
+            Bound::Excluded(value) => (value, false),
+            Bound::Included(value) => (value, true),
+        };
+        result.write_all(&[0])?;
+        value.to_key(result)?;
+        result.write_all(&[included as u8])?;
+        Ok(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        match <NullsLast<(T, u8)>>::from_key(key)?.0 {
+            None => Ok(UpperBound(Bound::Unbounded)),
+            Some((value, 0)) => Ok(UpperBound(Bound::Excluded(value))),
+            Some((value, 1)) => Ok(UpperBound(Bound::Included(value))),
+            Some(_) => Err(Error::new(ErrorKind::InvalidData, "invalid bound tag")),
+        }
+    }
+}
+
+/// `(start, end)`, so ranges sort by start and then end.
+impl<T: IndexKey> IndexKey for RangeInclusive<T> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        let (start, end) = self.into_inner();
+        (start, end).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let (start, end) = <(T, T)>::from_key(key)?;
+        Ok(start..=end)
+    }
+}
+
+#[test]
+fn test_bound() {
+    let lower = [
+        Bound::Unbounded,
+        Bound::Included(-1i32),
+        Bound::Excluded(-1),
+        Bound::Included(0),
+        Bound::Excluded(0),
+        Bound::Included(7),
+    ];
+    let keys: Vec<_> = lower.iter().map(|b| to_key((*b, 0u8))).collect();
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    for b in lower {
+        assert_eq!(from_key::<Bound<i32>>(to_key(b)).unwrap(), b);
+    }
+    let upper = [
+        UpperBound(Bound::Excluded(-1i32)),
+        UpperBound(Bound::Included(-1)),
+        UpperBound(Bound::Excluded(5)),
+        UpperBound(Bound::Included(5)),
+        UpperBound(Bound::Unbounded),
+    ];
+    let keys: Vec<_> = upper.iter().map(|b| to_key((*b, 0u8))).collect();
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    for b in upper {
+        assert_eq!(from_key::<UpperBound<i32>>(to_key(b)).unwrap(), b);
+    }
+    assert!(from_key::<Bound<u8>>(vec![1, 5, 2]).is_err());
+    assert!(from_key::<UpperBound<u8>>(vec![0, 5, 2]).is_err());
+
+    let windows = [10u32..=20, 10..=25, 11..=12];
+    let keys: Vec<_> = windows.iter().map(|r| to_key(r.clone())).collect();
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(
+        from_key::<(RangeInclusive<u32>, String)>(to_key((3..=4u32, "a".to_owned()))).unwrap(),
+        (3..=4, "a".to_owned())
+    );
+}
+
+macro_rules! impl_tuple {
+    ( $( $v:ident ),+ ) => {
+        impl< $( $v ),+ > IndexKey for ( $($v),+ )
+        where
+            $( $v : IndexKey ,)+
+        {
+            #[inline]
+            #[allow(non_snake_case)]
+            fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+                let ($( $v,)+) = self;
+                $(
+                    $v.to_key(result)?;
+                )+
+                Ok(result)
+            }
+            #[inline]
+            fn from_key<R: Read>(key: &mut R) -> Result<( $($v),+ ), Error> {
+                Ok(( $(
+                    $v::from_key(key)?,
+                )+ ))
+            }
+        }
+    }
+}
+
+impl_tuple!(T1, T2);
+impl_tuple!(T1, T2, T3);
+impl_tuple!(T1, T2, T3, T4);
+impl_tuple!(T1, T2, T3, T4, T5);
+impl_tuple!(T1, T2, T3, T4, T5, T6);
+impl_tuple!(T1, T2, T3, T4, T5, T6, T7);
+impl_tuple!(T1, T2, T3, T4, T5, T6, T7, T8);
+impl_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9);
+impl_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
+impl_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11);
+impl_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12);
+impl_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13);
+impl_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14);
+impl_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15);
+impl_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15, T16);
+
+/// elements back to back with no length or terminator, since `N` is fixed.
+impl<T: IndexKey, const N: usize> IndexKey for [T; N] {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        for item in self {
+            item.to_key(result)?;
+        }
+        Ok(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let mut items = Vec::with_capacity(N);
+        for _ in 0..N {
+            items.push(T::from_key(key)?);
+        }
+        match <[T; N]>::try_from(items) {
+            Ok(array) => Ok(array),
+            Err(_) => unreachable!("read exactly N items"),
+        }
+    }
+}
+
+#[test]
+fn test_array() {
+    let id = [7u8; 16];
+    assert_eq!(to_key(id), id.to_vec());
+    assert_eq!(from_key::<[u8; 16]>(to_key(id)).unwrap(), id);
+    let keys: Vec<_> = [[0u32, 0, 0, 1], [0, 0, 1, 0], [1, 0, 0, 0]]
+        .iter()
+        .map(|a| to_key((*a, 0u8)))
+        .collect();
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    let words = ["a".to_owned(), "".to_owned()];
+    assert_eq!(
+        from_key::<([String; 2], u8)>(to_key((words.clone(), 3u8))).unwrap(),
+        (words, 3)
+    );
+    assert_eq!(to_key([0u8; 0]), vec![]);
+    assert!(from_key::<[u16; 2]>(vec![0, 1, 0]).is_err());
+}
+
+/// a list of any key type, compared element-wise with a shorter prefix first.
+/// every element is preceded by a 1 and the list ends with a 0, which works
+/// because element keys are prefix-free. `Vec<u8>` itself keeps its escaped
+/// byte-string encoding; wrap it to get this one.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Seq<T>(pub Vec<T>);
+
+impl<T: IndexKey> IndexKey for Seq<T> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        for item in self.0 {
+            result.write_all(&[1])?;
+            item.to_key(result)?;
+        }
+        result.write_all(&[0])?;
+        Ok(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let mut items = vec![];
+        loop {
+            let mut tag = [0];
+            key.read_exact(&mut tag)?;
+            match tag[0] {
+                0 => return Ok(Seq(items)),
+                1 => items.push(T::from_key(key)?),
+                _ => return Err(Error::new(ErrorKind::InvalidData, "invalid list tag")),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_seq() {
+    let lists: Vec<Vec<i32>> = vec![
+        vec![],
+        vec![-5],
+        vec![-5, 0],
+        vec![-5, 1],
+        vec![0],
+        vec![1, -1],
+    ];
+    let keys: Vec<_> = lists
+        .iter()
+        .map(|l| to_key((Seq(l.clone()), 0u8)))
+        .collect();
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    let words = Seq(vec!["ab".to_owned(), "".to_owned(), "b".to_owned()]);
+    assert!(to_key(Seq(vec!["a".to_owned(), "c".to_owned()])) < to_key(words.clone()));
+    assert_eq!(
+        from_key::<(u8, Seq<String>, Seq<Seq<u8>>)>(to_key((
+            1u8,
+            words.clone(),
+            Seq(vec![Seq(vec![2u8])])
+        )))
+        .unwrap(),
+        (1, words, Seq(vec![Seq(vec![2])]))
+    );
+    assert!(from_key::<Seq<u8>>(vec![2]).is_err());
+    assert!(from_key::<Seq<u8>>(vec![1, 5]).is_err());
+}
+
+/// the elements in ascending order, tagged as in [`Seq`]. decoding rejects
+/// elements out of order or repeated, so each set has one key.
+impl<T: IndexKey + Ord> IndexKey for std::collections::BTreeSet<T> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        Seq(self.into_iter().collect()).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let Seq(items) = Seq::<T>::from_key(key)?;
+        if !items.windows(2).all(|w| w[0] < w[1]) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "set elements out of order",
+            ));
+        }
+        Ok(items.into_iter().collect())
+    }
+}
+
+/// the entries in ascending key order as a [`Seq`] of `(key, value)`.
+/// decoding rejects keys out of order or repeated.
+impl<K: IndexKey + Ord, V: IndexKey> IndexKey for std::collections::BTreeMap<K, V> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        Seq(self.into_iter().collect()).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let Seq(entries) = Seq::<(K, V)>::from_key(key)?;
+        if !entries.windows(2).all(|w| w[0].0 < w[1].0) {
+            return Err(Error::new(ErrorKind::InvalidData, "map keys out of order"));
+        }
+        Ok(entries.into_iter().collect())
+    }
+}
+
+#[test]
+fn test_btree() {
+    use std::collections::{BTreeMap, BTreeSet};
+    let set =
+        |items: &[&str]| -> BTreeSet<String> { items.iter().map(|s| s.to_string()).collect() };
+    let sets = [
+        set(&[]),
+        set(&["a"]),
+        set(&["a", "b"]),
+        set(&["a", "c"]),
+        set(&["b"]),
+    ];
+    let keys: Vec<_> = sets.iter().map(|s| to_key((s.clone(), 0u8))).collect();
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    assert!(sets.windows(2).all(|w| w[0] < w[1]));
+    for s in &sets {
+        assert_eq!(from_key::<BTreeSet<String>>(to_key(s.clone())).unwrap(), *s);
+    }
+    assert_eq!(
+        to_key(set(&["b", "a"])),
+        to_key(Seq(vec!["a".to_owned(), "b".to_owned()]))
+    );
+    assert!(from_key::<BTreeSet<u8>>(to_key(Seq(vec![2u8, 1]))).is_err());
+    assert!(from_key::<BTreeSet<u8>>(to_key(Seq(vec![1u8, 1]))).is_err());
+
+    let map: BTreeMap<u8, String> = [(2, "b".to_owned()), (1, "a".to_owned())].into();
+    assert_eq!(
+        from_key::<(BTreeMap<u8, String>, u8)>(to_key((map.clone(), 9u8))).unwrap(),
+        (map.clone(), 9)
+    );
+    let mut bigger = map.clone();
+    bigger.insert(3, String::new());
+    assert!(to_key(map) < to_key(bigger));
+    assert!(from_key::<BTreeMap<u8, u8>>(to_key(Seq(vec![(1u8, 0u8), (1, 1)]))).is_err());
+}
+
+/// frames the inner key as an escaped, terminated byte string, so
+/// `(Nested((a, b)), c)` and `(a, Nested((b, c)))` encode differently where
+/// the plain tuples flatten to the same bytes. ordering among the inner keys is
+/// kept.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Nested<T>(pub T);
+
+impl<T: IndexKey> IndexKey for Nested<T> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        let mut inner = vec![];
+        self.0.to_key(&mut inner)?;
+        inner.to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let inner = Vec::<u8>::from_key(key)?;
+        let mut cursor = Cursor::new(&inner[..]);
+        let value = T::from_key(&mut cursor)?;
+        if cursor.position() != inner.len() as u64 {
+            return Err(Error::new(
+                std::io::ErrorKind::InvalidData,
+                "trailing bytes in nested key",
+            ));
+        }
+        Ok(Nested(value))
+    }
+}
+
+#[test]
+fn test_nested() {
+    let left = to_key((Nested((1u8, 2u8)), 3u8));
+    let right = to_key((1u8, Nested((2u8, 3u8))));
+    assert_eq!(to_key(((1u8, 2u8), 3u8)), to_key((1u8, (2u8, 3u8))));
+    assert_ne!(left, right);
+    assert_eq!(
+        from_key::<(Nested<(u8, u8)>, u8)>(left).unwrap(),
+        (Nested((1, 2)), 3)
+    );
+    assert!(from_key::<(Nested<(u8, u8)>, u8)>(right).is_err());
+    let err = try_to_key(Nested((1u8, Rejected))).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    let keys: Vec<_> = [(0u16, 0u16), (0, 1), (1, 0), (256, 0), (u16::MAX, 0)]
+        .iter()
+        .map(|t| to_key((Nested(*t), 0u8)))
+        .collect();
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+}
+
+/// sorts `T` in descending order by inverting every byte of its key. this is
+/// only correct because keys are prefix-free: no key is a proper prefix of
+/// another, so inverting never turns "shorter sorts first" around.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Desc<T>(pub T);
+
+/// un-inverts bytes as they are read.
+struct Inverted<'a, R>(&'a mut R);
+
+impl<R: Read> Read for Inverted<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let n = self.0.read(buf)?;
+        buf[..n].iter_mut().for_each(|b| *b = !*b);
+        Ok(n)
+    }
+}
+
+impl<T: IndexKey> IndexKey for Desc<T> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        let mut key = vec![];
+        self.0.to_key(&mut key)?;
+        key.iter_mut().for_each(|b| *b = !*b);
+        result.write_all(&key)?;
+        Ok(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        Ok(Desc(T::from_key(&mut Inverted(key))?))
+    }
+}
+
+/// the same bytes as [`Desc`].
+impl<T: IndexKey> IndexKey for std::cmp::Reverse<T> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        Desc(self.0).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        Ok(std::cmp::Reverse(Desc::from_key(key)?.0))
+    }
+}
+
+#[test]
+fn test_desc() {
+    let values = ["", "a", "a\0", "ab", "b"];
+    let mut keys: Vec<_> = values
+        .iter()
+        .map(|s| to_key((Desc(s.to_string()), 1u8)))
+        .collect();
+    assert!(keys.windows(2).all(|w| w[0] > w[1]));
+    keys.sort();
+    assert_eq!(
+        from_key::<(Desc<String>, u8)>(keys[0].clone()).unwrap(),
+        (Desc("b".to_owned()), 1)
+    );
+    let scores: Vec<_> = [f64::NEG_INFINITY, -1.0, 0.0, 2.5, f64::INFINITY]
+        .iter()
+        .map(|f| to_key(Desc(*f)))
+        .collect();
+    assert!(scores.windows(2).all(|w| w[0] > w[1]));
+    assert_eq!(from_key::<Desc<f64>>(scores[3].clone()).unwrap(), Desc(2.5));
+    let err = try_to_key((1u8, Desc(Rejected))).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    use std::cmp::Reverse;
+    let mut events = vec![(1u8, Reverse(10u64)), (0, Reverse(5)), (1, Reverse(20))];
+    let mut keys: Vec<_> = events.iter().map(|e| to_key(*e)).collect();
+    keys.sort();
+    events.sort();
+    let decoded: Vec<_> = keys
+        .into_iter()
+        .map(|k| from_key::<(u8, Reverse<u64>)>(k).unwrap())
+        .collect();
+    assert_eq!(decoded, events);
+    assert_eq!(to_key(Reverse(1u8)), to_key(Desc(1u8)));
+    let err = try_to_key((Reverse(Rejected), 1u8)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+/// the same key as the boxed value.
+impl<T: IndexKey> IndexKey for Box<T> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        (*self).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        T::from_key(key).map(Box::new)
+    }
+}
+
+/// the same key as the shared value, cloned out only if still shared.
+impl<T: IndexKey + Clone> IndexKey for std::rc::Rc<T> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        std::rc::Rc::unwrap_or_clone(self).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        T::from_key(key).map(std::rc::Rc::new)
+    }
+}
+
+/// the same key as the shared value, cloned out only if still shared.
+impl<T: IndexKey + Clone> IndexKey for std::sync::Arc<T> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        std::sync::Arc::unwrap_or_clone(self).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        T::from_key(key).map(std::sync::Arc::new)
+    }
+}
+
+#[test]
+fn test_smart_pointers() {
+    use std::rc::Rc;
+    use std::sync::Arc;
+    let name = "ä".to_owned();
+    assert_eq!(to_key(Box::new(name.clone())), to_key(name.clone()));
+    let shared = Rc::new(name.clone());
+    let other = Rc::clone(&shared);
+    assert_eq!(to_key(shared), to_key(name.clone()));
+    assert_eq!(*other, name);
+    assert_eq!(to_key(Arc::new((1u8, 2u16))), to_key((1u8, 2u16)));
+    assert_eq!(
+        from_key::<(Box<u8>, Rc<String>, Arc<i32>)>(to_key((1u8, name.clone(), -3i32))).unwrap(),
+        (Box::new(1), Rc::new(name), Arc::new(-3))
+    );
+}
+
+/// the same key as the wrapped integer.
+impl<T: IndexKey> IndexKey for std::num::Wrapping<T> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        self.0.to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        T::from_key(key).map(std::num::Wrapping)
+    }
+}
+
+/// the same key as the wrapped integer.
+impl<T: IndexKey> IndexKey for std::num::Saturating<T> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        self.0.to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        T::from_key(key).map(std::num::Saturating)
+    }
+}
+
+#[test]
+fn test_wrapping_saturating() {
+    use std::num::{Saturating, Wrapping};
+    let counters = [
+        Wrapping(-3i32),
+        Wrapping(0),
+        Wrapping(i32::MAX) + Wrapping(1),
+    ];
+    assert_eq!(to_key(counters[2]), to_key(i32::MIN));
+    assert!(to_key(counters[0]) < to_key(counters[1]));
+    assert_eq!(to_key(Saturating(u8::MAX) + Saturating(1)), to_key(u8::MAX));
+    assert_eq!(
+        from_key::<(Wrapping<u64>, Saturating<i16>)>(to_key((Wrapping(9u64), Saturating(-2i16))))
+            .unwrap(),
+        (Wrapping(9), Saturating(-2))
+    );
+}
+
+#[test]
+fn test_tuple16() {
+    type Wide = (
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        String,
+        u8,
+    );
+    let wide = |last: u8, name: &str| -> Wide {
+        (
+            1,
+            2,
+            3,
+            4,
+            5,
+            6,
+            7,
+            8,
+            9,
+            10,
+            11,
+            12,
+            13,
+            14,
+            name.to_owned(),
+            last,
+        )
+    };
+    let keys = [wide(9, "a"), wide(0, "ab"), wide(0, "b"), wide(1, "b")].map(to_key);
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    for key in &keys {
+        assert_eq!(&to_key(from_key::<Wide>(key.clone()).unwrap()), key);
+    }
+    let (_, _, _, _, _, _, _, _, _, _, _, _, _, _, name, last) =
+        from_key::<Wide>(keys[1].clone()).unwrap();
+    assert_eq!((name.as_str(), last), ("ab", 0));
+}
+
+#[test]
+fn test_tuple() {
+    let list1: Vec<u8> = vec![1, 2, 1, 2, 0];
+    let list2: Vec<u8> = vec![1, 2, 1, 2, 2];
+    let string: String = "123".to_owned();
+    let key = to_key((
+        list1.clone(),
+        list2.clone(),
+        string.clone(),
+        true,
+        1.0f32,
+        1i64,
+    ));
+
+    let (l1, l2, s, b, f, i): (Vec<u8>, Vec<u8>, String, bool, f32, i64) = from_key(key).unwrap();
+    assert_eq!(list1, l1);
+    assert_eq!(list2, l2);
+    assert_eq!(string, s);
+    assert!(b);
+    assert_eq!(1.0f32, f);
+    assert_eq!(1i64, i);
+}
+
+#[test]
+fn test_tuple2() {
+    let it = VecRange(vec![], 1);
+    for (a1, _) in it {
+        let it2 = VecRange(vec![], 1);
+        for (a2, _) in it2 {
+            for b1 in [0u16, 1, u16::MAX].iter() {
+                for b2 in [0u16, 1, u16::MAX].iter() {
+                    let (b1, b2) = (*b1, *b2);
+                    if a1 < a2 {
+                        assert!(to_key((a1.clone(), b1)) < to_key((a2.clone(), b2)));
+                        continue;
+                    }
+                    if a1 > a2 {
+                        assert!(to_key((a1.clone(), b1)) > to_key((a2.clone(), b2)));
+                        continue;
+                    }
+                    assert!(b1.cmp(&b2) == to_key((a1.clone(), b1)).cmp(&to_key((a2.clone(), b2))))
+                }
+            }
+        }
+    }
+}
+
+pub fn escape_encode<'a, R: Read, W: Write>(
+    src: &mut R,
+    result: &'a mut W,
+) -> Result<&'a mut W, Error> {
+    let mut buf = [0u8];
+    while src.read_exact(&mut buf).is_ok() {
+        if buf[0] <= codec::ESCAPE {
+            result.write_all(&[codec::ESCAPE])?;
+        }
+        result.write_all(&buf)?;
+    }
+    result.write_all(&[codec::TERMINATOR])?;
+    Ok(result)
+}
+
+pub fn escape_decode<'a, R: Read, W: Write>(
+    src: &mut R,
+    result: &'a mut W,
+) -> Result<&'a mut W, Error> {
+    let mut state = Unescaper::default();
+    let mut buf = [0u8];
+    let limit = FIELD_LIMIT.with(Cell::get);
+    let mut written = 0usize;
+    loop {
+        match src.read_exact(&mut buf) {
+            Ok(()) => (),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(result),
+            Err(e) => return Err(e),
+        }
+        match state.step(buf[0]) {
+            Step::Byte(byte) => {
+                written += 1;
+                if written > limit {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        "field exceeds max_bytes_field",
+                    ));
+                }
+                result.write_all(&[byte])?
+            }
+            Step::Escape => (),
+            Step::End => return Ok(result),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum DecodeError {
+    /// the field is longer than the buffer. the buffer holds its first bytes
+    /// and `src` is left inside the field.
+    Overflow,
+    Io(Error),
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DecodeError::Overflow => f.write_str("field overflows decode buffer"),
+            DecodeError::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<Error> for DecodeError {
+    fn from(e: Error) -> DecodeError {
+        DecodeError::Io(e)
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(e: DecodeError) -> Error {
+        match e {
+            DecodeError::Overflow => Error::new(ErrorKind::InvalidData, e.to_string()),
+            DecodeError::Io(e) => e,
+        }
+    }
+}
+
+/// [`escape_decode`] into a caller-provided buffer, without allocating;
+/// returns the decoded length.
+pub fn escape_decode_into<R: Read>(src: &mut R, buf: &mut [u8]) -> Result<usize, DecodeError> {
+    let mut state = Unescaper::default();
+    let mut byte = [0u8];
+    let mut len = 0;
+    loop {
+        match src.read_exact(&mut byte) {
+            Ok(()) => (),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(len),
+            Err(e) => return Err(e.into()),
+        }
+        match state.step(byte[0]) {
+            Step::Byte(b) => {
+                *buf.get_mut(len).ok_or(DecodeError::Overflow)? = b;
+                len += 1;
+            }
+            Step::Escape => (),
+            Step::End => return Ok(len),
+        }
+    }
+}
+
+#[test]
+fn test_escape_decode_into() {
+    let key = to_key((vec![0u8, 1, 2], 9u8));
+    let mut src = &key[..];
+    let mut buf = [0u8; 3];
+    assert_eq!(escape_decode_into(&mut src, &mut buf).unwrap(), 3);
+    assert_eq!(buf, [0, 1, 2]);
+    assert_eq!(src, &[9]);
+
+    let mut src = &key[..];
+    let mut small = [0u8; 2];
+    let err = escape_decode_into(&mut src, &mut small).unwrap_err();
+    assert!(matches!(err, DecodeError::Overflow));
+    assert_eq!(small, [0, 1]);
+    assert_eq!(Error::from(err).kind(), ErrorKind::InvalidData);
+    assert_eq!(escape_decode_into(&mut &[0u8][..], &mut []).unwrap(), 0);
+}
+
+/// encodes `i` into a new key, panicking if `i` cannot be encoded; see
+/// [`try_to_key`] for values that may be rejected.
+pub fn to_key<I: IndexKey>(i: I) -> Vec<u8> {
+    try_to_key(i).expect("key encoding failed")
+}
+
+pub fn try_to_key<I: IndexKey>(i: I) -> Result<Vec<u8>, Error> {
+    let mut result = vec![];
+    i.to_key(&mut result)?;
+    #[cfg(feature = "metrics")]
+    crate::metrics::encoded::<I>(result.len());
+    #[cfg(feature = "audit")]
+    crate::audit::encoded::<I>(&result);
+    Ok(result)
+}
+
+pub fn from_key<I: IndexKey>(src: Vec<u8>) -> Result<I, Error> {
+    #[cfg(feature = "metrics")]
+    let len = src.len();
+    let mut cur = Cursor::new(src);
+    let result = I::from_key(&mut cur);
+    #[cfg(feature = "metrics")]
+    crate::metrics::decoded::<I>(len, result.is_ok());
+    #[cfg(feature = "audit")]
+    crate::audit::decoded::<I>(cur.get_ref(), result.is_ok());
+    result
+}
+
+/// a value that always fails to encode.
+#[cfg(test)]
+struct Rejected;
+
+#[cfg(test)]
+impl IndexKey for Rejected {
+    fn to_key<W: Write>(self, _: &mut W) -> Result<&mut W, Error> {
+        Err(Error::new(ErrorKind::InvalidInput, "rejected"))
+    }
+    fn from_key<R: Read>(_: &mut R) -> Result<Self, Error> {
+        Ok(Rejected)
+    }
+}
+
+#[test]
+fn test_try_to_key() {
+    assert_eq!(try_to_key((1u8, 2u8)).unwrap(), to_key((1u8, 2u8)));
+    let err = try_to_key((1u8, Rejected)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(std::panic::catch_unwind(|| to_key(Rejected)).is_err());
+}
+
+thread_local! {
+    static FIELD_LIMIT: Cell<usize> = const { Cell::new(usize::MAX) };
+}
+
+/// limits for decoding untrusted keys. `max_bytes_field` caps every decoded
+/// byte string and string, `max_total` the encoded bytes read in all. either
+/// being exceeded is an `InvalidData` error instead of unbounded allocation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DecodeOptions {
+    pub max_bytes_field: usize,
+    pub max_total: usize,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> DecodeOptions {
+        DecodeOptions {
+            max_bytes_field: usize::MAX,
+            max_total: usize::MAX,
+        }
+    }
+}
+
+struct Limited<'a, R> {
+    inner: &'a mut R,
+    left: usize,
+}
+
+impl<R: Read> Read for Limited<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        if self.left == 0 && !buf.is_empty() {
+            // a short read would look like a clean end of key.
+            let mut probe = [0u8];
+            return match self.inner.read(&mut probe)? {
+                0 => Ok(0),
+                _ => Err(Error::new(ErrorKind::InvalidData, "key exceeds max_total")),
+            };
+        }
+        let len = buf.len().min(self.left);
+        let read = self.inner.read(&mut buf[..len])?;
+        self.left -= read;
+        Ok(read)
+    }
+}
+
+struct RestoreLimit(usize);
+
+impl Drop for RestoreLimit {
+    fn drop(&mut self) {
+        FIELD_LIMIT.with(|limit| limit.set(self.0));
+    }
+}
+
+impl DecodeOptions {
+    pub fn decode<I: IndexKey, R: Read>(&self, key: &mut R) -> Result<I, Error> {
+        let _restore = RestoreLimit(FIELD_LIMIT.with(|limit| limit.replace(self.max_bytes_field)));
+        I::from_key(&mut Limited {
+            inner: key,
+            left: self.max_total,
+        })
+    }
+}
+
+pub fn from_key_with<I: IndexKey>(src: Vec<u8>, options: &DecodeOptions) -> Result<I, Error> {
+    if src.len() > options.max_total {
+        return Err(Error::new(ErrorKind::InvalidData, "key exceeds max_total"));
+    }
+    options.decode(&mut Cursor::new(src))
+}
+
+#[test]
+fn test_decode_options() {
+    let options = DecodeOptions {
+        max_bytes_field: 4,
+        max_total: 18,
+    };
+    let key = to_key(("abcd".to_owned(), vec![0u8; 4], 7u32));
+    assert_eq!(
+        from_key_with::<(String, Vec<u8>, u32)>(key.clone(), &options).unwrap(),
+        ("abcd".to_owned(), vec![0; 4], 7)
+    );
+    let long = to_key(("abcde".to_owned(), 1u8));
+    assert!(from_key_with::<(String, u8)>(long.clone(), &options).is_err());
+    assert!(from_key::<(String, u8)>(long).is_ok());
+
+    let big = to_key(vec![b'x'; 1 << 20]);
+    let small = DecodeOptions {
+        max_total: 64,
+        ..DecodeOptions::default()
+    };
+    let err = from_key_with::<Vec<u8>>(big.clone(), &small);
+    assert_eq!(err.unwrap_err().kind(), ErrorKind::InvalidData);
+    // streamed, the limit stops reading instead of buffering the whole field.
+    let mut stream = &big[..];
+    assert!(small.decode::<Vec<u8>, _>(&mut stream).is_err());
+    assert!(stream.len() > big.len() - 128);
+    assert_eq!(FIELD_LIMIT.with(Cell::get), usize::MAX);
+}
+
+/// decodes a batch on the rayon thread pool. the output keeps input order;
+/// if several keys fail, the error of any one of them is returned.
+#[cfg(feature = "rayon")]
+pub fn from_keys_par<I: IndexKey + Send>(keys: Vec<Vec<u8>>) -> Result<Vec<I>, Error> {
+    use rayon::prelude::*;
+    keys.into_par_iter().map(from_key).collect()
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_from_keys_par() {
+    let keys: Vec<Vec<u8>> = (0..10_000u32).map(|i| to_key((i, i.to_string()))).collect();
+    let decoded: Vec<(u32, String)> = from_keys_par(keys).unwrap();
+    assert!(decoded
+        .iter()
+        .enumerate()
+        .all(|(i, (n, s))| *n as usize == i && *s == i.to_string()));
+    assert!(from_keys_par::<u32>(vec![]).unwrap().is_empty());
+    assert!(from_keys_par::<u32>(vec![to_key(1u32), vec![1]]).is_err());
+}
+
+/// encode into a scratch buffer and hand the finished key to `result` in a
+/// single `write_all`, so a failing encoder never leaves a partial key behind.
+pub fn to_key_atomic<I: IndexKey, W: Write>(i: I, result: &mut W) -> Result<&mut W, Error> {
+    let mut scratch = vec![];
+    i.to_key(&mut scratch)?;
+    result.write_all(&scratch)?;
+    Ok(result)
+}
+
+#[cfg(test)]
+struct FailAfter(Vec<u8>, usize);
+
+#[cfg(test)]
+impl Write for FailAfter {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        if self.1 == 0 {
+            return Err(Error::other("fail"));
+        }
+        self.1 -= 1;
+        self.0.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_to_key_atomic() {
+    let mut w = FailAfter(vec![], 1);
+    assert!((1u8, 2u8).to_key(&mut w).is_err());
+    assert_eq!(w.0, vec![1]);
+
+    let mut w = FailAfter(vec![], 0);
+    assert!(to_key_atomic((1u8, 2u8), &mut w).is_err());
+    assert!(w.0.is_empty());
+
+    let mut w = FailAfter(vec![], 1);
+    to_key_atomic((1u8, 2u8), &mut w).unwrap();
+    assert_eq!(w.0, to_key((1u8, 2u8)));
+}
+
+struct FnWriter<F: FnMut(&[u8])>(F);
+
+impl<F: FnMut(&[u8])> Write for FnWriter<F> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        (self.0)(buf);
+        Ok(buf.len())
+    }
+    #[inline]
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// stream the key bytes of `i` into `f` chunk by chunk without building a `Vec`.
+pub fn write_with<I: IndexKey, F: FnMut(&[u8])>(i: I, f: F) -> Result<(), Error> {
+    i.to_key(&mut FnWriter(f))?;
+    Ok(())
+}
+
+#[test]
+fn test_write_with() {
+    let value = (1u32, "ab".to_owned(), -1i8);
+    let mut collected = vec![];
+    let mut chunks = 0;
+    write_with(value.clone(), |chunk| {
+        chunks += 1;
+        collected.extend_from_slice(chunk);
+    })
+    .unwrap();
+    assert_eq!(collected, to_key(value));
+    assert!(chunks > 1);
+}
@@ -0,0 +1,245 @@
+//! hashed key fields scoped to a tenant secret.
+//!
+//! a [`KeyedHasher`] holds a tenant's secret and derives [`Hashed`] and
+//! [`Sharded`] fields with HMAC-SHA-256 instead of a bare hash, so the same
+//! value hashes differently for every tenant and keys cannot be linked across
+//! them. secrets carry a version: after [`KeyedHasher::rotate`] the previous
+//! secret is kept, and the `_all` methods return keys under both so a
+//! migration can dual-write and look up either until
+//! [`KeyedHasher::finish_rotation`].
+
+use crate::digest::hmac_sha256;
+use crate::{to_key, IndexKey};
+use std::fmt;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::marker::PhantomData;
+
+// domain tags, so hashes and shard numbers of one value are unrelated.
+const HASHED: u8 = 0;
+const SHARDED: u8 = 1;
+
+#[derive(Clone)]
+struct Secret {
+    version: u32,
+    bytes: Vec<u8>,
+}
+
+impl Secret {
+    fn mac(&self, tag: u8, key: &[u8]) -> [u8; 32] {
+        hmac_sha256(&self.bytes, &[&[tag][..], key].concat())
+    }
+}
+
+#[derive(Clone)]
+pub struct KeyedHasher {
+    current: Secret,
+    previous: Option<Secret>,
+}
+
+impl fmt::Debug for KeyedHasher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("KeyedHasher")
+            .field("version", &self.current.version)
+            .field("previous", &self.previous.as_ref().map(|s| s.version))
+            .finish()
+    }
+}
+
+impl KeyedHasher {
+    pub fn new(version: u32, secret: &[u8]) -> KeyedHasher {
+        KeyedHasher {
+            current: Secret {
+                version,
+                bytes: secret.to_vec(),
+            },
+            previous: None,
+        }
+    }
+
+    /// switches to a new secret, keeping the current one as previous.
+    pub fn rotate(&mut self, version: u32, secret: &[u8]) -> Result<(), Error> {
+        if version == self.current.version {
+            return Err(Error::new(ErrorKind::InvalidInput, "secret version reused"));
+        }
+        let next = KeyedHasher::new(version, secret).current;
+        self.previous = Some(std::mem::replace(&mut self.current, next));
+        Ok(())
+    }
+
+    /// forgets the previous secret once every key has been migrated.
+    pub fn finish_rotation(&mut self) {
+        self.previous = None;
+    }
+
+    pub fn version(&self) -> u32 {
+        self.current.version
+    }
+
+    fn secrets(&self) -> impl Iterator<Item = &Secret> {
+        std::iter::once(&self.current).chain(&self.previous)
+    }
+
+    pub fn hash<T: IndexKey>(&self, value: T) -> Hashed<T> {
+        Hashed::with(&self.current, &to_key(value))
+    }
+
+    /// the hash under the current secret, then the previous one if rotating.
+    pub fn hash_all<T: IndexKey>(&self, value: T) -> Vec<Hashed<T>> {
+        let key = to_key(value);
+        self.secrets().map(|s| Hashed::with(s, &key)).collect()
+    }
+
+    /// `value` placed in one of `shards` shards, at least 1.
+    pub fn shard<T: IndexKey + Clone>(&self, value: T, shards: u32) -> Sharded<T> {
+        Sharded::with(&self.current, value, shards)
+    }
+
+    /// the placement under the current secret, then the previous one if
+    /// rotating.
+    pub fn shard_all<T: IndexKey + Clone>(&self, value: T, shards: u32) -> Vec<Sharded<T>> {
+        self.secrets()
+            .map(|s| Sharded::with(s, value.clone(), shards))
+            .collect()
+    }
+}
+
+/// a secret version and the first 16 bytes of the HMAC of a value's key. the
+/// value cannot be recovered; only equality lookups work.
+pub struct Hashed<T> {
+    version: u32,
+    digest: [u8; 16],
+    value: PhantomData<fn() -> T>,
+}
+
+impl<T> Hashed<T> {
+    fn with(secret: &Secret, key: &[u8]) -> Hashed<T> {
+        let mut digest = [0; 16];
+        digest.copy_from_slice(&secret.mac(HASHED, key)[..16]);
+        Hashed {
+            version: secret.version,
+            digest,
+            value: PhantomData,
+        }
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn digest(&self) -> &[u8; 16] {
+        &self.digest
+    }
+}
+
+impl<T> Clone for Hashed<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Hashed<T> {}
+
+impl<T> PartialEq for Hashed<T> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.version, self.digest) == (other.version, other.digest)
+    }
+}
+
+impl<T> Eq for Hashed<T> {}
+
+impl<T> fmt::Debug for Hashed<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Hashed(v{}, ", self.version)?;
+        for byte in &self.digest {
+            write!(f, "{:02x}", byte)?;
+        }
+        f.write_str(")")
+    }
+}
+
+impl<T> IndexKey for Hashed<T> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        (self.version, self.digest).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let (version, digest) = <(u32, [u8; 16])>::from_key(key)?;
+        Ok(Hashed {
+            version,
+            digest,
+            value: PhantomData,
+        })
+    }
+}
+
+/// a value behind a shard number derived from the tenant secret, so each
+/// tenant's keys spread over the shards differently.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Sharded<T> {
+    pub shard: u32,
+    pub value: T,
+}
+
+impl<T: IndexKey + Clone> Sharded<T> {
+    fn with(secret: &Secret, value: T, shards: u32) -> Sharded<T> {
+        let mac = secret.mac(SHARDED, &to_key(value.clone()));
+        let head = u64::from_be_bytes([
+            mac[0], mac[1], mac[2], mac[3], mac[4], mac[5], mac[6], mac[7],
+        ]);
+        Sharded {
+            shard: (head % u64::from(shards.max(1))) as u32,
+            value,
+        }
+    }
+}
+
+impl<T: IndexKey> IndexKey for Sharded<T> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        (self.shard, self.value).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let (shard, value) = <(u32, T)>::from_key(key)?;
+        Ok(Sharded { shard, value })
+    }
+}
+
+#[test]
+fn test_keyed_hasher() {
+    use crate::from_key;
+    let acme = KeyedHasher::new(1, b"acme secret");
+    let globex = KeyedHasher::new(1, b"globex secret");
+    let email = || "a@example.com".to_owned();
+    assert_eq!(acme.hash(email()), acme.hash(email()));
+    assert_ne!(acme.hash(email()), globex.hash(email()));
+    assert_ne!(acme.hash(email()), acme.hash("b@example.com".to_owned()));
+    let key = to_key((7u32, acme.hash(email())));
+    assert_eq!(key.len(), 4 + 4 + 16);
+    assert_eq!(
+        from_key::<(u32, Hashed<String>)>(key).unwrap().1,
+        acme.hash(email())
+    );
+
+    let placed: Vec<u32> = (0..64u64).map(|v| acme.shard(v, 4).shard).collect();
+    assert!(placed.iter().all(|s| *s < 4));
+    assert!((0..4).all(|s| placed.contains(&s)));
+    let other: Vec<u32> = (0..64u64).map(|v| globex.shard(v, 4).shard).collect();
+    assert_ne!(placed, other);
+    let sharded = acme.shard(9u64, 4);
+    assert_eq!(
+        from_key::<Sharded<u64>>(to_key(sharded.clone())).unwrap(),
+        sharded
+    );
+    assert_eq!(acme.shard(9u64, 0).shard, 0);
+
+    let mut rotating = acme.clone();
+    assert!(rotating.rotate(1, b"again").is_err());
+    rotating.rotate(2, b"acme secret 2").unwrap();
+    assert_eq!(rotating.version(), 2);
+    let both = rotating.hash_all(email());
+    assert_eq!(both.len(), 2);
+    assert_eq!(both[0].version(), 2);
+    assert_eq!(both[1], acme.hash(email()));
+    assert_eq!(rotating.shard_all(9u64, 4)[1], sharded);
+    rotating.finish_rotation();
+    assert_eq!(rotating.hash_all(email()), [rotating.hash(email())]);
+    assert!(!format!("{:?}", rotating).contains("secret"));
+}
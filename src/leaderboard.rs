@@ -0,0 +1,88 @@
+//! score-ordered leaderboard keys.
+//!
+//! entries encode as `(board, Desc(score), member)`, so a forward scan of a
+//! board yields the highest scores first. [`Ties`] decides how equal scores
+//! are ordered; time-based tie breaks put the time before the member.
+
+use crate::range::{prefix_successor, KeyRange};
+use crate::{to_key, Desc, IndexKey};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Ties {
+    /// `(board, Desc(score), member)`.
+    #[default]
+    ByMember,
+    /// `(board, Desc(score), at, member)`: whoever got there first ranks higher.
+    EarliestFirst,
+    /// `(board, Desc(score), Desc(at), member)`.
+    LatestFirst,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Leaderboard<B> {
+    pub board: B,
+    pub ties: Ties,
+}
+
+impl<B: IndexKey + Clone> Leaderboard<B> {
+    pub fn new(board: B, ties: Ties) -> Leaderboard<B> {
+        Leaderboard { board, ties }
+    }
+
+    /// `at` is when `member` reached `score`; ignored with [`Ties::ByMember`].
+    pub fn key<S: IndexKey, M: IndexKey>(&self, score: S, member: M, at: u64) -> Vec<u8> {
+        let head = (self.board.clone(), Desc(score));
+        match self.ties {
+            Ties::ByMember => to_key((head, member)),
+            Ties::EarliestFirst => to_key((head, at, member)),
+            Ties::LatestFirst => to_key((head, Desc(at), member)),
+        }
+    }
+
+    /// the board's keys and how many to take from the front of the scan.
+    pub fn rank_range(&self, top_n: usize) -> (KeyRange, usize) {
+        (KeyRange::prefix(to_key(self.board.clone())), top_n)
+    }
+
+    /// entries scoring at least `score`; counting them gives the rank of the
+    /// first entry below it.
+    pub fn at_least<S: IndexKey>(&self, score: S) -> KeyRange {
+        let board = to_key(self.board.clone());
+        let end = prefix_successor(&to_key((self.board.clone(), Desc(score))));
+        KeyRange { start: board, end }
+    }
+}
+
+#[test]
+fn test_leaderboard() {
+    use crate::from_key;
+    let board = Leaderboard::new(7u32, Ties::ByMember);
+    let mut keys = [
+        board.key(10.5f64, "ann".to_owned(), 0),
+        board.key(99.0f64, "bob".to_owned(), 0),
+        board.key(10.5f64, "abe".to_owned(), 0),
+        board.key(-3.0f64, "cat".to_owned(), 0),
+        Leaderboard::new(8u32, Ties::ByMember).key(1e9f64, "zed".to_owned(), 0),
+    ];
+    keys.sort();
+    let (range, n) = board.rank_range(3);
+    let top: Vec<String> = keys
+        .iter()
+        .filter(|k| range.contains(k))
+        .take(n)
+        .map(|k| from_key::<(u32, Desc<f64>, String)>(k.clone()).unwrap().2)
+        .collect();
+    assert_eq!(top, vec!["bob", "abe", "ann"]);
+    let above = board.at_least(10.5f64);
+    assert_eq!(keys.iter().filter(|k| above.contains(k)).count(), 3);
+
+    for (ties, winner) in [(Ties::EarliestFirst, "early"), (Ties::LatestFirst, "late")] {
+        let board = Leaderboard::new(1u8, ties);
+        let mut keys = [
+            (board.key(5u32, "early".to_owned(), 100), "early"),
+            (board.key(5u32, "late".to_owned(), 200), "late"),
+        ];
+        keys.sort();
+        assert_eq!(keys[0].1, winner);
+    }
+}
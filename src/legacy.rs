@@ -0,0 +1,86 @@
+//! reading keys written by naive encoders.
+//!
+//! [`LegacyLe`] decodes integers stored little-endian in two's complement,
+//! as `to_le_bytes` writes them. such keys do NOT sort numerically, so it is
+//! only for reading and migrating old data: [`fix_order`] rewrites a legacy
+//! key into this crate's order-preserving format.
+
+use crate::{try_to_key, IndexKey};
+use std::io::{Cursor, Error, ErrorKind, Read, Write};
+
+/// an integer encoded little-endian. not order-preserving.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LegacyLe<T>(pub T);
+
+macro_rules! impl_le {
+    ($($t:ty),+) => {
+        $(
+            impl IndexKey for LegacyLe<$t> {
+                fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+                    result.write_all(&self.0.to_le_bytes())?;
+                    Ok(result)
+                }
+                fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+                    let mut bytes = [0u8; std::mem::size_of::<$t>()];
+                    key.read_exact(&mut bytes)?;
+                    Ok(LegacyLe(<$t>::from_le_bytes(bytes)))
+                }
+            }
+        )+
+    };
+}
+
+impl_le!(u16, u32, u64, u128, i16, i32, i64, i128);
+
+/// decodes a whole legacy key as `L`, maps it with `fix`, typically
+/// unwrapping its [`LegacyLe`] fields, and encodes the result.
+pub fn fix_order<L: IndexKey, F: IndexKey>(
+    legacy: &[u8],
+    fix: impl FnOnce(L) -> F,
+) -> Result<Vec<u8>, Error> {
+    let mut cursor = Cursor::new(legacy);
+    let value = L::from_key(&mut cursor)?;
+    if cursor.position() as usize != legacy.len() {
+        return Err(Error::new(ErrorKind::InvalidData, "trailing bytes"));
+    }
+    try_to_key(fix(value))
+}
+
+#[test]
+fn test_legacy_le() {
+    use crate::{from_key, to_key};
+    assert_eq!(to_key(LegacyLe(0x0102u16)), vec![2, 1]);
+    assert_eq!(to_key(LegacyLe(-2i32)), vec![0xfe, 0xff, 0xff, 0xff]);
+    assert_eq!(
+        from_key::<LegacyLe<i64>>(to_key(LegacyLe(-7i64))).unwrap(),
+        LegacyLe(-7)
+    );
+    // 256 sorts before 1 when little-endian.
+    assert!(to_key(LegacyLe(256u32)) < to_key(LegacyLe(1u32)));
+
+    type Old = (LegacyLe<u32>, String, LegacyLe<i16>);
+    let rows = [(256u32, "b", -1i16), (1, "a", 5), (1, "a", -300)];
+    let mut fixed: Vec<Vec<u8>> = rows
+        .iter()
+        .map(|(id, name, delta)| {
+            let old = to_key((LegacyLe(*id), name.to_string(), LegacyLe(*delta)));
+            fix_order(&old, |(LegacyLe(id), name, LegacyLe(delta)): Old| {
+                (id, name, delta)
+            })
+            .unwrap()
+        })
+        .collect();
+    fixed.sort();
+    let decoded: Vec<(u32, String, i16)> =
+        fixed.into_iter().map(|k| from_key(k).unwrap()).collect();
+    assert_eq!(
+        decoded,
+        vec![
+            (1, "a".to_owned(), -300),
+            (1, "a".to_owned(), 5),
+            (256, "b".to_owned(), -1),
+        ]
+    );
+    assert!(fix_order(&[1, 0, 0, 0, 9], |LegacyLe(v): LegacyLe<u32>| v).is_err());
+    assert!(fix_order(&[1, 0], |LegacyLe(v): LegacyLe<u32>| v).is_err());
+}
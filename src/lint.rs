@@ -0,0 +1,142 @@
+//! common key layout mistakes, checked against a [`Schema`].
+
+use crate::schema::{Field, Schema};
+use std::fmt;
+
+/// fixed-width bytes beyond which [`lint_schema`] warns.
+pub const MAX_KEY_BYTES: usize = 256;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum LintWarning {
+    /// a byte string with fields after it: keys have no size bound, and only
+    /// an exact value, not a prefix of one, narrows the later fields.
+    VariableBeforeFields { field: String },
+    /// a leading float: `-0.0` and `0.0` are different keys and NaNs sort
+    /// after infinity.
+    FloatLeading { field: String },
+    /// a leading field named like a timestamp: ever-increasing keys send every
+    /// write to the same end of the keyspace.
+    MonotonicLeading { field: String },
+    /// the fixed-width fields alone exceed `max` bytes.
+    TooWide { bytes: usize, max: usize },
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LintWarning::VariableBeforeFields { field } => write!(
+                f,
+                "{}: variable-length field before other fields, only exact values narrow them",
+                field
+            ),
+            LintWarning::FloatLeading { field } => write!(
+                f,
+                "{}: leading float, -0.0/0.0 differ and NaN sorts last",
+                field
+            ),
+            LintWarning::MonotonicLeading { field } => write!(
+                f,
+                "{}: leading timestamp, writes hotspot on the newest keys",
+                field
+            ),
+            LintWarning::TooWide { bytes, max } => {
+                write!(f, "fixed fields take {} bytes, over {}", bytes, max)
+            }
+        }
+    }
+}
+
+fn is_timestamp(name: &str, field: Field) -> bool {
+    let integer = matches!(
+        field,
+        Field::U32 | Field::U64 | Field::U128 | Field::I32 | Field::I64 | Field::I128
+    );
+    let name = name.to_ascii_lowercase();
+    integer
+        && (["ts", "time", "timestamp", "date", "created", "updated"].contains(&name.as_str())
+            || name.ends_with("_at")
+            || name.ends_with("_ts")
+            || name.ends_with("_time"))
+}
+
+/// warnings for `schema`, in field order.
+pub fn lint_schema(schema: &Schema) -> Vec<LintWarning> {
+    let fields = schema.fields();
+    let mut warnings = vec![];
+    if let Some((name, field)) = fields.first() {
+        if matches!(field, Field::F32 | Field::F64) {
+            warnings.push(LintWarning::FloatLeading {
+                field: name.clone(),
+            });
+        }
+        if is_timestamp(name, *field) {
+            warnings.push(LintWarning::MonotonicLeading {
+                field: name.clone(),
+            });
+        }
+    }
+    for (name, field) in fields.iter().take(fields.len().saturating_sub(1)) {
+        if matches!(field, Field::Bytes | Field::String) {
+            warnings.push(LintWarning::VariableBeforeFields {
+                field: name.clone(),
+            });
+        }
+    }
+    let bytes: usize = fields.iter().map(|(_, field)| field.width()).sum();
+    if bytes > MAX_KEY_BYTES {
+        warnings.push(LintWarning::TooWide {
+            bytes,
+            max: MAX_KEY_BYTES,
+        });
+    }
+    warnings
+}
+
+#[test]
+fn test_lint_schema() {
+    let schema = Schema::new(&[
+        ("created_at", Field::U64),
+        ("name", Field::String),
+        ("id", Field::U32),
+    ])
+    .unwrap();
+    let warnings = lint_schema(&schema);
+    assert_eq!(
+        warnings,
+        vec![
+            LintWarning::MonotonicLeading {
+                field: "created_at".to_owned()
+            },
+            LintWarning::VariableBeforeFields {
+                field: "name".to_owned()
+            },
+        ]
+    );
+    assert!(warnings[1].to_string().starts_with("name: "));
+
+    let good = Schema::new(&[
+        ("tenant", Field::U64),
+        ("ts", Field::U64),
+        ("name", Field::String),
+    ])
+    .unwrap();
+    assert!(lint_schema(&good).is_empty());
+
+    let score = Schema::new(&[("score", Field::F64)]).unwrap();
+    assert_eq!(
+        lint_schema(&score),
+        vec![LintWarning::FloatLeading {
+            field: "score".to_owned()
+        }]
+    );
+
+    let names: Vec<String> = (0..17).map(|i| format!("f{}", i)).collect();
+    let fields: Vec<(&str, Field)> = names.iter().map(|n| (n.as_str(), Field::U128)).collect();
+    assert_eq!(
+        lint_schema(&Schema::new(&fields).unwrap()),
+        vec![LintWarning::TooWide {
+            bytes: 272,
+            max: MAX_KEY_BYTES
+        }]
+    );
+}
@@ -0,0 +1,191 @@
+//! compact, normalized country and language keys.
+
+use crate::IndexKey;
+use std::fmt;
+use std::io::{Error, ErrorKind, Read, Write};
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidInput, msg.to_owned())
+}
+
+/// ISO 3166-1 alpha-2 code, stored as two uppercase ASCII bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CountryCode([u8; 2]);
+
+impl CountryCode {
+    pub fn new(code: &str) -> Result<CountryCode, Error> {
+        match code.as_bytes() {
+            [a, b] if a.is_ascii_alphabetic() && b.is_ascii_alphabetic() => Ok(CountryCode([
+                a.to_ascii_uppercase(),
+                b.to_ascii_uppercase(),
+            ])),
+            _ => Err(invalid("invalid country code")),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.0).unwrap()
+    }
+}
+
+impl fmt::Display for CountryCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl IndexKey for CountryCode {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        result.write_all(&self.0)?;
+        Ok(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let mut code = [0; 2];
+        key.read_exact(&mut code)?;
+        match CountryCode::new(std::str::from_utf8(&code).unwrap_or("")) {
+            Ok(country) if country.0 == code => Ok(country),
+            _ => Err(Error::new(ErrorKind::InvalidData, "invalid country code")),
+        }
+    }
+}
+
+/// BCP 47 `language[-Script][-REGION]` in canonical casing, encoded in a fixed
+/// 10 bytes: language (3), script (4), region (3), each zero padded, so a tag
+/// sorts right before its more specific forms. variants and extensions are
+/// rejected since they do not fit the fixed layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LanguageTag([u8; 10]);
+
+impl LanguageTag {
+    pub fn new(tag: &str) -> Result<LanguageTag, Error> {
+        let mut bytes = [0u8; 10];
+        let mut parts = tag.split(['-', '_']);
+        let language = parts.next().unwrap_or("");
+        if !(2..=3).contains(&language.len()) || !language.bytes().all(|b| b.is_ascii_alphabetic())
+        {
+            return Err(invalid("invalid language subtag"));
+        }
+        for (i, b) in language.bytes().enumerate() {
+            bytes[i] = b.to_ascii_lowercase();
+        }
+        let mut next = parts.next();
+        if let Some(script) =
+            next.filter(|s| s.len() == 4 && s.bytes().all(|b| b.is_ascii_alphabetic()))
+        {
+            for (i, b) in script.bytes().enumerate() {
+                bytes[3 + i] = if i == 0 {
+                    b.to_ascii_uppercase()
+                } else {
+                    b.to_ascii_lowercase()
+                };
+            }
+            next = parts.next();
+        }
+        if let Some(region) = next {
+            let alpha = region.len() == 2 && region.bytes().all(|b| b.is_ascii_alphabetic());
+            let numeric = region.len() == 3 && region.bytes().all(|b| b.is_ascii_digit());
+            if !alpha && !numeric {
+                return Err(invalid("invalid region subtag"));
+            }
+            for (i, b) in region.bytes().enumerate() {
+                bytes[7 + i] = b.to_ascii_uppercase();
+            }
+        }
+        if parts.next().is_some() {
+            return Err(invalid("unsupported language subtags"));
+        }
+        Ok(LanguageTag(bytes))
+    }
+
+    fn field(&self, from: usize, to: usize) -> Option<&str> {
+        let end = self.0[from..to]
+            .iter()
+            .position(|b| *b == 0)
+            .map_or(to, |p| from + p);
+        std::str::from_utf8(&self.0[from..end])
+            .ok()
+            .filter(|s| !s.is_empty())
+    }
+
+    pub fn language(&self) -> &str {
+        self.field(0, 3).unwrap_or("")
+    }
+
+    pub fn script(&self) -> Option<&str> {
+        self.field(3, 7)
+    }
+
+    pub fn region(&self) -> Option<&str> {
+        self.field(7, 10)
+    }
+}
+
+impl fmt::Display for LanguageTag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.language())?;
+        for part in self.script().iter().chain(self.region().iter()) {
+            write!(f, "-{}", part)?;
+        }
+        Ok(())
+    }
+}
+
+impl IndexKey for LanguageTag {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        result.write_all(&self.0)?;
+        Ok(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let mut bytes = [0; 10];
+        key.read_exact(&mut bytes)?;
+        let tag = LanguageTag(bytes);
+        match LanguageTag::new(&tag.to_string()) {
+            Ok(parsed) if parsed == tag => Ok(tag),
+            _ => Err(Error::new(ErrorKind::InvalidData, "invalid language tag")),
+        }
+    }
+}
+
+#[test]
+fn test_locale() {
+    use crate::{from_key, to_key};
+    assert_eq!(CountryCode::new("de").unwrap().as_str(), "DE");
+    assert!(CountryCode::new("DEU").is_err());
+    assert!(CountryCode::new("D1").is_err());
+    assert_eq!(
+        from_key::<CountryCode>(to_key(CountryCode::new("us").unwrap()))
+            .unwrap()
+            .as_str(),
+        "US"
+    );
+    for bad in [vec![b'u', b's'], vec![b'1', b'A'], vec![0xff, 0xff]] {
+        let err = from_key::<CountryCode>(bad).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    let tag = LanguageTag::new("ZH_hant-tw").unwrap();
+    assert_eq!(tag.to_string(), "zh-Hant-TW");
+    assert_eq!(tag.script(), Some("Hant"));
+    assert_eq!(LanguageTag::new("es-419").unwrap().region(), Some("419"));
+    assert_eq!(LanguageTag::new("EN").unwrap().to_string(), "en");
+    assert!(LanguageTag::new("en-US-x-private").is_err());
+    assert!(LanguageTag::new("e").is_err());
+    assert!(LanguageTag::new("en-U").is_err());
+
+    let mut list: Vec<LanguageTag> = ["en-US", "en", "eng", "en-Latn", "de-CH", "en-GB"]
+        .iter()
+        .map(|t| LanguageTag::new(t).unwrap())
+        .collect();
+    list.sort_by_key(|t| {
+        assert_eq!(from_key::<LanguageTag>(to_key(*t)).unwrap(), *t);
+        to_key(*t)
+    });
+    let sorted: Vec<String> = list.iter().map(|t| t.to_string()).collect();
+    assert_eq!(
+        sorted,
+        vec!["de-CH", "en", "en-GB", "en-US", "en-Latn", "eng"]
+    );
+    assert_eq!(to_key(LanguageTag::new("en").unwrap()).len(), 10);
+    let err = from_key::<LanguageTag>(vec![b'e'; 10]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
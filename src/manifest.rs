@@ -0,0 +1,185 @@
+//! backup manifests: which ranges of which keyspaces were copied, with counts and digests.
+
+use crate::digest::DigestBuilder;
+use crate::range::KeyRange;
+use crate::{to_key, IndexKey};
+use std::io::{Error, ErrorKind, Read, Write};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ManifestEntry {
+    pub keyspace: String,
+    pub range: KeyRange,
+    pub count: u64,
+    pub digest: [u8; 32],
+}
+
+impl ManifestEntry {
+    /// digest the sorted keys of `range`; keys outside it are an error.
+    pub fn compute<K: AsRef<[u8]>, I: IntoIterator<Item = K>>(
+        keyspace: String,
+        range: KeyRange,
+        keys: I,
+    ) -> Result<ManifestEntry, Error> {
+        let mut digest = DigestBuilder::new();
+        for key in keys {
+            if !range.contains(key.as_ref()) {
+                return Err(Error::new(ErrorKind::InvalidInput, "key outside range"));
+            }
+            digest.push(key.as_ref());
+        }
+        Ok(ManifestEntry {
+            keyspace,
+            range,
+            count: digest.count(),
+            digest: digest.finish(),
+        })
+    }
+
+    /// recompute from a restored copy and compare.
+    pub fn verify<K: AsRef<[u8]>, I: IntoIterator<Item = K>>(&self, keys: I) -> Result<(), Error> {
+        let actual = ManifestEntry::compute(self.keyspace.clone(), self.range.clone(), keys)?;
+        if actual.count != self.count {
+            return Err(Error::new(ErrorKind::InvalidData, "key count mismatch"));
+        }
+        if actual.digest != self.digest {
+            return Err(Error::new(ErrorKind::InvalidData, "digest mismatch"));
+        }
+        Ok(())
+    }
+}
+
+impl IndexKey for ManifestEntry {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        let bounded = self.range.end.is_some();
+        (
+            self.keyspace,
+            self.range.start,
+            bounded,
+            self.range.end.unwrap_or_default(),
+            self.count,
+        )
+            .to_key(result)?;
+        result.write_all(&self.digest)?;
+        Ok(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let (keyspace, start, bounded, end, count) =
+            <(String, Vec<u8>, bool, Vec<u8>, u64)>::from_key(key)?;
+        let mut digest = [0; 32];
+        key.read_exact(&mut digest)?;
+        Ok(ManifestEntry {
+            keyspace,
+            range: KeyRange {
+                start,
+                end: if bounded { Some(end) } else { None },
+            },
+            count,
+            digest,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Manifest {
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    pub fn builder() -> ManifestBuilder {
+        ManifestBuilder::default()
+    }
+
+    pub fn entry(&self, keyspace: &str, range: &KeyRange) -> Option<&ManifestEntry> {
+        self.entries
+            .iter()
+            .find(|e| e.keyspace == keyspace && e.range == *range)
+    }
+
+    pub fn total_count(&self) -> u64 {
+        self.entries.iter().map(|e| e.count).sum()
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut result = to_key(self.entries.len() as u64);
+        for entry in self.entries.iter() {
+            entry.clone().to_key(&mut result)?;
+        }
+        Ok(result)
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Manifest, Error> {
+        let mut cur = std::io::Cursor::new(&bytes[..]);
+        let len = u64::from_key(&mut cur)?;
+        let entries = (0..len)
+            .map(|_| ManifestEntry::from_key(&mut cur))
+            .collect::<Result<_, _>>()?;
+        if cur.position() as usize != bytes.len() {
+            return Err(Error::new(ErrorKind::InvalidData, "trailing bytes"));
+        }
+        Ok(Manifest { entries })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ManifestBuilder {
+    entries: Vec<ManifestEntry>,
+}
+
+impl ManifestBuilder {
+    pub fn add<K: AsRef<[u8]>, I: IntoIterator<Item = K>>(
+        &mut self,
+        keyspace: &str,
+        range: KeyRange,
+        keys: I,
+    ) -> Result<&mut ManifestBuilder, Error> {
+        if self
+            .entries
+            .iter()
+            .any(|e| e.keyspace == keyspace && e.range.overlaps(&range))
+        {
+            return Err(Error::new(ErrorKind::InvalidInput, "overlapping range"));
+        }
+        self.entries
+            .push(ManifestEntry::compute(keyspace.to_owned(), range, keys)?);
+        Ok(self)
+    }
+
+    pub fn build(&mut self) -> Manifest {
+        let mut entries = std::mem::take(&mut self.entries);
+        entries.sort_by(|a, b| (&a.keyspace, &a.range).cmp(&(&b.keyspace, &b.range)));
+        Manifest { entries }
+    }
+}
+
+#[test]
+fn test_manifest() {
+    let keys: Vec<Vec<u8>> = (0u32..100).map(to_key).collect();
+    let low = KeyRange::new(to_key(0u32), to_key(50u32));
+    let high = KeyRange::from(to_key(50u32));
+    let manifest = Manifest::builder()
+        .add("users", high.clone(), &keys[50..])
+        .unwrap()
+        .add("users", low.clone(), &keys[..50])
+        .unwrap()
+        .build();
+    assert_eq!(manifest.total_count(), 100);
+    assert_eq!(manifest.entries[0].range, low);
+
+    let bytes = manifest.to_bytes().unwrap();
+    let decoded = Manifest::from_bytes(bytes.clone()).unwrap();
+    assert_eq!(decoded, manifest);
+    let mut long = bytes;
+    long.push(0);
+    let err = Manifest::from_bytes(long).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    let entry = decoded.entry("users", &high).unwrap();
+    entry.verify(&keys[50..]).unwrap();
+    assert!(entry.verify(&keys[51..]).is_err());
+    assert!(entry.verify(&keys[49..]).is_err());
+    assert!(Manifest::builder()
+        .add("users", low.clone(), &keys[..1])
+        .unwrap()
+        .add("users", low, &keys[..1])
+        .is_err());
+}
@@ -0,0 +1,154 @@
+//! range-partitioned merkle tree for reconciling two key sets.
+//!
+//! both sides must build with the same boundaries so the trees line up.
+
+use crate::digest::DigestBuilder;
+use crate::range::KeyRange;
+use sha2::{Digest, Sha256};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleTree {
+    leaves: Vec<KeyRange>,
+    // levels[0] holds leaf hashes, the last level holds the root.
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl MerkleTree {
+    /// `boundaries` must be sorted; `n` boundaries make `n + 1` leaf ranges.
+    pub fn build<I: IntoIterator<Item = (Vec<u8>, [u8; 32])>>(
+        boundaries: &[Vec<u8>],
+        entries: I,
+    ) -> MerkleTree {
+        let mut starts = vec![vec![]];
+        starts.extend(boundaries.iter().cloned());
+        let leaves: Vec<KeyRange> = starts
+            .iter()
+            .enumerate()
+            .map(|(i, start)| match starts.get(i + 1) {
+                Some(end) => KeyRange::new(start.clone(), end.clone()),
+                None => KeyRange::from(start.clone()),
+            })
+            .collect();
+
+        let mut entries: Vec<_> = entries.into_iter().collect();
+        entries.sort();
+        let mut builders = vec![DigestBuilder::new(); leaves.len()];
+        for (key, value_hash) in entries {
+            let leaf = boundaries.partition_point(|b| *b <= key);
+            builders[leaf].push(&key).push(&value_hash);
+        }
+
+        let mut levels = vec![builders
+            .iter()
+            .map(|b| if b.count() == 0 { [0; 32] } else { b.finish() })
+            .collect::<Vec<_>>()];
+        while levels.last().unwrap().len() > 1 {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| {
+                    let mut hasher = Sha256::new();
+                    pair.iter().for_each(|h| hasher.update(h));
+                    hasher.finalize().into()
+                })
+                .collect();
+            levels.push(next);
+        }
+        MerkleTree { leaves, levels }
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.levels.last().unwrap()[0]
+    }
+
+    /// number of levels, leaves included.
+    pub fn depth(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// `level` counts down from the root at 0.
+    pub fn hash(&self, level: usize, index: usize) -> Option<[u8; 32]> {
+        let levels = self.levels.len().checked_sub(level + 1)?;
+        self.levels[levels].get(index).copied()
+    }
+
+    /// child indexes of a node, for walking the tree one level at a time.
+    pub fn children(&self, level: usize, index: usize) -> Vec<usize> {
+        if level + 1 >= self.levels.len() {
+            return vec![];
+        }
+        let below = &self.levels[self.levels.len() - level - 2];
+        (index * 2..(index * 2 + 2).min(below.len())).collect()
+    }
+
+    /// key range covered by a node.
+    pub fn range(&self, level: usize, index: usize) -> KeyRange {
+        let span = 1 << (self.levels.len() - 1 - level);
+        let first = &self.leaves[index * span];
+        let last = &self.leaves[((index + 1) * span).min(self.leaves.len()) - 1];
+        KeyRange {
+            start: first.start.clone(),
+            end: last.end.clone(),
+        }
+    }
+
+    /// ranges whose contents differ, coalesced where adjacent.
+    /// trees built from different boundaries differ everywhere.
+    pub fn diff_ranges(&self, other: &MerkleTree) -> Vec<KeyRange> {
+        if self.leaves != other.leaves {
+            return vec![KeyRange::all()];
+        }
+        let mut result: Vec<KeyRange> = vec![];
+        let mut stack = vec![(0, 0)];
+        while let Some((level, index)) = stack.pop() {
+            if self.hash(level, index) == other.hash(level, index) {
+                continue;
+            }
+            let children = self.children(level, index);
+            if children.is_empty() {
+                let range = self.range(level, index);
+                match result.last_mut() {
+                    Some(last) if last.end.as_ref() == Some(&range.start) => last.end = range.end,
+                    _ => result.push(range),
+                }
+                continue;
+            }
+            stack.extend(children.into_iter().rev().map(|i| (level + 1, i)));
+        }
+        result
+    }
+}
+
+#[test]
+fn test_merkle() {
+    use crate::digest::sha256;
+    use crate::to_key;
+    let boundaries: Vec<Vec<u8>> = (1u32..8).map(|i| to_key(i * 100)).collect();
+    let entries = |skip: u32| {
+        (0u32..800)
+            .filter(move |i| *i != skip)
+            .map(|i| (to_key(i), sha256(&i.to_be_bytes())))
+    };
+    let a = MerkleTree::build(&boundaries, entries(u32::MAX));
+    let b = MerkleTree::build(&boundaries, entries(u32::MAX).rev());
+    assert_eq!(a.root(), b.root());
+    assert!(a.diff_ranges(&b).is_empty());
+
+    let c = MerkleTree::build(&boundaries, entries(350));
+    assert_ne!(a.root(), c.root());
+    let diff = a.diff_ranges(&c);
+    assert_eq!(diff, vec![KeyRange::new(to_key(300u32), to_key(400u32))]);
+    assert!(diff[0].contains(&to_key(350u32)));
+
+    let d = MerkleTree::build(
+        &boundaries,
+        entries(350).filter(|(k, _)| *k != to_key(450u32)),
+    );
+    assert_eq!(
+        a.diff_ranges(&d),
+        vec![KeyRange::new(to_key(300u32), to_key(500u32))]
+    );
+    assert_eq!(a.range(0, 0), KeyRange::all());
+    assert_eq!(a.depth(), 4);
+}
@@ -0,0 +1,98 @@
+//! opt-in encode/decode metrics through [`to_key`](crate::to_key) and
+//! [`from_key`](crate::from_key).
+//!
+//! install one process-wide [`Recorder`] and forward to whatever metrics
+//! system is in use. every call reports under `std::any::type_name` of the key:
+//!
+//! - `index_key.encode` / `index_key.decode` counters, incremented by 1;
+//! - `index_key.encode.bytes` / `index_key.decode.bytes` histograms of key length;
+//! - `index_key.decode.errors` counter for failed decodes.
+
+use std::io::Error;
+use std::sync::OnceLock;
+
+pub trait Recorder: Send + Sync {
+    fn counter(&self, name: &'static str, type_name: &'static str, value: u64);
+    fn histogram(&self, name: &'static str, type_name: &'static str, value: f64);
+}
+
+static RECORDER: OnceLock<Box<dyn Recorder>> = OnceLock::new();
+
+/// fails if a recorder is already installed.
+pub fn set_recorder<R: Recorder + 'static>(recorder: R) -> Result<(), Error> {
+    RECORDER
+        .set(Box::new(recorder))
+        .map_err(|_| Error::other("metrics recorder already set"))
+}
+
+pub(crate) fn encoded<I>(len: usize) {
+    if let Some(recorder) = RECORDER.get() {
+        let type_name = std::any::type_name::<I>();
+        recorder.counter("index_key.encode", type_name, 1);
+        recorder.histogram("index_key.encode.bytes", type_name, len as f64);
+    }
+}
+
+pub(crate) fn decoded<I>(len: usize, ok: bool) {
+    if let Some(recorder) = RECORDER.get() {
+        let type_name = std::any::type_name::<I>();
+        recorder.counter("index_key.decode", type_name, 1);
+        recorder.histogram("index_key.decode.bytes", type_name, len as f64);
+        if !ok {
+            recorder.counter("index_key.decode.errors", type_name, 1);
+        }
+    }
+}
+
+#[test]
+fn test_metrics() {
+    use crate::{from_key, to_key, IndexKey};
+    use std::io::{Read, Write};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug)]
+    struct Metered(u16);
+    impl IndexKey for Metered {
+        fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+            self.0.to_key(result)
+        }
+        fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+            u16::from_key(key).map(Metered)
+        }
+    }
+
+    type Events = Arc<Mutex<Vec<(&'static str, f64)>>>;
+    struct Collect(Events);
+    impl Recorder for Collect {
+        fn counter(&self, name: &'static str, type_name: &'static str, value: u64) {
+            if type_name.ends_with("Metered") {
+                self.0.lock().unwrap().push((name, value as f64));
+            }
+        }
+        fn histogram(&self, name: &'static str, type_name: &'static str, value: f64) {
+            if type_name.ends_with("Metered") {
+                self.0.lock().unwrap().push((name, value));
+            }
+        }
+    }
+
+    let events = Events::default();
+    set_recorder(Collect(events.clone())).unwrap();
+    assert!(set_recorder(Collect(events.clone())).is_err());
+
+    let key = to_key(Metered(7));
+    from_key::<Metered>(key).unwrap();
+    from_key::<Metered>(vec![1]).unwrap_err();
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![
+            ("index_key.encode", 1.0),
+            ("index_key.encode.bytes", 2.0),
+            ("index_key.decode", 1.0),
+            ("index_key.decode.bytes", 2.0),
+            ("index_key.decode", 1.0),
+            ("index_key.decode.bytes", 1.0),
+            ("index_key.decode.errors", 1.0),
+        ]
+    );
+}
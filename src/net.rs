@@ -0,0 +1,281 @@
+//! network address keys.
+
+use crate::range::KeyRange;
+use crate::{to_key, IndexKey};
+use std::fmt;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::str::FromStr;
+
+/// 48-bit MAC address, encoded as its 6 raw bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MacAddr(pub [u8; 6]);
+
+impl MacAddr {
+    /// vendor prefix.
+    pub fn oui(&self) -> [u8; 3] {
+        [self.0[0], self.0[1], self.0[2]]
+    }
+
+    /// every address under a vendor prefix.
+    pub fn oui_range(oui: [u8; 3]) -> KeyRange {
+        KeyRange::prefix(oui.to_vec())
+    }
+}
+
+impl FromStr for MacAddr {
+    type Err = Error;
+    /// `aa:bb:cc:dd:ee:ff` or `aa-bb-cc-dd-ee-ff`.
+    fn from_str(s: &str) -> Result<MacAddr, Error> {
+        let invalid = || Error::new(ErrorKind::InvalidInput, "invalid mac address");
+        let mut bytes = [0u8; 6];
+        let mut parts = s.split([':', '-']);
+        for byte in bytes.iter_mut() {
+            let part = parts.next().filter(|p| p.len() == 2).ok_or_else(invalid)?;
+            *byte = u8::from_str_radix(part, 16).map_err(|_| invalid())?;
+        }
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(MacAddr(bytes))
+    }
+}
+
+impl fmt::Display for MacAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(
+            f,
+            "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+            a, b, c, d, e, g
+        )
+    }
+}
+
+impl IndexKey for MacAddr {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        result.write_all(&self.0)?;
+        Ok(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let mut bytes = [0; 6];
+        key.read_exact(&mut bytes)?;
+        Ok(MacAddr(bytes))
+    }
+}
+
+#[test]
+fn test_mac_addr() {
+    use crate::{from_key, to_key};
+    let mac: MacAddr = "00:1A:2b:3c:4d:5e".parse().unwrap();
+    assert_eq!(mac.to_string(), "00:1a:2b:3c:4d:5e");
+    assert_eq!("00-1a-2b-3c-4d-5e".parse::<MacAddr>().unwrap(), mac);
+    assert!("00:1a:2b:3c:4d".parse::<MacAddr>().is_err());
+    assert!("00:1a:2b:3c:4d:5e:6f".parse::<MacAddr>().is_err());
+    assert!("0:1a:2b:3c:4d:5e".parse::<MacAddr>().is_err());
+    assert_eq!(from_key::<MacAddr>(to_key(mac)).unwrap(), mac);
+
+    let range = MacAddr::oui_range(mac.oui());
+    assert!(range.contains(&to_key(mac)));
+    assert!(range.contains(&to_key(MacAddr([0, 0x1a, 0x2b, 255, 255, 255]))));
+    assert!(!range.contains(&to_key(MacAddr([0, 0x1a, 0x2c, 0, 0, 0]))));
+    assert!(to_key(MacAddr([0, 0, 1, 0, 0, 0])) > to_key(MacAddr([0, 0, 0, 255, 255, 255])));
+}
+
+/// the 4 raw octets.
+impl IndexKey for Ipv4Addr {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        result.write_all(&self.octets())?;
+        Ok(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let mut bytes = [0; 4];
+        key.read_exact(&mut bytes)?;
+        Ok(Ipv4Addr::from(bytes))
+    }
+}
+
+/// the 16 raw octets.
+impl IndexKey for Ipv6Addr {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        result.write_all(&self.octets())?;
+        Ok(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let mut bytes = [0; 16];
+        key.read_exact(&mut bytes)?;
+        Ok(Ipv6Addr::from(bytes))
+    }
+}
+
+const V4: u8 = 4;
+const V6: u8 = 6;
+
+/// a family byte, 4 or 6, then the octets, so every v4 address sorts before
+/// every v6 one. v4-mapped v6 addresses stay v6.
+impl IndexKey for IpAddr {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        match self {
+            IpAddr::V4(addr) => {
+                result.write_all(&[V4])?;
+                addr.to_key(result)
+            }
+            IpAddr::V6(addr) => {
+                result.write_all(&[V6])?;
+                addr.to_key(result)
+            }
+        }
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let mut family = [0];
+        key.read_exact(&mut family)?;
+        match family[0] {
+            V4 => Ok(IpAddr::V4(Ipv4Addr::from_key(key)?)),
+            V6 => Ok(IpAddr::V6(Ipv6Addr::from_key(key)?)),
+            _ => Err(Error::new(ErrorKind::InvalidData, "invalid address family")),
+        }
+    }
+}
+
+/// the address, then the port.
+impl IndexKey for SocketAddrV4 {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        (*self.ip(), self.port()).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let (ip, port) = <(Ipv4Addr, u16)>::from_key(key)?;
+        Ok(SocketAddrV4::new(ip, port))
+    }
+}
+
+/// the address, then the port, then flow info and scope id so they survive a
+/// round trip.
+impl IndexKey for SocketAddrV6 {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        (*self.ip(), self.port(), self.flowinfo(), self.scope_id()).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let (ip, port, flowinfo, scope_id) = <(Ipv6Addr, u16, u32, u32)>::from_key(key)?;
+        Ok(SocketAddrV6::new(ip, port, flowinfo, scope_id))
+    }
+}
+
+/// the family byte of [`IpAddr`], then as above.
+impl IndexKey for SocketAddr {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        match self {
+            SocketAddr::V4(addr) => {
+                result.write_all(&[V4])?;
+                addr.to_key(result)
+            }
+            SocketAddr::V6(addr) => {
+                result.write_all(&[V6])?;
+                addr.to_key(result)
+            }
+        }
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let mut family = [0];
+        key.read_exact(&mut family)?;
+        match family[0] {
+            V4 => Ok(SocketAddr::V4(SocketAddrV4::from_key(key)?)),
+            V6 => Ok(SocketAddr::V6(SocketAddrV6::from_key(key)?)),
+            _ => Err(Error::new(ErrorKind::InvalidData, "invalid address family")),
+        }
+    }
+}
+
+/// every [`SocketAddr`] key of `host`, whatever the port.
+pub fn host_range(host: IpAddr) -> KeyRange {
+    KeyRange::prefix(to_key(host))
+}
+
+/// the [`IpAddr`] keys inside `network/bits`.
+pub fn cidr_range(network: IpAddr, bits: u32) -> Result<KeyRange, Error> {
+    let (family, width, value) = match network {
+        IpAddr::V4(addr) => (V4, 32, u128::from(u32::from(addr))),
+        IpAddr::V6(addr) => (V6, 128, u128::from(addr)),
+    };
+    if bits > width {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "prefix longer than address",
+        ));
+    }
+    let hosts = u128::MAX.checked_shr(128 - width + bits).unwrap_or(0);
+    let start = value & !hosts;
+    let last = start | hosts;
+    let addr = |value: u128| match family {
+        V4 => IpAddr::V4(Ipv4Addr::from(value as u32)),
+        _ => IpAddr::V6(Ipv6Addr::from(value)),
+    };
+    // past the last address of the family, the next family byte.
+    let end = if last == u128::MAX >> (128 - width) {
+        vec![family + 1]
+    } else {
+        to_key(addr(last + 1))
+    };
+    Ok(KeyRange::new(to_key(addr(start)), end))
+}
+
+#[test]
+fn test_ip_addr() {
+    use crate::from_key;
+    let addrs: Vec<IpAddr> = [
+        "0.0.0.0",
+        "10.0.0.1",
+        "255.255.255.255",
+        "::",
+        "::ffff:10.0.0.1",
+        "2001:db8::1",
+    ]
+    .iter()
+    .map(|a| a.parse().unwrap())
+    .collect();
+    let keys: Vec<_> = addrs.iter().map(|a| to_key(*a)).collect();
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    for addr in &addrs {
+        assert_eq!(from_key::<IpAddr>(to_key(*addr)).unwrap(), *addr);
+    }
+    assert_eq!(to_key(Ipv4Addr::new(10, 0, 0, 1)), vec![10, 0, 0, 1]);
+    assert!(from_key::<IpAddr>(vec![5, 0, 0, 0, 0]).is_err());
+
+    let range = cidr_range("10.0.0.77".parse().unwrap(), 25).unwrap();
+    let ip = |s: &str| to_key(s.parse::<IpAddr>().unwrap());
+    assert!(range.contains(&ip("10.0.0.0")) && range.contains(&ip("10.0.0.127")));
+    assert!(!range.contains(&ip("10.0.0.128")) && !range.contains(&ip("9.255.255.255")));
+    let all_v4 = cidr_range("1.2.3.4".parse().unwrap(), 0).unwrap();
+    assert!(all_v4.contains(&ip("255.255.255.255")) && !all_v4.contains(&ip("::")));
+    let host = cidr_range("2001:db8::1".parse().unwrap(), 128).unwrap();
+    assert!(host.contains(&ip("2001:db8::1")) && !host.contains(&ip("2001:db8::2")));
+    assert!(cidr_range("::".parse().unwrap(), 0)
+        .unwrap()
+        .contains(&ip("ffff::")));
+    assert!(cidr_range("1.2.3.4".parse().unwrap(), 33).is_err());
+}
+
+#[test]
+fn test_socket_addr() {
+    use crate::from_key;
+    let addrs: Vec<SocketAddr> = [
+        "10.0.0.1:80",
+        "10.0.0.1:443",
+        "10.0.0.2:1",
+        "[::1]:80",
+        "[fe80::1%3]:80",
+    ]
+    .iter()
+    .map(|a| a.parse().unwrap())
+    .collect();
+    let keys: Vec<_> = addrs.iter().map(|a| to_key(*a)).collect();
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    for addr in &addrs {
+        assert_eq!(from_key::<SocketAddr>(to_key(*addr)).unwrap(), *addr);
+    }
+    let host = host_range("10.0.0.1".parse().unwrap());
+    assert_eq!(keys.iter().filter(|k| host.contains(k)).count(), 2);
+    assert_eq!(
+        to_key(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 1)),
+        vec![127, 0, 0, 1, 0, 1]
+    );
+}
@@ -0,0 +1,950 @@
+//! numeric key types beyond the primitives.
+
+use crate::codec::FixedWidth;
+use crate::key::{read_fixed, write_fixed};
+use crate::IndexKey;
+#[cfg(feature = "num-rational")]
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind, Read, Write};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Rounding {
+    /// half away from zero.
+    Nearest,
+    /// half to even.
+    NearestEven,
+    Floor,
+    Ceil,
+    TowardZero,
+}
+
+impl Rounding {
+    fn apply(self, value: f64) -> f64 {
+        match self {
+            Rounding::Nearest => value.round(),
+            Rounding::NearestEven => value.round_ties_even(),
+            Rounding::Floor => value.floor(),
+            Rounding::Ceil => value.ceil(),
+            Rounding::TowardZero => value.trunc(),
+        }
+    }
+}
+
+/// signed Q-format number: the raw `i64` counts units of `2^-FRAC_BITS`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FixedPoint<const FRAC_BITS: u32>(pub i64);
+
+impl<const FRAC_BITS: u32> FixedPoint<FRAC_BITS> {
+    fn scale() -> f64 {
+        2f64.powi(FRAC_BITS as i32)
+    }
+
+    pub fn from_raw(raw: i64) -> FixedPoint<FRAC_BITS> {
+        FixedPoint(raw)
+    }
+
+    pub fn raw(self) -> i64 {
+        self.0
+    }
+
+    pub fn from_int(value: i64) -> Result<FixedPoint<FRAC_BITS>, Error> {
+        value
+            .checked_mul(1i64.checked_shl(FRAC_BITS).unwrap_or(0))
+            .filter(|_| FRAC_BITS < 63)
+            .map(FixedPoint)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "value out of range"))
+    }
+
+    /// NaN and values outside the representable range are errors.
+    pub fn from_f64(value: f64, rounding: Rounding) -> Result<FixedPoint<FRAC_BITS>, Error> {
+        let scaled = rounding.apply(value * Self::scale());
+        // 2^63 is exact in f64; i64::MAX is not.
+        if scaled.is_nan() || scaled < -(2f64.powi(63)) || scaled >= 2f64.powi(63) {
+            return Err(Error::new(ErrorKind::InvalidInput, "value out of range"));
+        }
+        Ok(FixedPoint(scaled as i64))
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / Self::scale()
+    }
+}
+
+impl<const FRAC_BITS: u32> FixedWidth for FixedPoint<FRAC_BITS> {
+    type Bytes = [u8; 8];
+    fn encode(self) -> [u8; 8] {
+        self.0.encode()
+    }
+    fn decode(bytes: [u8; 8]) -> Self {
+        FixedPoint(i64::decode(bytes))
+    }
+}
+
+impl<const FRAC_BITS: u32> IndexKey for FixedPoint<FRAC_BITS> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        write_fixed(self, result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        read_fixed(key)
+    }
+}
+
+#[test]
+fn test_fixed_point() {
+    use crate::{from_key, to_key};
+    type Q16 = FixedPoint<16>;
+    let v = Q16::from_f64(1.5, Rounding::Nearest).unwrap();
+    assert_eq!(v.raw(), 3 << 15);
+    assert_eq!(v.to_f64(), 1.5);
+    assert_eq!(Q16::from_int(2).unwrap().to_f64(), 2.0);
+    assert!(Q16::from_int(i64::MAX).is_err());
+    assert!(Q16::from_f64(f64::NAN, Rounding::Floor).is_err());
+    assert!(Q16::from_f64(1e300, Rounding::Floor).is_err());
+
+    let third = 1.0 / 3.0;
+    assert!(
+        Q16::from_f64(third, Rounding::Floor).unwrap()
+            < Q16::from_f64(third, Rounding::Ceil).unwrap()
+    );
+    let half_unit = 0.5 / 65536.0;
+    assert_eq!(
+        Q16::from_f64(half_unit, Rounding::Nearest).unwrap().raw(),
+        1
+    );
+    assert_eq!(
+        Q16::from_f64(half_unit, Rounding::NearestEven)
+            .unwrap()
+            .raw(),
+        0
+    );
+    assert_eq!(
+        Q16::from_f64(-half_unit, Rounding::TowardZero)
+            .unwrap()
+            .raw(),
+        0
+    );
+    assert_eq!(
+        Q16::from_f64(-half_unit, Rounding::Floor).unwrap().raw(),
+        -1
+    );
+
+    let mut list: Vec<Q16> = [-2.25, 0.0, 1e-4, -1e-4, 1000.5, -1000.5]
+        .iter()
+        .map(|v| Q16::from_f64(*v, Rounding::Nearest).unwrap())
+        .collect();
+    list.sort_by_key(|v| {
+        assert_eq!(from_key::<Q16>(to_key(*v)).unwrap(), *v);
+        to_key(*v)
+    });
+    let sorted: Vec<f64> = list.iter().map(|v| v.to_f64()).collect();
+    assert!(sorted.windows(2).all(|w| w[0] < w[1]));
+}
+
+/// the `f32` and `f64` layout: the sign bit set for positives and every bit
+/// flipped for negatives.
+#[cfg(feature = "half")]
+impl FixedWidth for half::f16 {
+    type Bytes = [u8; 2];
+    fn encode(self) -> [u8; 2] {
+        let value = self.to_bits() as i16;
+        (((value >> 15) | i16::MIN) ^ value).to_be_bytes()
+    }
+    fn decode(bytes: [u8; 2]) -> Self {
+        let value = i16::from_be_bytes(bytes);
+        half::f16::from_bits(((!value >> 15 | i16::MIN) ^ value) as u16)
+    }
+}
+
+#[cfg(feature = "half")]
+impl IndexKey for half::f16 {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        write_fixed(self, result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        read_fixed(key)
+    }
+}
+
+#[cfg(feature = "half")]
+#[test]
+fn test_f16() {
+    use crate::{from_key, to_key};
+    use half::f16;
+    // -inf, min, -1.0, -0.0, 0.0, smallest subnormal, 1.0, 1.5, max, inf.
+    let sorted = [
+        0xfc00, 0xfbff, 0xbc00, 0x8000, 0x0000, 0x0001, 0x3c00, 0x3e00, 0x7bff, 0x7c00,
+    ];
+    let mut list: Vec<f16> = sorted.iter().rev().map(|b| f16::from_bits(*b)).collect();
+    list.sort_by_key(|v| {
+        assert_eq!(from_key::<f16>(to_key(*v)).unwrap().to_bits(), v.to_bits());
+        to_key(*v)
+    });
+    let bits: Vec<u16> = list.iter().map(|v| v.to_bits()).collect();
+    assert_eq!(bits, sorted);
+    assert!(to_key(f16::NAN) > to_key(f16::INFINITY));
+    assert!(from_key::<f16>(to_key(f16::NAN)).unwrap().is_nan());
+}
+
+// `ordered_float::OrderedFloat` orders every NaN as one value after infinity
+// and `-0.0` equal to `0.0`, so keys canonicalize both.
+#[cfg(feature = "ordered-float")]
+macro_rules! impl_ordered_float {
+    ($f:ty) => {
+        impl IndexKey for ordered_float::OrderedFloat<$f> {
+            fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+                let value = if self.0.is_nan() {
+                    <$f>::NAN
+                } else {
+                    self.0 + 0.0
+                };
+                write_fixed(value, result)
+            }
+            fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+                read_fixed(key).map(ordered_float::OrderedFloat)
+            }
+        }
+
+        /// `-0.0` encodes as `0.0`; decoding rejects NaN keys.
+        impl IndexKey for ordered_float::NotNan<$f> {
+            fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+                write_fixed(self.into_inner() + 0.0, result)
+            }
+            fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+                ordered_float::NotNan::new(read_fixed::<$f, _>(key)?)
+                    .map_err(|_| Error::new(ErrorKind::InvalidData, "NaN key"))
+            }
+        }
+    };
+}
+
+#[cfg(feature = "ordered-float")]
+impl_ordered_float!(f32);
+#[cfg(feature = "ordered-float")]
+impl_ordered_float!(f64);
+
+#[cfg(feature = "ordered-float")]
+#[test]
+fn test_ordered_float() {
+    use crate::{from_key, to_key};
+    use ordered_float::{NotNan, OrderedFloat};
+    let negative_nan = -f64::NAN;
+    assert!(negative_nan.is_sign_negative());
+    let sorted = [f64::NEG_INFINITY, -1.5, 0.0, 2.0, f64::INFINITY, f64::NAN];
+    let mut list: Vec<_> = sorted.iter().rev().map(|v| OrderedFloat(*v)).collect();
+    list.push(OrderedFloat(negative_nan));
+    list.push(OrderedFloat(-0.0));
+    list.sort_by_key(|v| to_key(*v));
+    let mut by_ord = list.clone();
+    by_ord.sort();
+    assert_eq!(list, by_ord);
+    assert!(list[..2].iter().all(|v| !v.0.is_nan()));
+    assert!(list[6..].iter().all(|v| v.0.is_nan()));
+    assert_eq!(
+        to_key(OrderedFloat(negative_nan)),
+        to_key(OrderedFloat(f64::NAN))
+    );
+    assert_eq!(to_key(OrderedFloat(-0.0f32)), to_key(OrderedFloat(0.0f32)));
+    assert_eq!(
+        from_key::<OrderedFloat<f32>>(to_key(OrderedFloat(-3.25f32))).unwrap(),
+        OrderedFloat(-3.25)
+    );
+
+    let value = NotNan::new(-7.5f64).unwrap();
+    assert_eq!(from_key::<NotNan<f64>>(to_key(value)).unwrap(), value);
+    assert_eq!(to_key(NotNan::new(-0.0f64).unwrap()), to_key(0.0f64));
+    assert_eq!(to_key(value), to_key(-7.5f64));
+    assert!(from_key::<NotNan<f64>>(to_key(f64::NAN)).is_err());
+    assert!(from_key::<NotNan<f32>>(to_key(-f32::NAN)).is_err());
+}
+
+/// float truncated toward zero to `DIGITS` (at least 1) significant digits before
+/// encoding, so near-equal values share a key. lossy: only the truncated value
+/// round-trips. NaN, infinities and zeros pass through unchanged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Bucketted<F, const DIGITS: u32>(F);
+
+macro_rules! impl_bucketted {
+    ($f:ty) => {
+        impl<const DIGITS: u32> Bucketted<$f, DIGITS> {
+            pub fn new(value: $f) -> Bucketted<$f, DIGITS> {
+                if !value.is_finite() || value == 0.0 {
+                    return Bucketted(value);
+                }
+                // shortest round-trip `d.ddd…e±x`, so 1.23 stays 1.23.
+                let formatted = format!("{:e}", value);
+                let (mantissa, exponent) = formatted.split_once('e').unwrap();
+                let sign = if value < 0.0 { 1 } else { 0 };
+                let keep = match DIGITS {
+                    0 | 1 => sign + 1,
+                    digits => sign + 1 + digits as usize,
+                };
+                let truncated: $f =
+                    format!("{}e{}", &mantissa[..keep.min(mantissa.len())], exponent)
+                        .parse()
+                        .unwrap();
+                Bucketted(truncated)
+            }
+
+            pub fn get(self) -> $f {
+                self.0
+            }
+        }
+
+        impl<const DIGITS: u32> IndexKey for Bucketted<$f, DIGITS> {
+            fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+                write_fixed(self.0, result)
+            }
+            fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+                read_fixed(key).map(Bucketted)
+            }
+        }
+    };
+}
+
+impl_bucketted!(f32);
+impl_bucketted!(f64);
+
+#[test]
+fn test_bucketted() {
+    use crate::{from_key, to_key};
+    type B3 = Bucketted<f64, 3>;
+    assert_eq!(B3::new(1.23999).get(), 1.23);
+    assert_eq!(B3::new(-1.23999).get(), -1.23);
+    assert_eq!(B3::new(1.23).get(), 1.23);
+    assert_eq!(B3::new(123456.0).get(), 123000.0);
+    assert_eq!(B3::new(0.000987654).get(), 0.000987);
+    assert_eq!(B3::new(5.0).get(), 5.0);
+    assert!(B3::new(f64::NAN).get().is_nan());
+    assert_eq!(Bucketted::<f32, 2>::new(9.87).get(), 9.8);
+    assert_eq!(Bucketted::<f64, 0>::new(9.87).get(), 9.0);
+
+    assert_eq!(to_key(B3::new(2.0011)), to_key(B3::new(2.0019)));
+    assert!(to_key(B3::new(2.001)) < to_key(B3::new(2.011)));
+    assert_eq!(
+        from_key::<B3>(to_key(B3::new(7.777))).unwrap(),
+        B3::new(7.77)
+    );
+
+    let inputs = [-5.5, -5.49, -0.01, 0.0, 0.011, 0.0111, 4.5678, 4.569, 1e10];
+    let keys: Vec<_> = inputs.iter().map(|v| to_key(B3::new(*v))).collect();
+    assert!(keys.windows(2).all(|w| w[0] <= w[1]));
+}
+
+/// amount in minor units of a currency; keys group by currency first, then
+/// order by amount.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money {
+    currency: [u8; 3],
+    minor_units: i128,
+}
+
+impl Money {
+    /// `currency` must be three ASCII letters; it is stored uppercased.
+    pub fn new(currency: &str, minor_units: i128) -> Result<Money, Error> {
+        Ok(Money {
+            currency: currency_code(currency.as_bytes())?,
+            minor_units,
+        })
+    }
+
+    pub fn currency(&self) -> &str {
+        std::str::from_utf8(&self.currency).unwrap()
+    }
+
+    pub fn minor_units(&self) -> i128 {
+        self.minor_units
+    }
+}
+
+fn currency_code(code: &[u8]) -> Result<[u8; 3], Error> {
+    match code {
+        [a, b, c] if code.iter().all(u8::is_ascii_alphabetic) => Ok([
+            a.to_ascii_uppercase(),
+            b.to_ascii_uppercase(),
+            c.to_ascii_uppercase(),
+        ]),
+        _ => Err(Error::new(ErrorKind::InvalidInput, "invalid currency code")),
+    }
+}
+
+impl IndexKey for Money {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        result.write_all(&self.currency)?;
+        self.minor_units.to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let mut code = [0; 3];
+        key.read_exact(&mut code)?;
+        if currency_code(&code).ok() != Some(code) {
+            return Err(Error::new(ErrorKind::InvalidData, "invalid currency code"));
+        }
+        Ok(Money {
+            currency: code,
+            minor_units: i128::from_key(key)?,
+        })
+    }
+}
+
+#[test]
+fn test_money() {
+    use crate::{from_key, to_key};
+    assert!(Money::new("US", 1).is_err());
+    assert!(Money::new("U$D", 1).is_err());
+    assert_eq!(Money::new("eur", 5).unwrap().currency(), "EUR");
+    assert!(from_key::<Money>(to_key((b'u', b'S', b'D', 0i128))).is_err());
+    let err = from_key::<Money>(to_key((b'U', b'$', b'D', 0i128))).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    let mut list = [
+        Money::new("USD", 100).unwrap(),
+        Money::new("EUR", 5).unwrap(),
+        Money::new("USD", -250).unwrap(),
+        Money::new("EUR", i128::MAX).unwrap(),
+    ];
+    list.sort_by_key(|m| {
+        assert_eq!(from_key::<Money>(to_key(*m)).unwrap(), *m);
+        to_key(*m)
+    });
+    let sorted: Vec<_> = list
+        .iter()
+        .map(|m| (m.currency(), m.minor_units()))
+        .collect();
+    assert_eq!(
+        sorted,
+        vec![("EUR", 5), ("EUR", i128::MAX), ("USD", -250), ("USD", 100)]
+    );
+}
+
+#[cfg(any(feature = "bigdecimal", feature = "rust_decimal"))]
+const DECIMAL_NEGATIVE: u8 = 0;
+#[cfg(any(feature = "bigdecimal", feature = "rust_decimal"))]
+const DECIMAL_ZERO: u8 = 1;
+#[cfg(any(feature = "bigdecimal", feature = "rust_decimal"))]
+const DECIMAL_POSITIVE: u8 = 2;
+
+/// a nonzero decimal `0.d1 d2 ... dn * 10^exponent` as a sign byte, the
+/// exponent and the digits each plus one, closed by 0; negative values have
+/// everything after the sign byte inverted. equal values encode equally
+/// whatever their scale. `digits` has no leading or trailing zeros.
+#[cfg(any(feature = "bigdecimal", feature = "rust_decimal"))]
+fn write_decimal<'a, W: Write>(
+    negative: bool,
+    exponent: i64,
+    digits: &[u8],
+    result: &'a mut W,
+) -> Result<&'a mut W, Error> {
+    if digits.is_empty() {
+        result.write_all(&[DECIMAL_ZERO])?;
+        return Ok(result);
+    }
+    let flip = if negative { 255 } else { 0 };
+    let mut bytes = exponent.encode().to_vec();
+    bytes.extend(digits.iter().map(|d| d + 1));
+    bytes.push(0);
+    bytes.iter_mut().for_each(|b| *b ^= flip);
+    result.write_all(&[if negative {
+        DECIMAL_NEGATIVE
+    } else {
+        DECIMAL_POSITIVE
+    }])?;
+    result.write_all(&bytes)?;
+    Ok(result)
+}
+
+/// inverse of [`write_decimal`]; zero is no digits.
+#[cfg(any(feature = "bigdecimal", feature = "rust_decimal"))]
+fn read_decimal<R: Read>(key: &mut R) -> Result<(bool, i64, Vec<u8>), Error> {
+    let invalid = || Error::new(ErrorKind::InvalidData, "invalid decimal");
+    let negative = match u8::from_key(key)? {
+        DECIMAL_ZERO => return Ok((false, 0, vec![])),
+        DECIMAL_NEGATIVE => true,
+        DECIMAL_POSITIVE => false,
+        _ => return Err(invalid()),
+    };
+    let flip = if negative { 255 } else { 0 };
+    let mut exponent = [0u8; 8];
+    key.read_exact(&mut exponent)?;
+    exponent.iter_mut().for_each(|b| *b ^= flip);
+    let mut digits = vec![];
+    let mut byte = [0u8];
+    loop {
+        key.read_exact(&mut byte)?;
+        match byte[0] ^ flip {
+            0 => break,
+            d @ 1..=10 => digits.push(d - 1),
+            _ => return Err(invalid()),
+        }
+    }
+    if digits.first() == Some(&0) || digits.last().is_none_or(|d| *d == 0) {
+        return Err(invalid());
+    }
+    Ok((negative, i64::decode(exponent), digits))
+}
+
+/// the digits of `magnitude * 10^-scale` without trailing zeros, and the
+/// exponent placing them as in [`write_decimal`].
+#[cfg(any(feature = "bigdecimal", feature = "rust_decimal"))]
+fn decimal_digits(magnitude: &str, scale: i64) -> Result<(i64, Vec<u8>), Error> {
+    let digits: Vec<u8> = magnitude
+        .trim_end_matches('0')
+        .bytes()
+        .map(|c| c - b'0')
+        .collect();
+    let zeros = (magnitude.len() - digits.len()) as i64;
+    let exponent = (digits.len() as i64 + zeros)
+        .checked_sub(scale)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "decimal out of range"))?;
+    Ok((exponent, digits))
+}
+
+/// sorts numerically; decoding normalizes to the smallest scale, so `1.50`
+/// comes back as `1.5`.
+#[cfg(feature = "rust_decimal")]
+impl IndexKey for rust_decimal::Decimal {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        let mantissa = self.mantissa();
+        let (exponent, digits) = decimal_digits(
+            &mantissa.unsigned_abs().to_string(),
+            i64::from(self.scale()),
+        )?;
+        write_decimal(mantissa < 0, exponent, &digits, result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let (negative, exponent, digits) = read_decimal(key)?;
+        let out_of_range = || Error::new(ErrorKind::InvalidData, "decimal out of range");
+        let shift = exponent
+            .checked_sub(digits.len() as i64)
+            .ok_or_else(out_of_range)?;
+        let (zeros, scale) = if shift >= 0 {
+            (shift, 0)
+        } else {
+            (0, shift.unsigned_abs())
+        };
+        if zeros > 38 || scale > u64::from(rust_decimal::Decimal::MAX_SCALE) {
+            return Err(out_of_range());
+        }
+        let mut mantissa: i128 = 0;
+        for d in digits
+            .iter()
+            .copied()
+            .chain(std::iter::repeat_n(0, zeros as usize))
+        {
+            mantissa = mantissa
+                .checked_mul(10)
+                .and_then(|m| m.checked_add(i128::from(d)))
+                .ok_or_else(out_of_range)?;
+        }
+        let mantissa = if negative { -mantissa } else { mantissa };
+        rust_decimal::Decimal::try_from_i128_with_scale(mantissa, scale as u32)
+            .map_err(|_| out_of_range())
+    }
+}
+
+#[cfg(feature = "rust_decimal")]
+#[test]
+fn test_rust_decimal() {
+    use crate::{from_key, to_key};
+    use rust_decimal::Decimal;
+    let d = |mantissa: i128, scale| Decimal::from_i128_with_scale(mantissa, scale);
+    let sorted = [
+        Decimal::MIN,
+        d(-125, 1),
+        d(-1, 0),
+        d(-5, 1),
+        d(-5, 2),
+        d(-1, 28),
+        d(0, 0),
+        d(1, 28),
+        d(1, 3),
+        d(1, 2),
+        d(1, 1),
+        d(1, 0),
+        d(15, 1),
+        d(10, 0),
+        d(12, 0),
+        d(100, 0),
+        Decimal::MAX,
+    ];
+    let keys: Vec<_> = sorted.iter().map(|v| to_key(*v)).collect();
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    for v in sorted {
+        let decoded = from_key::<Decimal>(to_key(v)).unwrap();
+        assert_eq!(
+            (decoded.mantissa(), decoded.scale()),
+            (v.mantissa(), v.scale())
+        );
+    }
+    assert_eq!(to_key(d(150, 2)), to_key(d(15, 1)));
+    assert_eq!(from_key::<Decimal>(to_key(d(150, 2))).unwrap().scale(), 1);
+    assert_eq!(to_key(d(0, 5)), to_key(d(0, 0)));
+    assert_eq!(to_key(-Decimal::ZERO), to_key(Decimal::ZERO));
+    // 2^96 does not fit the 96-bit mantissa.
+    let mut too_big = vec![];
+    write_decimal(
+        false,
+        29,
+        &[
+            7, 9, 2, 2, 8, 1, 6, 2, 5, 1, 4, 2, 6, 4, 3, 3, 7, 5, 9, 3, 5, 4, 3, 9, 5, 0, 3, 3, 6,
+        ],
+        &mut too_big,
+    )
+    .unwrap();
+    assert!(from_key::<Decimal>(too_big).is_err());
+    assert!(from_key::<Decimal>(vec![DECIMAL_POSITIVE, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0]).is_err());
+}
+
+/// the layout of `rust_decimal::Decimal`, so equal values encode equally
+/// whatever their scale; keys grow with the digit count.
+#[cfg(feature = "bigdecimal")]
+impl IndexKey for bigdecimal::BigDecimal {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        use bigdecimal::num_bigint::Sign;
+        let (int, scale) = self.as_bigint_and_scale();
+        let (exponent, digits) = decimal_digits(&int.magnitude().to_string(), scale)?;
+        write_decimal(int.sign() == Sign::Minus, exponent, &digits, result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        use bigdecimal::num_bigint::{BigInt, BigUint, Sign};
+        let (negative, exponent, digits) = read_decimal(key)?;
+        if digits.is_empty() {
+            return Ok(bigdecimal::BigDecimal::default());
+        }
+        let scale = (digits.len() as i64)
+            .checked_sub(exponent)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "decimal out of range"))?;
+        let magnitude = BigUint::from_radix_be(&digits, 10).expect("decimal digits");
+        let sign = if negative { Sign::Minus } else { Sign::Plus };
+        Ok(bigdecimal::BigDecimal::new(
+            BigInt::from_biguint(sign, magnitude),
+            scale,
+        ))
+    }
+}
+
+#[cfg(feature = "bigdecimal")]
+#[test]
+fn test_big_decimal() {
+    use crate::{from_key, to_key};
+    use bigdecimal::BigDecimal;
+    use std::str::FromStr;
+    let sorted = [
+        "-1e100",
+        "-123.45",
+        "-12",
+        "-1.5",
+        "-1",
+        "-0.5",
+        "-0.05",
+        "0",
+        "0.001",
+        "0.0011",
+        "0.01",
+        "1",
+        "1.00000000000000000000000000000000000001",
+        "10",
+        "12.5",
+        "100",
+        "9999999999999999999999999999999999999999",
+        "1e100",
+    ];
+    let values: Vec<BigDecimal> = sorted
+        .iter()
+        .map(|t| BigDecimal::from_str(t).unwrap())
+        .collect();
+    let keys: Vec<_> = values.iter().map(|v| to_key(v.clone())).collect();
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    for value in &values {
+        assert_eq!(
+            from_key::<BigDecimal>(to_key(value.clone())).unwrap(),
+            *value
+        );
+    }
+    let p = |t| BigDecimal::from_str(t).unwrap();
+    assert_eq!(to_key(p("1.50")), to_key(p("001.5")));
+    assert_eq!(to_key(p("-0.0")), to_key(p("0")));
+    assert_eq!(
+        from_key::<BigDecimal>(to_key(p("1.500")))
+            .unwrap()
+            .fractional_digit_count(),
+        1
+    );
+    assert_eq!(
+        from_key::<BigDecimal>(to_key(p("1.5e3"))).unwrap(),
+        p("1500")
+    );
+    assert!(from_key::<BigDecimal>(vec![DECIMAL_POSITIVE, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0]).is_err());
+}
+
+#[cfg(all(feature = "bigdecimal", feature = "rust_decimal"))]
+#[test]
+fn test_big_decimal_matches_rust_decimal() {
+    use crate::to_key;
+    use std::str::FromStr;
+    for text in [
+        "-12.5",
+        "0",
+        "0.001",
+        "1.50",
+        "79228162514264337593543950335",
+    ] {
+        assert_eq!(
+            to_key(bigdecimal::BigDecimal::from_str(text).unwrap()),
+            to_key(rust_decimal::Decimal::from_str(text).unwrap())
+        );
+    }
+}
+
+/// the continued fraction `[a0; a1, a2, ...]` of the reduced value: `a0` is
+/// the floor as an `i64`, and each later term a `u64` inverted at odd
+/// positions, since a larger term there means a smaller value. the terms
+/// close with an infinite one, `u64::MAX` in place, so a shorter expansion
+/// sorts where its value lies, and keys sort numerically.
+#[cfg(feature = "num-rational")]
+impl IndexKey for num_rational::Ratio<i64> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        if *self.denom() == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "invalid ratio"));
+        }
+        // reduced in i128, where `i64::MIN / -1` cannot overflow.
+        let value = num_rational::Ratio::new(i128::from(*self.numer()), i128::from(*self.denom()));
+        let (numer, denom) = (*value.numer(), *value.denom());
+        let whole = i64::try_from(numer.div_euclid(denom))
+            .map_err(|_| Error::new(ErrorKind::InvalidInput, "ratio out of range"))?;
+        whole.to_key(result)?;
+        let (mut p, mut q) = (denom, numer.rem_euclid(denom));
+        let mut odd = true;
+        while q != 0 {
+            let term = (p / q) as u64;
+            (p, q) = (q, p % q);
+            (if odd { !term } else { term }).to_key(result)?;
+            odd = !odd;
+        }
+        (if odd { 0 } else { u64::MAX }).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let invalid = || Error::new(ErrorKind::InvalidData, "invalid ratio");
+        let whole = i64::from_key(key)?;
+        let mut terms = vec![];
+        let mut odd = true;
+        loop {
+            let term = u64::from_key(key)?;
+            let term = if odd { !term } else { term };
+            if term == u64::MAX {
+                break;
+            }
+            if term == 0 {
+                return Err(invalid());
+            }
+            terms.push(term);
+            odd = !odd;
+        }
+        if terms.last() == Some(&1) {
+            return Err(invalid());
+        }
+        // fold [a1; a2, ...] into p / q from the last term, starting from
+        // infinity; the fraction after the floor is then q / p, already
+        // reduced.
+        let (mut p, mut q) = (1i128, 0i128);
+        for term in terms.iter().rev() {
+            let next = i128::from(*term) * p + q;
+            if next > i128::from(i64::MAX) {
+                return Err(invalid());
+            }
+            (p, q) = (next, p);
+        }
+        let numer = i64::try_from(i128::from(whole) * p + q).map_err(|_| invalid())?;
+        Ok(num_rational::Ratio::new_raw(numer, p as i64))
+    }
+}
+
+#[cfg(feature = "num-rational")]
+#[test]
+fn test_ratio() {
+    use crate::{from_key, to_key};
+    use num_rational::Ratio;
+    let r = |n, d| Ratio::<i64>::new(n, d);
+    assert!(crate::to_key_atomic(Ratio::new_raw(1i64, 0), &mut vec![]).is_err());
+    assert!(crate::to_key_atomic(Ratio::new_raw(i64::MIN, -1), &mut vec![]).is_err());
+    assert_eq!(to_key(Ratio::new_raw(2i64, -4)), to_key(r(-1, 2)));
+
+    let mut all = vec![
+        Ratio::from_integer(i64::MIN),
+        Ratio::from_integer(i64::MAX),
+        r(i64::MAX, i64::MAX - 1),
+        r(1, i64::MAX),
+        r(-1, i64::MAX),
+        r(i64::MIN + 1, i64::MAX),
+    ];
+    for n in -20..=20 {
+        for d in 1..=12 {
+            all.push(r(n, d));
+        }
+    }
+    for v in &all {
+        assert_eq!(from_key::<Ratio<i64>>(to_key(*v)).unwrap(), *v);
+    }
+    let mut by_value = all.clone();
+    by_value.sort();
+    by_value.dedup();
+    let mut by_key = all.clone();
+    by_key.sort_by_key(|v| to_key(*v));
+    by_key.dedup();
+    assert_eq!(by_key, by_value);
+    assert_eq!(to_key(Ratio::new_raw(2i64, 4)), to_key(r(1, 2)));
+    // a trailing 1 term is not canonical: [0; 1, 1] is written [0; 2].
+    assert!(from_key::<Ratio<i64>>(to_key((0i64, !1u64, 1u64, 0u64))).is_err());
+    assert!(from_key::<Ratio<i64>>(to_key((0i64, !0u64))).is_err());
+}
+
+// a continued fraction term of a `BigRational`: the byte length as a `u32`,
+// then the big-endian magnitude, all inverted if `flip` is 255. terms are
+// nonzero and distinct lengths never prefix each other, so keys sort by
+// value; `u32::MAX` with no bytes is the closing infinite term.
+#[cfg(feature = "num-rational")]
+fn write_big_term<'a, W: Write>(
+    term: Option<&num_bigint::BigUint>,
+    flip: u8,
+    result: &'a mut W,
+) -> Result<&'a mut W, Error> {
+    let mut bytes = match term {
+        Some(term) => {
+            let magnitude = term.to_bytes_be();
+            let len = u32::try_from(magnitude.len())
+                .ok()
+                .filter(|len| *len < u32::MAX)
+                .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "ratio too large"))?;
+            [&len.to_be_bytes()[..], &magnitude].concat()
+        }
+        None => u32::MAX.to_be_bytes().to_vec(),
+    };
+    bytes.iter_mut().for_each(|b| *b ^= flip);
+    result.write_all(&bytes)?;
+    Ok(result)
+}
+
+/// inverse of [`write_big_term`]; `None` for the infinite term.
+#[cfg(feature = "num-rational")]
+fn read_big_term<R: Read>(key: &mut R, flip: u8) -> Result<Option<num_bigint::BigUint>, Error> {
+    let invalid = || Error::new(ErrorKind::InvalidData, "invalid ratio");
+    let mut len = [0u8; 4];
+    key.read_exact(&mut len)?;
+    len.iter_mut().for_each(|b| *b ^= flip);
+    let len = u32::from_be_bytes(len);
+    if len == u32::MAX {
+        return Ok(None);
+    }
+    let mut magnitude = vec![];
+    key.take(u64::from(len)).read_to_end(&mut magnitude)?;
+    if magnitude.len() != len as usize {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "truncated ratio"));
+    }
+    magnitude.iter_mut().for_each(|b| *b ^= flip);
+    if magnitude.first().is_none_or(|b| *b == 0) {
+        return Err(invalid());
+    }
+    Ok(Some(num_bigint::BigUint::from_bytes_be(&magnitude)))
+}
+
+/// the continued fraction of `Ratio<i64>` with unbounded terms: the floor as
+/// a sign byte, 0 negative, 1 zero or 2 positive, then its magnitude as a
+/// term, inverted when negative; the later terms as for `Ratio<i64>`,
+/// inverted at odd positions and closed by an infinite term.
+#[cfg(feature = "num-rational")]
+impl IndexKey for num_rational::BigRational {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        use num_bigint::{BigInt, Sign};
+        if self.denom().sign() == Sign::NoSign {
+            return Err(Error::new(ErrorKind::InvalidInput, "invalid ratio"));
+        }
+        let value = num_rational::BigRational::new(self.numer().clone(), self.denom().clone());
+        let whole = value.floor().to_integer();
+        let (sign, flip) = match whole.sign() {
+            Sign::Minus => (0, 255),
+            Sign::NoSign => (1, 0),
+            Sign::Plus => (2, 0),
+        };
+        result.write_all(&[sign])?;
+        if sign != 1 {
+            write_big_term(Some(whole.magnitude()), flip, result)?;
+        }
+        let (mut p, mut q): (BigInt, BigInt) = (
+            value.denom().clone(),
+            value.numer() - &whole * value.denom(),
+        );
+        let mut odd = true;
+        while q.sign() != Sign::NoSign {
+            let term = &p / &q;
+            let rem = &p % &q;
+            p = std::mem::replace(&mut q, rem);
+            write_big_term(Some(term.magnitude()), if odd { 255 } else { 0 }, result)?;
+            odd = !odd;
+        }
+        write_big_term(None, if odd { 255 } else { 0 }, result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        use num_bigint::{BigInt, BigUint, Sign};
+        let invalid = || Error::new(ErrorKind::InvalidData, "invalid ratio");
+        let whole = match u8::from_key(key)? {
+            0 => BigInt::from_biguint(Sign::Minus, read_big_term(key, 255)?.ok_or_else(invalid)?),
+            1 => BigInt::default(),
+            2 => BigInt::from_biguint(Sign::Plus, read_big_term(key, 0)?.ok_or_else(invalid)?),
+            _ => return Err(invalid()),
+        };
+        let mut terms = vec![];
+        let mut odd = true;
+        while let Some(term) = read_big_term(key, if odd { 255 } else { 0 })? {
+            terms.push(term);
+            odd = !odd;
+        }
+        if terms.last() == Some(&BigUint::from(1u8)) {
+            return Err(invalid());
+        }
+        // fold as for `Ratio<i64>`, from infinity at the last term.
+        let (mut p, mut q) = (BigInt::from(1u8), BigInt::default());
+        for term in terms.into_iter().rev() {
+            let next = BigInt::from(term) * &p + q;
+            (p, q) = (next, p);
+        }
+        Ok(num_rational::BigRational::new(whole * &p + q, p))
+    }
+}
+
+#[cfg(feature = "num-rational")]
+#[test]
+fn test_big_rational() {
+    use crate::{from_key, to_key};
+    use num_bigint::BigInt;
+    use num_rational::BigRational;
+    let big = |n: i64, d: i64| BigRational::new(BigInt::from(n), BigInt::from(d));
+    let huge = BigInt::from(u64::MAX) * BigInt::from(u64::MAX);
+    let mut all = vec![
+        BigRational::from_integer(-huge.clone()),
+        BigRational::from_integer(huge.clone()),
+        BigRational::new(BigInt::from(1), huge.clone()),
+        BigRational::new(BigInt::from(-1), huge.clone()),
+        BigRational::new(huge.clone() + 1u8, huge.clone()),
+        big(i64::MIN, 3),
+    ];
+    for n in -20..=20 {
+        for d in 1..=12 {
+            all.push(big(n, d));
+        }
+    }
+    for v in &all {
+        assert_eq!(from_key::<BigRational>(to_key(v.clone())).unwrap(), *v);
+    }
+    let mut by_value = all.clone();
+    by_value.sort();
+    by_value.dedup();
+    let mut by_key = all.clone();
+    by_key.sort_by_key(|v| to_key(v.clone()));
+    by_key.dedup();
+    assert_eq!(by_key, by_value);
+    assert!(from_key::<BigRational>(vec![3]).is_err());
+    // [0; 1, 1] is written [0; 2].
+    let mut one_one = vec![1];
+    write_big_term(Some(&1u8.into()), 255, &mut one_one).unwrap();
+    write_big_term(Some(&1u8.into()), 0, &mut one_one).unwrap();
+    write_big_term(None, 255, &mut one_one).unwrap();
+    assert!(from_key::<BigRational>(one_one).is_err());
+}
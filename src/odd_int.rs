@@ -0,0 +1,170 @@
+//! 3 and 6 byte integers, for values where 4 or 8 bytes is wasteful.
+
+use crate::codec::FixedWidth;
+use crate::key::{read_fixed, write_fixed};
+use crate::IndexKey;
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind, Read, Write};
+
+macro_rules! odd_unsigned {
+    ($name:ident, $inner:ty, $n:expr) => {
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name($inner);
+
+        impl $name {
+            pub const MIN: $name = $name(0);
+            pub const MAX: $name = $name((1 << ($n * 8)) - 1);
+
+            pub fn new(value: $inner) -> Result<$name, Error> {
+                if value > Self::MAX.0 {
+                    return Err(Error::new(ErrorKind::InvalidInput, "value out of range"));
+                }
+                Ok($name(value))
+            }
+
+            pub fn get(self) -> $inner {
+                self.0
+            }
+        }
+
+        impl TryFrom<$inner> for $name {
+            type Error = Error;
+            fn try_from(value: $inner) -> Result<$name, Error> {
+                $name::new(value)
+            }
+        }
+
+        impl From<$name> for $inner {
+            fn from(value: $name) -> $inner {
+                value.0
+            }
+        }
+
+        impl FixedWidth for $name {
+            type Bytes = [u8; $n];
+            fn encode(self) -> [u8; $n] {
+                let mut bytes = [0; $n];
+                bytes.copy_from_slice(&self.0.to_be_bytes()[std::mem::size_of::<$inner>() - $n..]);
+                bytes
+            }
+            fn decode(bytes: [u8; $n]) -> $name {
+                let mut wide = [0; std::mem::size_of::<$inner>()];
+                wide[std::mem::size_of::<$inner>() - $n..].copy_from_slice(&bytes);
+                $name(<$inner>::from_be_bytes(wide))
+            }
+        }
+
+        impl IndexKey for $name {
+            fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+                write_fixed(self, result)
+            }
+            fn from_key<R: Read>(key: &mut R) -> Result<$name, Error> {
+                read_fixed(key)
+            }
+        }
+    };
+}
+
+odd_unsigned!(U24, u32, 3);
+odd_unsigned!(U48, u64, 6);
+
+// stored offset by MIN so the unsigned big-endian bytes sort like the value.
+macro_rules! odd_signed {
+    ($name:ident, $inner:ty, $unsigned:ident, $n:expr) => {
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name($inner);
+
+        impl $name {
+            pub const MIN: $name = $name(-(1 << ($n * 8 - 1)));
+            pub const MAX: $name = $name((1 << ($n * 8 - 1)) - 1);
+
+            pub fn new(value: $inner) -> Result<$name, Error> {
+                if !(Self::MIN.0..=Self::MAX.0).contains(&value) {
+                    return Err(Error::new(ErrorKind::InvalidInput, "value out of range"));
+                }
+                Ok($name(value))
+            }
+
+            pub fn get(self) -> $inner {
+                self.0
+            }
+        }
+
+        impl TryFrom<$inner> for $name {
+            type Error = Error;
+            fn try_from(value: $inner) -> Result<$name, Error> {
+                $name::new(value)
+            }
+        }
+
+        impl From<$name> for $inner {
+            fn from(value: $name) -> $inner {
+                value.0
+            }
+        }
+
+        impl FixedWidth for $name {
+            type Bytes = [u8; $n];
+            fn encode(self) -> [u8; $n] {
+                $unsigned((self.0 - Self::MIN.0) as _).encode()
+            }
+            fn decode(bytes: [u8; $n]) -> $name {
+                $name($unsigned::decode(bytes).0 as $inner + Self::MIN.0)
+            }
+        }
+
+        impl IndexKey for $name {
+            fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+                write_fixed(self, result)
+            }
+            fn from_key<R: Read>(key: &mut R) -> Result<$name, Error> {
+                read_fixed(key)
+            }
+        }
+    };
+}
+
+odd_signed!(I24, i32, U24, 3);
+odd_signed!(I48, i64, U48, 6);
+
+#[test]
+fn test_odd_int() {
+    use crate::{from_key, to_key};
+    assert!(U24::new(1 << 24).is_err());
+    assert!(U48::try_from(1u64 << 48).is_err());
+    assert!(I24::new(1 << 23).is_err());
+    assert!(I48::new(-(1 << 47) - 1).is_err());
+    assert_eq!(to_key(U24::new(0x010203).unwrap()), vec![1, 2, 3]);
+    assert_eq!(to_key(U48::MAX), vec![255; 6]);
+    assert_eq!(to_key(I24::MIN), vec![0; 3]);
+
+    let mut list: Vec<I48> = [0i64, -1, 1, -(1 << 47), (1 << 47) - 1, 12345]
+        .iter()
+        .map(|v| I48::new(*v).unwrap())
+        .collect();
+    list.sort_by_key(|v| {
+        assert_eq!(from_key::<I48>(to_key(*v)).unwrap(), *v);
+        to_key(*v)
+    });
+    let sorted: Vec<i64> = list.into_iter().map(i64::from).collect();
+    assert_eq!(sorted, vec![-(1 << 47), -1, 0, 1, 12345, (1 << 47) - 1]);
+
+    let mut list: Vec<U24> = [5u32, 0, 0xffffff, 256]
+        .iter()
+        .map(|v| U24::new(*v).unwrap())
+        .collect();
+    list.sort_by_key(|v| {
+        assert_eq!(from_key::<U24>(to_key(*v)).unwrap(), *v);
+        to_key(*v)
+    });
+    assert_eq!(
+        list.iter().map(|v| v.get()).collect::<Vec<_>>(),
+        vec![0, 5, 256, 0xffffff]
+    );
+    assert_eq!(
+        from_key::<I24>(to_key(I24::new(-7).unwrap()))
+            .unwrap()
+            .get(),
+        -7
+    );
+}
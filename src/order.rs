@@ -0,0 +1,137 @@
+//! fractional indexing for user-ordered lists.
+//!
+//! an [`OrderKey`] is a base-256 fraction in `(0, 1)`, written as its digits
+//! with no trailing zero, so byte order is numeric order and there is always
+//! room for another key between two others. keys grow by about a byte per
+//! eight inserts at the same spot; [`OrderKey::spread`] hands out fresh, short
+//! keys when a list is rebalanced.
+
+use crate::IndexKey;
+use std::io::{Error, ErrorKind, Read, Write};
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OrderKey(Vec<u8>);
+
+impl OrderKey {
+    /// the key of the first item of an empty list.
+    pub fn first() -> OrderKey {
+        OrderKey(vec![128])
+    }
+
+    /// a key strictly between `before` and `after`; `None` is the start or
+    /// end of the list.
+    pub fn between(before: Option<&OrderKey>, after: Option<&OrderKey>) -> Result<OrderKey, Error> {
+        let lo = before.map_or(&[][..], |k| &k.0[..]);
+        let mut hi = after.map(|k| &k.0[..]);
+        if hi.is_some_and(|hi| hi <= lo) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "order keys out of order",
+            ));
+        }
+        let mut digits = vec![];
+        for i in 0.. {
+            let a = u16::from(lo.get(i).copied().unwrap_or(0));
+            // `hi` never runs out while still equal to `lo`, as `hi > lo`.
+            let b = hi.map_or(256, |hi| u16::from(hi.get(i).copied().unwrap_or(0)));
+            if b > a + 1 {
+                digits.push(((a + b) / 2) as u8);
+                break;
+            }
+            digits.push(a as u8);
+            if b == a + 1 {
+                // anything after `lo` from here on stays below `hi`.
+                hi = None;
+            }
+        }
+        Ok(OrderKey(digits))
+    }
+
+    /// `n` evenly spaced keys, as short as possible, for rebalancing a list.
+    pub fn spread(n: usize) -> Vec<OrderKey> {
+        let mut len = 1;
+        while (n as u128 + 1) >> (8 * len) != 0 {
+            len += 1;
+        }
+        let scale = 1u128 << (8 * len);
+        (1..=n as u128)
+            .map(|i| {
+                let value = i * scale / (n as u128 + 1);
+                let mut digits = value.to_be_bytes()[16 - len..].to_vec();
+                while digits.last() == Some(&0) {
+                    digits.pop();
+                }
+                OrderKey(digits)
+            })
+            .collect()
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// whether keys around here have grown past `max_len` and the list
+    /// should be respread.
+    pub fn needs_rebalance(&self, max_len: usize) -> bool {
+        self.0.len() > max_len
+    }
+}
+
+impl IndexKey for OrderKey {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        self.0.to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let digits = Vec::<u8>::from_key(key)?;
+        if digits.last().is_none_or(|d| *d == 0) {
+            return Err(Error::new(ErrorKind::InvalidData, "invalid order key"));
+        }
+        Ok(OrderKey(digits))
+    }
+}
+
+#[test]
+fn test_order_key() {
+    use crate::{from_key, to_key};
+    let first = OrderKey::first();
+    let mut list = vec![first.clone()];
+    // keep inserting at the front, at the back and after the first item.
+    for i in 0..300 {
+        let key = match i % 3 {
+            0 => OrderKey::between(None, Some(&list[0])),
+            1 => OrderKey::between(list.last(), None),
+            _ => OrderKey::between(Some(&list[0]), Some(&list[1])),
+        }
+        .unwrap();
+        list.push(key);
+        list.sort();
+    }
+    assert!(list.windows(2).all(|w| w[0] < w[1]));
+    assert!(list.iter().all(|k| k.as_bytes().last() != Some(&0)));
+    let keys: Vec<_> = list.iter().map(|k| to_key(k.clone())).collect();
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(from_key::<OrderKey>(keys[7].clone()).unwrap(), list[7]);
+    assert!(list.iter().any(|k| k.needs_rebalance(8)));
+
+    let spread = OrderKey::spread(list.len());
+    assert_eq!(spread.len(), list.len());
+    assert!(spread.windows(2).all(|w| w[0] < w[1]));
+    assert!(spread.iter().all(|k| k.len() <= 2 && !k.is_empty()));
+    assert_eq!(OrderKey::spread(1), vec![first.clone()]);
+    assert!(OrderKey::spread(0).is_empty());
+
+    assert!(OrderKey::between(Some(&first), Some(&first)).is_err());
+    assert_eq!(
+        OrderKey::between(Some(&OrderKey(vec![1])), Some(&OrderKey(vec![2]))).unwrap(),
+        OrderKey(vec![1, 128])
+    );
+    assert!(from_key::<OrderKey>(to_key(vec![5u8, 0])).is_err());
+}
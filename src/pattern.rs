@@ -0,0 +1,194 @@
+//! field-wise filters over encoded keys.
+//!
+//! a [`KeyPattern`] such as `tenant=42, user=*, ts>1700000000` is compiled
+//! against a [`Schema`] into a [`KeyPredicate`]. literals are encoded once at
+//! compile time and every field is compared as encoded bytes, which sort like
+//! the values, so matching a key decodes nothing.
+//!
+//! clauses are `name op literal` with `op` one of `=`, `!=`, `<`, `<=`, `>`,
+//! `>=`; `name=*` matches anything. literals are written as in
+//! [`spec`](crate::spec); strings may be double quoted to keep leading or
+//! trailing spaces. literals cannot contain commas.
+
+use crate::codec::{raw_fields, FieldWidth};
+use crate::schema::Schema;
+use crate::spec::encode_value;
+use std::cmp::Ordering;
+use std::io::{Error, ErrorKind};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    fn accepts(self, ord: Ordering) -> bool {
+        match self {
+            Op::Eq => ord == Ordering::Equal,
+            Op::Ne => ord != Ordering::Equal,
+            Op::Lt => ord == Ordering::Less,
+            Op::Le => ord != Ordering::Greater,
+            Op::Gt => ord == Ordering::Greater,
+            Op::Ge => ord != Ordering::Less,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Clause {
+    pub field: String,
+    /// `None` for `field=*`.
+    pub test: Option<(Op, String)>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct KeyPattern {
+    pub clauses: Vec<Clause>,
+}
+
+fn invalid(msg: String) -> Error {
+    Error::new(ErrorKind::InvalidInput, msg)
+}
+
+impl KeyPattern {
+    pub fn parse(pattern: &str) -> Result<KeyPattern, Error> {
+        let mut clauses = vec![];
+        for clause in pattern.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+            let at = clause
+                .find(['=', '!', '<', '>'])
+                .ok_or_else(|| invalid(format!("missing operator in {:?}", clause)))?;
+            let field = clause[..at].trim();
+            let rest = &clause[at..];
+            let (op, literal) = [
+                ("!=", Op::Ne),
+                ("<=", Op::Le),
+                (">=", Op::Ge),
+                ("=", Op::Eq),
+                ("<", Op::Lt),
+                (">", Op::Gt),
+            ]
+            .iter()
+            .find_map(|(text, op)| rest.strip_prefix(text).map(|lit| (*op, lit.trim())))
+            .ok_or_else(|| invalid(format!("invalid operator in {:?}", clause)))?;
+            if field.is_empty() {
+                return Err(invalid(format!("missing field in {:?}", clause)));
+            }
+            let test = match (op, literal) {
+                (Op::Eq, "*") => None,
+                _ => {
+                    let literal = literal
+                        .strip_prefix('"')
+                        .and_then(|l| l.strip_suffix('"'))
+                        .unwrap_or(literal);
+                    Some((op, literal.to_owned()))
+                }
+            };
+            clauses.push(Clause {
+                field: field.to_owned(),
+                test,
+            });
+        }
+        Ok(KeyPattern { clauses })
+    }
+
+    /// fails on unknown fields and literals that do not parse as their field.
+    pub fn compile(&self, schema: &Schema) -> Result<KeyPredicate, Error> {
+        let mut tests = vec![];
+        for clause in &self.clauses {
+            let index = schema
+                .position(&clause.field)
+                .ok_or_else(|| invalid(format!("unknown field {}", clause.field)))?;
+            if let Some((op, literal)) = &clause.test {
+                let field = schema.fields()[index].1;
+                tests.push((index, *op, encode_value(field.type_name(), literal)?));
+            }
+        }
+        tests.sort_by_key(|(index, _, _)| *index);
+        let used = tests.last().map_or(0, |(index, _, _)| index + 1);
+        Ok(KeyPredicate {
+            widths: schema.widths()[..used].to_vec(),
+            tests,
+        })
+    }
+}
+
+/// a compiled [`KeyPattern`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct KeyPredicate {
+    /// layout up to the last tested field; later fields are never looked at.
+    widths: Vec<FieldWidth>,
+    tests: Vec<(usize, Op, Vec<u8>)>,
+}
+
+impl KeyPredicate {
+    /// a key too short for a tested field does not match.
+    pub fn matches(&self, key: &[u8]) -> bool {
+        let mut tests = self.tests.iter().peekable();
+        for (index, field) in raw_fields(key, &self.widths).enumerate() {
+            while let Some((_, op, literal)) = tests.next_if(|(i, _, _)| *i == index) {
+                if let FieldWidth::Fixed(width) = self.widths[index] {
+                    if field.len() != width {
+                        return false;
+                    }
+                }
+                if !op.accepts(field.cmp(&literal[..])) {
+                    return false;
+                }
+            }
+        }
+        tests.next().is_none()
+    }
+}
+
+#[test]
+fn test_key_pattern() {
+    use crate::schema::Field;
+    use crate::to_key;
+    let schema = Schema::new(&[
+        ("tenant", Field::U64),
+        ("user", Field::String),
+        ("ts", Field::I64),
+        ("score", Field::F64),
+    ])
+    .unwrap();
+    let pattern = KeyPattern::parse("tenant=42, user=*, ts>1700000000").unwrap();
+    assert_eq!(pattern.clauses.len(), 3);
+    let predicate = pattern.compile(&schema).unwrap();
+    let key = |tenant: u64, user: &str, ts: i64| to_key((tenant, user.to_owned(), ts, 0.5f64));
+    assert!(predicate.matches(&key(42, "ann", 1_700_000_001)));
+    assert!(predicate.matches(&key(42, "", i64::MAX)));
+    assert!(!predicate.matches(&key(42, "ann", 1_700_000_000)));
+    assert!(!predicate.matches(&key(41, "ann", 1_800_000_000)));
+    assert!(!predicate.matches(&key(42, "ann", -5)));
+    assert!(!predicate.matches(&key(42, "ann", 1_800_000_000)[..12]));
+
+    let predicate = KeyPattern::parse("user >= \"b\", user<c , score != -0.5, score<=2")
+        .unwrap()
+        .compile(&schema)
+        .unwrap();
+    assert!(predicate.matches(&to_key((1u64, "bob".to_owned(), 0i64, 2.0f64))));
+    assert!(!predicate.matches(&to_key((1u64, "c".to_owned(), 0i64, 1.0f64))));
+    assert!(!predicate.matches(&to_key((1u64, "b".to_owned(), 0i64, -0.5f64))));
+    assert!(!predicate.matches(&to_key((1u64, "b".to_owned(), 0i64, 2.5f64))));
+    assert!(KeyPattern::parse("")
+        .unwrap()
+        .compile(&schema)
+        .unwrap()
+        .matches(&[]));
+
+    assert!(KeyPattern::parse("tenant 42").is_err());
+    assert!(KeyPattern::parse("=42").is_err());
+    assert!(KeyPattern::parse("tenant=42")
+        .unwrap()
+        .compile(&Schema::new(&[]).unwrap())
+        .is_err());
+    assert!(KeyPattern::parse("tenant=-1")
+        .unwrap()
+        .compile(&schema)
+        .is_err());
+}
@@ -0,0 +1,274 @@
+//! picking a secondary index for a set of predicates.
+//!
+//! every registered [`Schema`] lays out the same logical record in a different
+//! field order. an index can serve equality predicates on a leading run of its
+//! fields plus range predicates on the field right after; everything else is
+//! left as residual filtering. [`IndexSet::choose_index`] picks the index with
+//! the smallest expected scan when every index has a [`Histogram`], and the
+//! longest usable prefix otherwise.
+
+use crate::histogram::Histogram;
+use crate::pattern::{Clause, Op};
+use crate::range::{prefix_successor, KeyRange};
+use crate::schema::Schema;
+use crate::spec::encode_value;
+use std::cmp::Ordering;
+use std::io::{Error, ErrorKind};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Plan<'a> {
+    pub name: &'a str,
+    pub schema: &'a Schema,
+    /// the index keys to scan.
+    pub range: KeyRange,
+    /// predicates the range does not already guarantee.
+    pub residual: Vec<Clause>,
+    /// equality fields used, then whether a range bound was used.
+    pub prefix: (usize, bool),
+    /// expected rows in `range`, if the index has a histogram.
+    pub estimate: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct IndexSet {
+    indexes: Vec<(String, Schema)>,
+    histograms: Vec<(String, Histogram)>,
+}
+
+/// the first unused predicate `field op literal`, and its literal.
+fn find(predicates: &[Clause], used: &[bool], field: &str, op: Op) -> Option<(usize, String)> {
+    predicates
+        .iter()
+        .enumerate()
+        .find_map(|(i, c)| match &c.test {
+            Some((o, literal)) if !used[i] && c.field == field && *o == op => {
+                Some((i, literal.clone()))
+            }
+            _ => None,
+        })
+}
+
+fn plan<'a>(
+    name: &'a str,
+    schema: &'a Schema,
+    histogram: Option<&Histogram>,
+    predicates: &[Clause],
+) -> Result<Plan<'a>, Error> {
+    let mut used = vec![false; predicates.len()];
+    let mut prefix = vec![];
+    let mut eq_fields = 0;
+    let (mut lower, mut upper) = (None, None);
+    for (field_name, field) in schema.fields() {
+        if let Some((i, literal)) = find(predicates, &used, field_name, Op::Eq) {
+            prefix.extend(encode_value(field.type_name(), &literal)?);
+            used[i] = true;
+            eq_fields += 1;
+            continue;
+        }
+        for op in [Op::Gt, Op::Ge] {
+            if lower.is_none() {
+                if let Some((i, literal)) = find(predicates, &used, field_name, op) {
+                    lower = Some((op, encode_value(field.type_name(), &literal)?));
+                    used[i] = true;
+                }
+            }
+        }
+        for op in [Op::Lt, Op::Le] {
+            if upper.is_none() {
+                if let Some((i, literal)) = find(predicates, &used, field_name, op) {
+                    upper = Some((op, encode_value(field.type_name(), &literal)?));
+                    used[i] = true;
+                }
+            }
+        }
+        break;
+    }
+    let with = |literal: &[u8]| [&prefix[..], literal].concat();
+    // keys equal to a literal, and anything after it, share `prefix + literal`.
+    let start = match &lower {
+        None => Some(prefix.clone()),
+        Some((Op::Ge, literal)) => Some(with(literal)),
+        Some((_, literal)) => prefix_successor(&with(literal)),
+    };
+    let end = match &upper {
+        None => prefix_successor(&prefix),
+        Some((Op::Lt, literal)) => Some(with(literal)),
+        Some((_, literal)) => prefix_successor(&with(literal)),
+    };
+    let range = match start {
+        Some(start) => KeyRange { start, end },
+        // nothing sorts after the bound.
+        None => KeyRange::new(vec![], vec![]),
+    };
+    let estimate = histogram.map(|h| h.estimate_rows(&range).round() as u64);
+    Ok(Plan {
+        name,
+        schema,
+        range,
+        residual: predicates
+            .iter()
+            .zip(used)
+            .filter(|(c, used)| !used && c.test.is_some())
+            .map(|(c, _)| c.clone())
+            .collect(),
+        prefix: (eq_fields, lower.is_some() || upper.is_some()),
+        estimate,
+    })
+}
+
+impl IndexSet {
+    pub fn new() -> IndexSet {
+        IndexSet::default()
+    }
+
+    pub fn register(&mut self, name: &str, schema: Schema) -> &mut IndexSet {
+        self.indexes.push((name.to_owned(), schema));
+        self
+    }
+
+    /// replaces any histogram already set for `name`.
+    pub fn set_histogram(
+        &mut self,
+        name: &str,
+        histogram: Histogram,
+    ) -> Result<&mut IndexSet, Error> {
+        if !self.indexes.iter().any(|(n, _)| n == name) {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("no index {}", name),
+            ));
+        }
+        self.histograms.retain(|(n, _)| n != name);
+        self.histograms.push((name.to_owned(), histogram));
+        Ok(self)
+    }
+
+    /// plans for every index, in registration order.
+    pub fn plans(&self, predicates: &[Clause]) -> Result<Vec<Plan<'_>>, Error> {
+        self.indexes
+            .iter()
+            .map(|(name, schema)| {
+                let histogram = self.histograms.iter().find(|(n, _)| n == name);
+                plan(name, schema, histogram.map(|(_, h)| h), predicates)
+            })
+            .collect()
+    }
+
+    /// the plan with the fewest expected rows if every index has a histogram,
+    /// else the one with the longest usable prefix; ties go to the longer
+    /// prefix, then to the index registered first.
+    pub fn choose_index(&self, predicates: &[Clause]) -> Result<Plan<'_>, Error> {
+        let plans = self.plans(predicates)?;
+        let estimated = plans.iter().all(|p| p.estimate.is_some());
+        let better = |a: &Plan, b: &Plan| match (estimated, a.estimate.cmp(&b.estimate)) {
+            (true, Ordering::Less) => true,
+            (true, Ordering::Greater) => false,
+            _ => a.prefix > b.prefix,
+        };
+        let mut best: Option<Plan> = None;
+        for plan in plans {
+            if best.as_ref().is_none_or(|b| better(&plan, b)) {
+                best = Some(plan);
+            }
+        }
+        best.ok_or_else(|| Error::new(ErrorKind::NotFound, "no index registered"))
+    }
+}
+
+#[test]
+fn test_choose_index() {
+    use crate::pattern::KeyPattern;
+    use crate::schema::Field;
+    use crate::to_key;
+    let mut set = IndexSet::new();
+    set.register(
+        "by_user",
+        Schema::new(&[
+            ("user", Field::String),
+            ("ts", Field::U64),
+            ("id", Field::U32),
+        ])
+        .unwrap(),
+    )
+    .register(
+        "by_tenant_ts",
+        Schema::new(&[
+            ("tenant", Field::U16),
+            ("ts", Field::U64),
+            ("id", Field::U32),
+        ])
+        .unwrap(),
+    );
+    let clauses = |p: &str| KeyPattern::parse(p).unwrap().clauses;
+
+    let plan = set
+        .choose_index(&clauses("tenant=7, ts>=100, ts<200, user=*, id!=3"))
+        .unwrap();
+    assert_eq!(plan.name, "by_tenant_ts");
+    assert_eq!(plan.prefix, (1, true));
+    assert_eq!(plan.residual, clauses("id!=3"));
+    for (key, inside) in [
+        ((7u16, 100u64, 0u32), true),
+        ((7, 199, u32::MAX), true),
+        ((7, 99, u32::MAX), false),
+        ((7, 200, 0), false),
+        ((8, 150, 0), false),
+    ] {
+        assert_eq!(plan.range.contains(&to_key(key)), inside, "{:?}", key);
+    }
+
+    let plan = set.choose_index(&clauses("user=ann, ts>5, ts<=9")).unwrap();
+    assert_eq!(plan.name, "by_user");
+    let key = |ts: u64| to_key(("ann".to_owned(), ts, 1u32));
+    assert!(!plan.range.contains(&key(5)) && plan.range.contains(&key(6)));
+    assert!(plan.range.contains(&key(9)) && !plan.range.contains(&key(10)));
+
+    let plan = set.choose_index(&clauses("id=1")).unwrap();
+    assert_eq!((plan.name, plan.prefix), ("by_user", (0, false)));
+    assert_eq!(plan.range, KeyRange::all());
+    assert_eq!(plan.residual, clauses("id=1"));
+
+    let plan = set
+        .choose_index(&clauses("tenant=1, ts>18446744073709551615"))
+        .unwrap();
+    assert!(plan.range.is_empty());
+    assert!(set.choose_index(&clauses("tenant=x")).is_err());
+    assert!(IndexSet::new().choose_index(&[]).is_err());
+}
+
+#[test]
+fn test_choose_index_by_estimate() {
+    use crate::pattern::KeyPattern;
+    use crate::schema::Field;
+    use crate::to_key;
+    let mut set = IndexSet::new();
+    set.register(
+        "by_status",
+        Schema::new(&[("status", Field::U8), ("id", Field::U32)]).unwrap(),
+    )
+    .register(
+        "by_day",
+        Schema::new(&[("day", Field::U16), ("id", Field::U32)]).unwrap(),
+    );
+    let clauses = KeyPattern::parse("status=1, day>=10, day<20")
+        .unwrap()
+        .clauses;
+    // without histograms the equality prefix wins.
+    assert_eq!(set.choose_index(&clauses).unwrap().name, "by_status");
+
+    // almost every row has status 1, while days spread over 1000 values.
+    let status = (0..1000u32).map(|id| to_key((u8::from(id % 100 != 0), id)));
+    let day = (0..1000u32).map(|id| to_key((id as u16, id)));
+    set.set_histogram("by_status", Histogram::build(status.collect(), 16, 1000))
+        .unwrap()
+        .set_histogram("by_day", Histogram::build(day.collect(), 16, 1000))
+        .unwrap();
+    let plan = set.choose_index(&clauses).unwrap();
+    assert_eq!(plan.name, "by_day");
+    assert!(plan.estimate.unwrap() < 50);
+    assert_eq!(
+        plan.residual,
+        KeyPattern::parse("status=1").unwrap().clauses
+    );
+    assert!(set.set_histogram("missing", Histogram::default()).is_err());
+}
@@ -0,0 +1,108 @@
+//! delay-queue keys on an ordered store.
+//!
+//! messages are keyed `(topic, visible_at, seq)`, so scanning a topic from the
+//! start yields the messages that became visible first. a consumer scans
+//! [`QueueKey::ready_range`], and hides a message it is working on, or retries
+//! one later, by moving it to a new `visible_at` with [`QueueKey::requeue`].
+
+use crate::range::{prefix_successor, KeyRange};
+use crate::{to_key, IndexKey};
+use std::io::{Error, Read, Write};
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct QueueKey<T> {
+    pub topic: T,
+    pub visible_at: u64,
+    /// breaks ties between messages visible at the same time, e.g. a counter.
+    pub seq: u64,
+}
+
+impl<T: IndexKey + Clone> QueueKey<T> {
+    pub fn new(topic: T, visible_at: u64, seq: u64) -> QueueKey<T> {
+        QueueKey {
+            topic,
+            visible_at,
+            seq,
+        }
+    }
+
+    /// messages of `topic` visible at `now`, oldest first.
+    pub fn ready_range(topic: T, now: u64) -> KeyRange {
+        let start = to_key(topic.clone());
+        let end = match now.checked_add(1) {
+            Some(after) => Some(to_key((topic, after))),
+            None => prefix_successor(&start),
+        };
+        KeyRange { start, end }
+    }
+
+    /// every message of `topic`, visible or not.
+    pub fn topic_range(topic: T) -> KeyRange {
+        KeyRange::prefix(to_key(topic))
+    }
+
+    /// the same message visible again at `visible_at`; delete `self` and
+    /// write the result in one batch.
+    pub fn requeue(&self, visible_at: u64) -> QueueKey<T> {
+        QueueKey {
+            visible_at,
+            ..self.clone()
+        }
+    }
+
+    /// requeue with exponential backoff: `base << attempt` after `now`,
+    /// capped at `max`.
+    pub fn requeue_backoff(&self, now: u64, attempt: u32, base: u64, max: u64) -> QueueKey<T> {
+        let delay = base.checked_shl(attempt).unwrap_or(u64::MAX).min(max);
+        self.requeue(now.saturating_add(delay))
+    }
+}
+
+impl<T: IndexKey> IndexKey for QueueKey<T> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        (self.topic, self.visible_at, self.seq).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let (topic, visible_at, seq) = <(T, u64, u64)>::from_key(key)?;
+        Ok(QueueKey {
+            topic,
+            visible_at,
+            seq,
+        })
+    }
+}
+
+#[test]
+fn test_queue_key() {
+    use crate::from_key;
+    let topic = "emails".to_owned();
+    let mut keys = [
+        to_key(QueueKey::new(topic.clone(), 200, 1)),
+        to_key(QueueKey::new(topic.clone(), 100, 2)),
+        to_key(QueueKey::new(topic.clone(), 100, 1)),
+        to_key(QueueKey::new("sms".to_owned(), 0, 0)),
+    ];
+    keys.sort();
+    let ready = QueueKey::ready_range(topic.clone(), 150);
+    let visible: Vec<_> = keys
+        .iter()
+        .filter(|k| ready.contains(k))
+        .map(|k| from_key::<QueueKey<String>>(k.clone()).unwrap())
+        .map(|q| (q.visible_at, q.seq))
+        .collect();
+    assert_eq!(visible, vec![(100, 1), (100, 2)]);
+    assert!(QueueKey::ready_range(topic.clone(), 200).contains(&keys[2]));
+    assert!(QueueKey::ready_range(topic.clone(), u64::MAX).contains(&keys[2]));
+    assert_eq!(
+        keys.iter()
+            .filter(|k| QueueKey::topic_range(topic.clone()).contains(k))
+            .count(),
+        3
+    );
+
+    let message = QueueKey::new(topic, 100, 2);
+    assert_eq!(message.requeue(500).seq, 2);
+    assert_eq!(message.requeue_backoff(1000, 3, 10, 60).visible_at, 1060);
+    assert_eq!(message.requeue_backoff(1000, 2, 10, 60).visible_at, 1040);
+    assert_eq!(message.requeue_backoff(1000, 99, 10, 60).visible_at, 1060);
+}
@@ -0,0 +1,93 @@
+//! half-open ranges over encoded keys.
+
+use std::ops::Bound;
+
+/// `[start, end)` over encoded keys; `end: None` is unbounded.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct KeyRange {
+    pub start: Vec<u8>,
+    pub end: Option<Vec<u8>>,
+}
+
+impl KeyRange {
+    pub fn new(start: Vec<u8>, end: Vec<u8>) -> KeyRange {
+        KeyRange {
+            start,
+            end: Some(end),
+        }
+    }
+
+    pub fn all() -> KeyRange {
+        KeyRange::default()
+    }
+
+    pub fn from(start: Vec<u8>) -> KeyRange {
+        KeyRange { start, end: None }
+    }
+
+    /// every key starting with `prefix`.
+    pub fn prefix(prefix: Vec<u8>) -> KeyRange {
+        let end = prefix_successor(&prefix);
+        KeyRange { start: prefix, end }
+    }
+
+    pub fn contains(&self, key: &[u8]) -> bool {
+        key >= &self.start[..] && self.end.as_ref().is_none_or(|end| key < &end[..])
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.end.as_ref().is_some_and(|end| *end <= self.start)
+    }
+
+    pub fn overlaps(&self, other: &KeyRange) -> bool {
+        let before = |a: &KeyRange, b: &KeyRange| a.end.as_ref().is_some_and(|end| *end <= b.start);
+        !self.is_empty() && !other.is_empty() && !before(self, other) && !before(other, self)
+    }
+
+    /// bounds usable with `BTreeMap<Vec<u8>, _>::range`.
+    pub fn bounds(&self) -> (Bound<&[u8]>, Bound<&[u8]>) {
+        (
+            Bound::Included(&self.start[..]),
+            match &self.end {
+                Some(end) => Bound::Excluded(&end[..]),
+                None => Bound::Unbounded,
+            },
+        )
+    }
+}
+
+/// smallest key greater than every key starting with `prefix`, `None` if unbounded.
+pub fn prefix_successor(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut end = prefix.to_vec();
+    while let Some(last) = end.pop() {
+        if last != 255 {
+            end.push(last + 1);
+            return Some(end);
+        }
+    }
+    None
+}
+
+#[test]
+fn test_key_range() {
+    use crate::to_key;
+    use std::collections::BTreeMap;
+    let range = KeyRange::prefix(to_key("ab".to_owned()));
+    assert!(range.contains(&to_key(("ab".to_owned(), 1u8))));
+    assert!(!range.contains(&to_key(("abc".to_owned(), 1u8))));
+    assert!(!range.contains(&to_key(("a".to_owned(), 1u8))));
+    assert_eq!(prefix_successor(&[1, 255, 255]), Some(vec![2]));
+    assert_eq!(prefix_successor(&[255]), None);
+    assert!(KeyRange::prefix(vec![255]).contains(&[255, 255, 1]));
+
+    assert!(KeyRange::new(vec![1], vec![3]).overlaps(&KeyRange::from(vec![2])));
+    assert!(!KeyRange::new(vec![1], vec![2]).overlaps(&KeyRange::from(vec![2])));
+    assert!(!KeyRange::new(vec![2], vec![2]).overlaps(&KeyRange::all()));
+
+    let map: BTreeMap<Vec<u8>, ()> = (0u8..10).map(|i| (vec![i], ())).collect();
+    let keys: Vec<_> = map
+        .range::<[u8], _>(KeyRange::new(vec![3], vec![5]).bounds())
+        .map(|(k, _)| k[0])
+        .collect();
+    assert_eq!(keys, vec![3, 4]);
+}
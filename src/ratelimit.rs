@@ -0,0 +1,99 @@
+//! window keys for rate limiters kept in a key-value store.
+//!
+//! counters live under `(subject, window_start)` with `window_start` a multiple
+//! of the window width. a tumbling limiter reads one key; a sliding one reads
+//! the current and previous windows through [`WindowKey::windows_covering`]
+//! and weighs the previous count with [`previous_weight`].
+
+use crate::range::{prefix_successor, KeyRange};
+use crate::{to_key, IndexKey};
+use std::io::{Error, Read, Write};
+
+/// start of the window containing `ts`. `window` must not be 0.
+pub fn window_start(ts: u64, window: u64) -> u64 {
+    assert!(window > 0, "zero-width window");
+    ts - ts % window
+}
+
+/// the share of the previous window still inside a sliding window ending at
+/// `now`, for the usual `current + previous * weight` estimate.
+pub fn previous_weight(now: u64, window: u64) -> f64 {
+    let elapsed = now - window_start(now, window);
+    1.0 - elapsed as f64 / window as f64
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WindowKey<S> {
+    pub subject: S,
+    pub start: u64,
+}
+
+impl<S: IndexKey + Clone> WindowKey<S> {
+    /// the window `now` falls into.
+    pub fn tumbling(subject: S, now: u64, window: u64) -> WindowKey<S> {
+        WindowKey {
+            subject,
+            start: window_start(now, window),
+        }
+    }
+
+    /// the windows of `subject` overlapping `[now - lookback, now]`; a
+    /// `lookback` of `window` covers the current and previous window.
+    pub fn windows_covering(subject: S, now: u64, window: u64, lookback: u64) -> KeyRange {
+        let first = window_start(now.saturating_sub(lookback), window);
+        let last = window_start(now, window);
+        let start = to_key((subject.clone(), first));
+        match last.checked_add(1) {
+            Some(end) => KeyRange::new(start, to_key((subject, end))),
+            None => KeyRange {
+                start,
+                end: prefix_successor(&to_key(subject)),
+            },
+        }
+    }
+
+    /// every window of `subject`, for clean-up.
+    pub fn subject_range(subject: S) -> KeyRange {
+        KeyRange::prefix(to_key(subject))
+    }
+}
+
+impl<S: IndexKey> IndexKey for WindowKey<S> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        (self.subject, self.start).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let (subject, start) = <(S, u64)>::from_key(key)?;
+        Ok(WindowKey { subject, start })
+    }
+}
+
+#[test]
+fn test_window_key() {
+    use crate::from_key;
+    let key = WindowKey::tumbling("ip:1.2.3.4".to_owned(), 125, 60);
+    assert_eq!(key.start, 120);
+    assert_eq!(
+        from_key::<WindowKey<String>>(to_key(key.clone())).unwrap(),
+        key
+    );
+    assert_eq!(previous_weight(135, 60), 0.75);
+
+    let range = WindowKey::windows_covering("ip:1.2.3.4".to_owned(), 125, 60, 60);
+    let window = |subject: &str, start: u64| {
+        to_key(WindowKey {
+            subject: subject.to_owned(),
+            start,
+        })
+    };
+    assert!(!range.contains(&window("ip:1.2.3.4", 0)));
+    assert!(range.contains(&window("ip:1.2.3.4", 60)));
+    assert!(range.contains(&window("ip:1.2.3.4", 120)));
+    assert!(!range.contains(&window("ip:1.2.3.4", 180)));
+    assert!(!range.contains(&window("ip:1.2.3.5", 120)));
+
+    let range = WindowKey::windows_covering(1u8, u64::MAX, 1, 0);
+    assert!(range.contains(&to_key((1u8, u64::MAX))));
+    assert!(!range.contains(&to_key((2u8, 0u64))));
+    assert!(WindowKey::subject_range(1u8).contains(&to_key((1u8, 5u64))));
+}
@@ -0,0 +1,164 @@
+//! routing table: the whole keyspace cut into contiguous ranges, each with an owner.
+
+use crate::range::KeyRange;
+use crate::{to_key, IndexKey};
+use std::io::{Cursor, Error, ErrorKind};
+
+/// range `i` is `[starts[i], starts[i + 1])`; the first start is always empty
+/// and the last range is unbounded, so every key has exactly one owner.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoutingTable<V> {
+    starts: Vec<Vec<u8>>,
+    owners: Vec<V>,
+}
+
+impl<V> RoutingTable<V> {
+    /// one range covering every key.
+    pub fn new(owner: V) -> RoutingTable<V> {
+        RoutingTable {
+            starts: vec![vec![]],
+            owners: vec![owner],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    fn range_at(&self, index: usize) -> KeyRange {
+        KeyRange {
+            start: self.starts[index].clone(),
+            end: self.starts.get(index + 1).cloned(),
+        }
+    }
+
+    fn index_of(&self, key: &[u8]) -> usize {
+        self.starts.partition_point(|s| &s[..] <= key) - 1
+    }
+
+    pub fn lookup(&self, key: &[u8]) -> (KeyRange, &V) {
+        let index = self.index_of(key);
+        (self.range_at(index), &self.owners[index])
+    }
+
+    pub fn lookup_mut(&mut self, key: &[u8]) -> &mut V {
+        let index = self.index_of(key);
+        &mut self.owners[index]
+    }
+
+    pub fn ranges(&self) -> impl Iterator<Item = (KeyRange, &V)> {
+        (0..self.len()).map(move |i| (self.range_at(i), &self.owners[i]))
+    }
+
+    /// every range sharing a key with `range`.
+    pub fn overlapping(&self, range: &KeyRange) -> Vec<(KeyRange, &V)> {
+        if range.is_empty() {
+            return vec![];
+        }
+        let first = self.index_of(&range.start);
+        let last = match &range.end {
+            Some(end) => self.starts.partition_point(|s| s < end),
+            None => self.len(),
+        };
+        (first..last)
+            .map(|i| (self.range_at(i), &self.owners[i]))
+            .collect()
+    }
+
+    /// cut the range containing `at` in two; the upper half goes to `owner`.
+    pub fn split(&mut self, at: Vec<u8>, owner: V) -> Result<(), Error> {
+        let index = self.index_of(&at);
+        if self.starts[index] == at {
+            return Err(Error::new(ErrorKind::InvalidInput, "already a boundary"));
+        }
+        self.starts.insert(index + 1, at);
+        self.owners.insert(index + 1, owner);
+        Ok(())
+    }
+
+    /// remove the boundary `at`; the lower range absorbs the upper one and
+    /// the upper owner is returned.
+    pub fn merge(&mut self, at: &[u8]) -> Result<V, Error> {
+        match self.starts.binary_search_by(|s| s[..].cmp(at)) {
+            Ok(index) if index > 0 => {
+                self.starts.remove(index);
+                Ok(self.owners.remove(index))
+            }
+            _ => Err(Error::new(ErrorKind::InvalidInput, "not a boundary")),
+        }
+    }
+}
+
+impl<V: IndexKey + Clone> RoutingTable<V> {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut result = to_key(self.len() as u64);
+        for (start, owner) in self.starts.iter().zip(self.owners.iter()) {
+            (start.clone(), owner.clone()).to_key(&mut result)?;
+        }
+        Ok(result)
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<RoutingTable<V>, Error> {
+        let mut cur = Cursor::new(bytes);
+        let len = u64::from_key(&mut cur)?;
+        let mut starts = vec![];
+        let mut owners = vec![];
+        for _ in 0..len {
+            let (start, owner) = <(Vec<u8>, V)>::from_key(&mut cur)?;
+            if starts
+                .last()
+                .map_or(!start.is_empty(), |last| *last >= start)
+            {
+                return Err(Error::new(ErrorKind::InvalidData, "unsorted boundaries"));
+            }
+            starts.push(start);
+            owners.push(owner);
+        }
+        if starts.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidData, "empty routing table"));
+        }
+        Ok(RoutingTable { starts, owners })
+    }
+}
+
+#[test]
+fn test_routing_table() {
+    let mut table = RoutingTable::new(1u32);
+    table.split(to_key(100u32), 2).unwrap();
+    table.split(to_key(200u32), 3).unwrap();
+    table.split(to_key(50u32), 4).unwrap();
+    assert!(table.split(to_key(50u32), 5).is_err());
+    assert_eq!(table.len(), 4);
+
+    assert_eq!(*table.lookup(&to_key(0u32)).1, 1);
+    assert_eq!(*table.lookup(&to_key(75u32)).1, 4);
+    assert_eq!(
+        table.lookup(&to_key(150u32)),
+        (KeyRange::new(to_key(100u32), to_key(200u32)), &2)
+    );
+    assert_eq!(table.lookup(&to_key(u32::MAX)).0.end, None);
+
+    let owners: Vec<u32> = table
+        .overlapping(&KeyRange::new(to_key(60u32), to_key(200u32)))
+        .into_iter()
+        .map(|(_, o)| *o)
+        .collect();
+    assert_eq!(owners, vec![4, 2]);
+
+    assert_eq!(table.merge(&to_key(100u32)).unwrap(), 2);
+    assert!(table.merge(&to_key(100u32)).is_err());
+    assert!(table.merge(&[]).is_err());
+    *table.lookup_mut(&to_key(150u32)) = 9;
+    assert_eq!(
+        table.lookup(&to_key(150u32)),
+        (KeyRange::new(to_key(50u32), to_key(200u32)), &9)
+    );
+
+    let decoded = RoutingTable::<u32>::from_bytes(table.to_bytes().unwrap()).unwrap();
+    assert_eq!(decoded, table);
+    assert_eq!(decoded.ranges().count(), 3);
+}
@@ -0,0 +1,194 @@
+//! named field layouts of composite keys, for tooling that works on encoded
+//! keys without the rust types at hand.
+
+use crate::codec::FieldWidth;
+use std::io::{Error, ErrorKind};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Field {
+    Bytes,
+    String,
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    F32,
+    F64,
+}
+
+impl Field {
+    /// encoded bytes of a fixed-width field, 0 for byte strings.
+    pub(crate) fn width(self) -> usize {
+        match self {
+            Field::Bytes | Field::String => 0,
+            Field::Bool | Field::U8 | Field::I8 => 1,
+            Field::U16 | Field::I16 => 2,
+            Field::U32 | Field::I32 | Field::F32 => 4,
+            Field::U64 | Field::I64 | Field::F64 => 8,
+            Field::U128 | Field::I128 => 16,
+        }
+    }
+
+    pub fn field_width(self) -> FieldWidth {
+        match self {
+            Field::Bytes | Field::String => FieldWidth::Escaped,
+            _ => FieldWidth::Fixed(self.width()),
+        }
+    }
+
+    /// the rust type name, as used by [`spec`](crate::spec).
+    pub fn type_name(self) -> &'static str {
+        match self {
+            Field::Bytes => "Vec<u8>",
+            Field::String => "String",
+            Field::Bool => "bool",
+            Field::U8 => "u8",
+            Field::U16 => "u16",
+            Field::U32 => "u32",
+            Field::U64 => "u64",
+            Field::U128 => "u128",
+            Field::I8 => "i8",
+            Field::I16 => "i16",
+            Field::I32 => "i32",
+            Field::I64 => "i64",
+            Field::I128 => "i128",
+            Field::F32 => "f32",
+            Field::F64 => "f64",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Schema {
+    fields: Vec<(String, Field)>,
+}
+
+impl Schema {
+    /// field names must be unique.
+    pub fn new(fields: &[(&str, Field)]) -> Result<Schema, Error> {
+        for (i, (name, _)) in fields.iter().enumerate() {
+            if fields[..i].iter().any(|(other, _)| other == name) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("duplicate field {}", name),
+                ));
+            }
+        }
+        Ok(Schema {
+            fields: fields
+                .iter()
+                .map(|(name, field)| (name.to_string(), *field))
+                .collect(),
+        })
+    }
+
+    pub fn fields(&self) -> &[(String, Field)] {
+        &self.fields
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|(n, _)| n == name)
+    }
+
+    pub fn widths(&self) -> Vec<FieldWidth> {
+        self.fields.iter().map(|(_, f)| f.field_width()).collect()
+    }
+}
+
+/// a key type whose encoding is the fields of a fixed [`Schema`], so it can
+/// be re-encoded by any [`KeyCodec`](crate::transcode::KeyCodec).
+pub trait KeySchema {
+    fn schema() -> Schema;
+}
+
+/// the rust type of each [`Field`], named as the variant, so
+/// [`key_struct!`](crate::key_struct) can check a declared kind against the
+/// field's type.
+pub mod kinds {
+    pub type Bytes = Vec<u8>;
+    pub type String = std::string::String;
+    pub type Bool = bool;
+    pub type U8 = u8;
+    pub type U16 = u16;
+    pub type U32 = u32;
+    pub type U64 = u64;
+    pub type U128 = u128;
+    pub type I8 = i8;
+    pub type I16 = i16;
+    pub type I32 = i32;
+    pub type I64 = i64;
+    pub type I128 = i128;
+    pub type F32 = f32;
+    pub type F64 = f64;
+}
+
+/// implements [`IndexKey`](crate::IndexKey), [`KeySchema`] and
+/// [`Explain`](crate::explain::Explain) for a struct,
+/// encoding the listed fields in order. each field names the [`Field`] it
+/// encodes as, e.g. `key_struct!(User { tenant: U64, name: String })`, and a
+/// kind that is not the field's type does not compile:
+///
+/// ```compile_fail
+/// struct User {
+///     tenant: u32,
+/// }
+/// index_key::key_struct!(User { tenant: U64 });
+/// ```
+#[macro_export]
+macro_rules! key_struct {
+    ($name:ident { $($field:ident: $kind:ident),+ $(,)? }) => {
+        impl $crate::IndexKey for $name {
+            fn to_key<W: std::io::Write>(self, result: &mut W) -> Result<&mut W, std::io::Error> {
+                $(<$crate::schema::kinds::$kind as $crate::IndexKey>::to_key(self.$field, result)?;)+
+                Ok(result)
+            }
+            fn from_key<R: std::io::Read>(key: &mut R) -> Result<Self, std::io::Error> {
+                Ok($name {
+                    $($field: <$crate::schema::kinds::$kind as $crate::IndexKey>::from_key(key)?,)+
+                })
+            }
+        }
+        impl $crate::schema::KeySchema for $name {
+            fn schema() -> $crate::schema::Schema {
+                $crate::schema::Schema::new(&[
+                    $((stringify!($field), $crate::schema::Field::$kind),)+
+                ])
+                .unwrap()
+            }
+        }
+        impl $crate::explain::Explain for $name {
+            fn explain_into(path: &str, out: &mut Vec<$crate::explain::Segment>) {
+                let schema = <$name as $crate::schema::KeySchema>::schema();
+                $crate::explain::explain_schema_into(path, &schema, out)
+            }
+        }
+    };
+}
+
+#[test]
+fn test_schema() {
+    let schema = Schema::new(&[("tenant", Field::U64), ("name", Field::String)]).unwrap();
+    assert_eq!(schema.position("name"), Some(1));
+    assert_eq!(schema.position("missing"), None);
+    assert_eq!(
+        schema.widths(),
+        vec![FieldWidth::Fixed(8), FieldWidth::Escaped]
+    );
+    assert_eq!(Field::I16.type_name(), "i16");
+    assert!(Schema::new(&[("a", Field::U8), ("a", Field::Bool)]).is_err());
+}
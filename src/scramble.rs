@@ -0,0 +1,255 @@
+//! deterministic anonymization of keyspaces for shareable fixtures.
+//!
+//! [`scramble_keys`] decodes every key by a schema and replaces each field with
+//! a seeded random value of the same type through a map that is monotonic over
+//! the whole dataset, so sort order, equality and prefix structure survive:
+//!
+//! - fixed-width fields map each distinct value to a random one of the same width;
+//! - byte strings keep their length, and each byte position is remapped on its own;
+//! - strings keep their length in chars, remapped per char position to printable
+//!   ascii where it has room, and to other non-surrogate chars otherwise;
+//! - bools are kept, since the only order-preserving map is the identity.
+
+use crate::codec::FixedWidth;
+use crate::conformance::Rng;
+pub use crate::schema::Field;
+use crate::IndexKey;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{Cursor, Error, ErrorKind, Read};
+
+/// whether an encoded fixed-width value may be produced.
+fn valid(field: Field, encoded: u128) -> bool {
+    match field {
+        Field::F32 => !f32::decode((encoded as u32).to_be_bytes()).is_nan(),
+        Field::F64 => !f64::decode((encoded as u64).to_be_bytes()).is_nan(),
+        _ => true,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Value {
+    Fixed(u128),
+    Bytes(Vec<u8>),
+    Chars(Vec<char>),
+}
+
+fn read_field<R: Read>(field: Field, key: &mut R) -> Result<Value, Error> {
+    match field {
+        Field::Bytes => Ok(Value::Bytes(Vec::<u8>::from_key(key)?)),
+        Field::String => {
+            let bytes = Vec::<u8>::from_key(key)?;
+            let s = String::from_utf8(bytes)
+                .map_err(|_| Error::new(ErrorKind::InvalidData, "invalid utf-8 string"))?;
+            Ok(Value::Chars(s.chars().collect()))
+        }
+        _ => {
+            let mut bytes = [0u8; 16];
+            let width = field.width();
+            key.read_exact(&mut bytes[16 - width..])?;
+            Ok(Value::Fixed(u128::from_be_bytes(bytes)))
+        }
+    }
+}
+
+fn write_field(field: Field, value: Value, result: &mut Vec<u8>) -> Result<(), Error> {
+    match value {
+        Value::Fixed(v) => result.extend_from_slice(&v.to_be_bytes()[16 - field.width()..]),
+        Value::Bytes(bytes) => {
+            bytes.to_key(result)?;
+        }
+        Value::Chars(chars) => {
+            chars.into_iter().collect::<String>().to_key(result)?;
+        }
+    }
+    Ok(())
+}
+
+/// `count` distinct sorted values drawn by `draw` and accepted by `valid`.
+fn sorted_sample<T: Ord>(
+    count: usize,
+    mut draw: impl FnMut() -> T,
+    valid: impl Fn(&T) -> bool,
+) -> Vec<T> {
+    let mut picked = BTreeSet::new();
+    while picked.len() < count {
+        let value = draw();
+        if valid(&value) {
+            picked.insert(value);
+        }
+    }
+    picked.into_iter().collect()
+}
+
+fn monotonic_map<T: Ord + Clone>(
+    used: BTreeSet<T>,
+    draw: impl FnMut() -> T,
+    valid: impl Fn(&T) -> bool,
+) -> BTreeMap<T, T> {
+    let sample = sorted_sample(used.len(), draw, valid);
+    used.into_iter().zip(sample).collect()
+}
+
+/// per-position maps for variable-length values.
+fn positional_maps<T: Ord + Clone>(
+    values: &[&Vec<T>],
+    mut draw: impl FnMut(usize) -> T,
+) -> Vec<BTreeMap<T, T>> {
+    let longest = values.iter().map(|v| v.len()).max().unwrap_or(0);
+    (0..longest)
+        .map(|p| {
+            let used: BTreeSet<T> = values.iter().filter_map(|v| v.get(p).cloned()).collect();
+            let count = used.len();
+            monotonic_map(used, || draw(count), |_| true)
+        })
+        .collect()
+}
+
+/// scrambles `keys` laid out as the fields of `schema` in order; the output
+/// keeps input order. the same keys, seed and schema give the same output.
+pub fn scramble_keys<I: IntoIterator<Item = Vec<u8>>>(
+    keys: I,
+    seed: u64,
+    schema: &[Field],
+) -> Result<Vec<Vec<u8>>, Error> {
+    let mut rows = vec![];
+    for key in keys {
+        let len = key.len() as u64;
+        let mut cursor = Cursor::new(key);
+        let row = schema
+            .iter()
+            .map(|field| read_field(*field, &mut cursor))
+            .collect::<Result<Vec<Value>, Error>>()?;
+        if cursor.position() != len {
+            return Err(Error::new(ErrorKind::InvalidData, "key longer than schema"));
+        }
+        rows.push(row);
+    }
+
+    let mut rng = Rng::new(seed);
+    for (i, field) in schema.iter().enumerate() {
+        let column = rows.iter().map(|row| &row[i]);
+        match field {
+            Field::Bool => (),
+            Field::Bytes => {
+                let values: Vec<&Vec<u8>> = column
+                    .filter_map(|v| match v {
+                        Value::Bytes(b) => Some(b),
+                        _ => None,
+                    })
+                    .collect();
+                let maps = positional_maps(&values, |_| rng.below(256) as u8);
+                for row in rows.iter_mut() {
+                    if let Value::Bytes(bytes) = &mut row[i] {
+                        for (p, b) in bytes.iter_mut().enumerate() {
+                            *b = maps[p][b];
+                        }
+                    }
+                }
+            }
+            Field::String => {
+                let values: Vec<&Vec<char>> = column
+                    .filter_map(|v| match v {
+                        Value::Chars(c) => Some(c),
+                        _ => None,
+                    })
+                    .collect();
+                let maps = positional_maps(&values, |count| {
+                    let (low, high) = if count <= 94 {
+                        (0x21, 0x7e)
+                    } else {
+                        (0x21, 0xd7ff)
+                    };
+                    char::from_u32(low + rng.below((high - low + 1) as u64) as u32).unwrap()
+                });
+                for row in rows.iter_mut() {
+                    if let Value::Chars(chars) = &mut row[i] {
+                        for (p, c) in chars.iter_mut().enumerate() {
+                            *c = maps[p][c];
+                        }
+                    }
+                }
+            }
+            _ => {
+                let used: BTreeSet<u128> = column
+                    .filter_map(|v| match v {
+                        Value::Fixed(x) => Some(*x),
+                        _ => None,
+                    })
+                    .collect();
+                let bits = field.width() as u32 * 8;
+                let map = monotonic_map(
+                    used,
+                    || {
+                        let wide = (rng.next_u64() as u128) << 64 | rng.next_u64() as u128;
+                        wide >> (128 - bits)
+                    },
+                    |v| valid(*field, *v),
+                );
+                for row in rows.iter_mut() {
+                    if let Value::Fixed(x) = &mut row[i] {
+                        *x = map[x];
+                    }
+                }
+            }
+        }
+    }
+
+    rows.into_iter()
+        .map(|row| {
+            let mut key = vec![];
+            for (field, value) in schema.iter().zip(row) {
+                write_field(*field, value, &mut key)?;
+            }
+            Ok(key)
+        })
+        .collect()
+}
+
+#[test]
+fn test_scramble_keys() {
+    use crate::{from_key, to_key};
+    type Row = (String, u32, Vec<u8>, f64, bool, i8);
+    let schema = [
+        Field::String,
+        Field::U32,
+        Field::Bytes,
+        Field::F64,
+        Field::Bool,
+        Field::I8,
+    ];
+    let rows: Vec<Row> = vec![
+        ("alice".into(), 7, vec![0, 1], -1.5, true, -3),
+        ("alice".into(), 9, vec![0, 1, 2], 0.0, false, 0),
+        ("alicia".into(), 7, vec![], 2.5, true, 5),
+        ("bob".into(), 1_000_000, vec![255], 1e300, false, -128),
+        ("bo".into(), 3, vec![0, 1], -1.5, true, 127),
+        ("çé".into(), 0, vec![9], f64::INFINITY, false, 1),
+    ];
+    let keys: Vec<Vec<u8>> = rows.iter().cloned().map(to_key).collect();
+    let scrambled = scramble_keys(keys.clone(), 42, &schema).unwrap();
+    assert_eq!(scrambled, scramble_keys(keys.clone(), 42, &schema).unwrap());
+    assert_ne!(scrambled, scramble_keys(keys.clone(), 43, &schema).unwrap());
+    assert_ne!(scrambled, keys);
+
+    let decoded: Vec<Row> = scrambled
+        .iter()
+        .map(|k| from_key(k.clone()).unwrap())
+        .collect();
+    for (a, b) in rows.iter().zip(decoded.iter()) {
+        assert_eq!(a.0.chars().count(), b.0.chars().count());
+        assert_eq!(a.2.len(), b.2.len());
+        assert_eq!(a.4, b.4);
+        assert!(!b.3.is_nan());
+    }
+    for i in 0..rows.len() {
+        for j in 0..rows.len() {
+            assert_eq!(keys[i].cmp(&keys[j]), scrambled[i].cmp(&scrambled[j]));
+            // each field keeps its own order too.
+            assert_eq!(rows[i].0.cmp(&rows[j].0), decoded[i].0.cmp(&decoded[j].0));
+            assert_eq!(rows[i].1.cmp(&rows[j].1), decoded[i].1.cmp(&decoded[j].1));
+            assert_eq!(rows[i].2.cmp(&rows[j].2), decoded[i].2.cmp(&decoded[j].2));
+            assert_eq!(rows[i].5.cmp(&rows[j].5), decoded[i].5.cmp(&decoded[j].5));
+        }
+    }
+    assert!(scramble_keys(vec![to_key(1u8)], 0, &[]).is_err());
+}
@@ -0,0 +1,72 @@
+//! session and auth token keys.
+//!
+//! a session is written twice: under `(user, token_hash)` to list or revoke a
+//! user's sessions, and under `(token_hash,)` to validate a token with a single
+//! point read. the two live in separate keyspaces and both come from one
+//! [`SessionKey`], so they cannot drift apart. tokens are only ever stored
+//! hashed.
+
+use crate::content_hash::ContentHash;
+use crate::range::KeyRange;
+use crate::{from_key, to_key, IndexKey};
+use std::io::Error;
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SessionKey<U> {
+    pub user: U,
+    pub token_hash: ContentHash<32>,
+}
+
+impl<U: IndexKey + Clone> SessionKey<U> {
+    pub fn new(user: U, token: &[u8]) -> SessionKey<U> {
+        SessionKey {
+            user,
+            token_hash: ContentHash::sha256(token),
+        }
+    }
+
+    /// `(user, token_hash)`, for the per-user keyspace.
+    pub fn user_key(&self) -> Vec<u8> {
+        to_key((self.user.clone(), self.token_hash))
+    }
+
+    /// `(token_hash,)`, for the validation keyspace.
+    pub fn token_key(&self) -> Vec<u8> {
+        to_key(self.token_hash)
+    }
+
+    /// the user key and the token key, to write together.
+    pub fn keys(&self) -> (Vec<u8>, Vec<u8>) {
+        (self.user_key(), self.token_key())
+    }
+
+    /// the validation key for a presented token.
+    pub fn lookup(token: &[u8]) -> Vec<u8> {
+        to_key(ContentHash::sha256(token))
+    }
+
+    /// every session of `user` in the per-user keyspace.
+    pub fn user_range(user: U) -> KeyRange {
+        KeyRange::prefix(to_key(user))
+    }
+
+    /// decode a key from the per-user keyspace, e.g. to revoke its token key.
+    pub fn from_user_key(key: Vec<u8>) -> Result<SessionKey<U>, Error> {
+        let (user, token_hash) = from_key(key)?;
+        Ok(SessionKey { user, token_hash })
+    }
+}
+
+#[test]
+fn test_session_key() {
+    let session = SessionKey::new(42u64, b"secret-token");
+    let (user_key, token_key) = session.keys();
+    assert_eq!(token_key, SessionKey::<u64>::lookup(b"secret-token"));
+    assert_ne!(token_key, SessionKey::<u64>::lookup(b"other-token"));
+    assert_eq!(token_key.len(), 32);
+    assert!(SessionKey::user_range(42u64).contains(&user_key));
+    assert!(!SessionKey::user_range(43u64).contains(&user_key));
+    let decoded = SessionKey::<u64>::from_user_key(user_key).unwrap();
+    assert_eq!(decoded, session);
+    assert_eq!(decoded.token_key(), token_key);
+}
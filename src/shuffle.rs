@@ -0,0 +1,138 @@
+//! keys in a stable pseudo-random order, for sampling scans.
+//!
+//! a [`Shuffled<T, SEED>`] encodes as a 64-bit hash of `SEED` and the value's
+//! key, then the value itself. records are spread uniformly over the hash space
+//! independent of their natural order, so scanning [`sample_range`] for a
+//! fraction `f` yields about `f` of the records, chosen at random but the same
+//! on every scan. a different seed gives an independent order.
+
+use crate::range::KeyRange;
+use crate::surrogate::hash_id;
+use crate::{to_key, try_to_key, IndexKey};
+use std::io::{Error, ErrorKind, Read, Write};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Shuffled<T, const SEED: u64> {
+    hash: u64,
+    value: T,
+}
+
+fn shuffle_hash(seed: u64, key: &[u8]) -> u64 {
+    hash_id(&[&seed.to_be_bytes()[..], key].concat())
+}
+
+impl<T: IndexKey + Clone, const SEED: u64> Shuffled<T, SEED> {
+    pub fn new(value: T) -> Shuffled<T, SEED> {
+        let hash = shuffle_hash(SEED, &to_key(value.clone()));
+        Shuffled { hash, value }
+    }
+}
+
+impl<T, const SEED: u64> Shuffled<T, SEED> {
+    /// the position in the shuffled order.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T: IndexKey + Clone, const SEED: u64> IndexKey for Shuffled<T, SEED> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        (self.hash, self.value).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let (hash, value) = <(u64, T)>::from_key(key)?;
+        if shuffle_hash(SEED, &try_to_key(value.clone())?) != hash {
+            return Err(Error::new(ErrorKind::InvalidData, "shuffle hash mismatch"));
+        }
+        Ok(Shuffled { hash, value })
+    }
+}
+
+/// the key `fraction` of the way through the hash space; `None` at or past
+/// the end.
+fn hash_at(fraction: f64) -> Option<Vec<u8>> {
+    if fraction >= 1.0 {
+        return None;
+    }
+    if fraction <= 0.0 {
+        return Some(vec![]);
+    }
+    let hash = (fraction * 18_446_744_073_709_551_616.0) as u64;
+    Some(hash.to_be_bytes().to_vec())
+}
+
+/// keys of [`Shuffled`] values in the first `fraction` of the hash space, a
+/// uniform sample of about `fraction` of them. fractions are clamped to
+/// `[0, 1]`.
+pub fn sample_range(fraction: f64) -> KeyRange {
+    sample_range_between(0.0, fraction)
+}
+
+/// keys in the hash space from `from` to `to`, as fractions of it; disjoint
+/// slices give disjoint samples.
+pub fn sample_range_between(from: f64, to: f64) -> KeyRange {
+    match hash_at(from) {
+        Some(start) => KeyRange {
+            start,
+            end: hash_at(to),
+        },
+        None => KeyRange::new(vec![], vec![]),
+    }
+}
+
+#[test]
+fn test_shuffled() {
+    use crate::from_key;
+    let mut keys: Vec<Vec<u8>> = (0..2000u32)
+        .map(|v| to_key(Shuffled::<_, 7>::new(v)))
+        .collect();
+    keys.sort();
+    let order: Vec<u32> = keys
+        .iter()
+        .map(|k| {
+            from_key::<Shuffled<u32, 7>>(k.clone())
+                .unwrap()
+                .into_inner()
+        })
+        .collect();
+    assert_ne!(order, (0..2000).collect::<Vec<_>>());
+    let other: Vec<u64> = (0..20u32)
+        .map(|v| Shuffled::<_, 8>::new(v).hash())
+        .collect();
+    let seven: Vec<u64> = (0..20u32)
+        .map(|v| Shuffled::<_, 7>::new(v).hash())
+        .collect();
+    assert_ne!(other, seven);
+
+    let tenth = sample_range(0.1);
+    let sample: Vec<u32> = keys
+        .iter()
+        .zip(&order)
+        .filter(|(k, _)| tenth.contains(k))
+        .map(|(_, v)| *v)
+        .collect();
+    assert!((140..260).contains(&sample.len()), "{}", sample.len());
+    let mean = sample.iter().map(|v| f64::from(*v)).sum::<f64>() / sample.len() as f64;
+    assert!((800.0..1200.0).contains(&mean), "{}", mean);
+
+    let rest = sample_range_between(0.1, 1.0);
+    assert_eq!(rest.end, None);
+    assert_eq!(
+        keys.iter().filter(|k| rest.contains(k)).count(),
+        2000 - sample.len()
+    );
+    assert_eq!(sample_range(2.0), KeyRange::all());
+    assert!(keys.iter().all(|k| !sample_range(0.0).contains(k)));
+    assert!(sample_range_between(1.0, 1.0).is_empty());
+
+    assert!(from_key::<Shuffled<u32, 8>>(keys[0].clone()).is_err());
+    assert!(from_key::<Shuffled<u32, 7>>(to_key((0u64, 5u32))).is_err());
+}
@@ -0,0 +1,204 @@
+//! encoded key length statistics, for choosing field representations.
+//!
+//! a [`SizeProfile`] counts key lengths per schema name, so a sample of real
+//! keys shows how much each layout costs. lengths are grouped into [`TIERS`]
+//! and summarized with exact percentiles; [`SizeStats::suggestion`] reads
+//! them as a hint between fixed-width, varint and hashed fields.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// upper bounds, inclusive, of the length tiers; longer keys go in a last,
+/// unbounded tier.
+pub const TIERS: [usize; 8] = [8, 16, 32, 64, 128, 256, 512, 1024];
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SizeProfile {
+    // per schema, the number of keys of each length.
+    schemas: BTreeMap<String, BTreeMap<usize, u64>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Representation {
+    /// every key has the same length already.
+    FixedWidth,
+    /// lengths vary but stay short: length-prefixed or varint fields fit.
+    Varint,
+    /// long keys dominate: a 16-byte hash of the long fields is smaller.
+    Hashed,
+}
+
+impl fmt::Display for Representation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Representation::FixedWidth => "fixed-width",
+            Representation::Varint => "varint",
+            Representation::Hashed => "hashed",
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SizeStats {
+    pub count: u64,
+    pub min: usize,
+    pub max: usize,
+    pub mean: f64,
+    pub p50: usize,
+    pub p90: usize,
+    pub p99: usize,
+    /// keys per tier, in the order of [`TIERS`] then the unbounded tier.
+    pub tiers: [u64; 9],
+}
+
+impl SizeStats {
+    fn from_lengths(lengths: &BTreeMap<usize, u64>) -> Option<SizeStats> {
+        let count: u64 = lengths.values().sum();
+        if count == 0 {
+            return None;
+        }
+        // nearest rank: the smallest length covering `p` percent of keys.
+        let percentile = |p: u64| {
+            let rank = (count * p).div_ceil(100).max(1);
+            let mut seen = 0;
+            for (len, n) in lengths {
+                seen += n;
+                if seen >= rank {
+                    return *len;
+                }
+            }
+            unreachable!()
+        };
+        let mut tiers = [0; 9];
+        for (len, n) in lengths {
+            tiers[TIERS.partition_point(|max| max < len)] += n;
+        }
+        let total: f64 = lengths.iter().map(|(len, n)| *len as f64 * *n as f64).sum();
+        Some(SizeStats {
+            count,
+            min: *lengths.keys().next().unwrap(),
+            max: *lengths.keys().next_back().unwrap(),
+            mean: total / count as f64,
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            tiers,
+        })
+    }
+
+    /// fixed-width when every key has the same length, hashed when the median
+    /// key is longer than 32 bytes, varint otherwise.
+    pub fn suggestion(&self) -> Representation {
+        if self.min == self.max {
+            Representation::FixedWidth
+        } else if self.p50 > 32 {
+            Representation::Hashed
+        } else {
+            Representation::Varint
+        }
+    }
+}
+
+impl SizeProfile {
+    pub fn new() -> SizeProfile {
+        SizeProfile::default()
+    }
+
+    pub fn record(&mut self, schema: &str, key: &[u8]) {
+        self.record_len(schema, key.len());
+    }
+
+    pub fn record_len(&mut self, schema: &str, len: usize) {
+        let lengths = self.schemas.entry(schema.to_owned()).or_default();
+        *lengths.entry(len).or_default() += 1;
+    }
+
+    pub fn schemas(&self) -> impl Iterator<Item = &str> {
+        self.schemas.keys().map(String::as_str)
+    }
+
+    /// statistics for one schema, `None` if it has no keys.
+    pub fn stats(&self, schema: &str) -> Option<SizeStats> {
+        SizeStats::from_lengths(self.schemas.get(schema)?)
+    }
+
+    /// statistics over every schema together.
+    pub fn total(&self) -> Option<SizeStats> {
+        let mut all = BTreeMap::new();
+        for (len, n) in self.schemas.values().flatten() {
+            *all.entry(*len).or_default() += n;
+        }
+        SizeStats::from_lengths(&all)
+    }
+
+    /// one line per schema, then the total.
+    pub fn report_text(&self) -> String {
+        let line = |name: &str, s: SizeStats| {
+            format!(
+                "{}: {} keys, {}..{} bytes, mean {:.1}, p50 {} p90 {} p99 {}, {}\n",
+                name,
+                s.count,
+                s.min,
+                s.max,
+                s.mean,
+                s.p50,
+                s.p90,
+                s.p99,
+                s.suggestion()
+            )
+        };
+        let mut text = String::new();
+        for name in self.schemas() {
+            if let Some(stats) = self.stats(name) {
+                text += &line(name, stats);
+            }
+        }
+        if let Some(stats) = self.total() {
+            text += &line("total", stats);
+        }
+        text
+    }
+}
+
+#[test]
+fn test_size_profile() {
+    use crate::to_key;
+    let mut profile = SizeProfile::new();
+    for id in 0..100u64 {
+        profile.record("ids", &to_key((1u32, id)));
+        let url = format!("https://example.com/{}", "x".repeat(id as usize));
+        profile.record("urls", &to_key(url));
+    }
+    profile.record_len("urls", 5000);
+    assert_eq!(profile.schemas().collect::<Vec<_>>(), ["ids", "urls"]);
+
+    let ids = profile.stats("ids").unwrap();
+    assert_eq!((ids.count, ids.min, ids.max, ids.p99), (100, 12, 12, 12));
+    assert_eq!(ids.tiers[1], 100);
+    assert_eq!(ids.suggestion(), Representation::FixedWidth);
+
+    let urls = profile.stats("urls").unwrap();
+    assert_eq!(urls.count, 101);
+    assert_eq!((urls.min, urls.max), (21, 5000));
+    assert_eq!(urls.p50, 71);
+    assert_eq!(urls.p90, 111);
+    assert_eq!(urls.tiers[2] + urls.tiers[3] + urls.tiers[4], 100);
+    assert_eq!(urls.tiers[8], 1);
+    assert_eq!(urls.suggestion(), Representation::Hashed);
+
+    let total = profile.total().unwrap();
+    assert_eq!(total.count, 201);
+    assert_eq!((total.p50, total.min), (21, 12));
+    assert_eq!(total.suggestion(), Representation::Varint);
+    assert!(profile.stats("none").is_none());
+    assert!(SizeProfile::new().total().is_none());
+
+    let report = profile.report_text();
+    assert_eq!(report.lines().count(), 3);
+    assert!(report.starts_with("ids: 100 keys, 12..12 bytes"));
+    assert!(report
+        .lines()
+        .last()
+        .unwrap()
+        .starts_with("total: 201 keys"));
+}
@@ -0,0 +1,102 @@
+//! sparse composite keys for wide, mostly-empty schemas.
+//!
+//! `SparseTuple((Option<A>, Option<B>, ...))` writes one bitmap byte, the
+//! first field in the highest bit, then only the present fields. absent fields
+//! cost nothing. keys sort by presence pattern first — as the bitmap byte, so
+//! a present first field sorts after an absent one regardless of the others —
+//! and then by the present fields in order.
+
+use crate::IndexKey;
+use std::io::{Error, ErrorKind, Read, Write};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SparseTuple<T>(pub T);
+
+macro_rules! impl_sparse {
+    ( $( $v:ident $i:tt ),+ ) => {
+        impl< $( $v ),+ > IndexKey for SparseTuple<( $( Option<$v>, )+ )>
+        where
+            $( $v : IndexKey ,)+
+        {
+            fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+                let fields = self.0;
+                let mut bitmap = 0u8;
+                $(
+                    if fields.$i.is_some() {
+                        bitmap |= 0x80 >> $i;
+                    }
+                )+
+                result.write_all(&[bitmap])?;
+                $(
+                    if let Some(value) = fields.$i {
+                        value.to_key(result)?;
+                    }
+                )+
+                Ok(result)
+            }
+            fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+                let mut bitmap = [0u8];
+                key.read_exact(&mut bitmap)?;
+                let bitmap = bitmap[0];
+                let used = 0u8 $( | 0x80 >> $i )+;
+                if bitmap & !used != 0 {
+                    return Err(Error::new(ErrorKind::InvalidData, "unknown sparse fields"));
+                }
+                Ok(SparseTuple(( $(
+                    if bitmap & (0x80 >> $i) != 0 {
+                        Some($v::from_key(key)?)
+                    } else {
+                        None
+                    },
+                )+ )))
+            }
+        }
+    }
+}
+
+impl_sparse!(T1 0);
+impl_sparse!(T1 0, T2 1);
+impl_sparse!(T1 0, T2 1, T3 2);
+impl_sparse!(T1 0, T2 1, T3 2, T4 3);
+impl_sparse!(T1 0, T2 1, T3 2, T4 3, T5 4);
+impl_sparse!(T1 0, T2 1, T3 2, T4 3, T5 4, T6 5);
+impl_sparse!(T1 0, T2 1, T3 2, T4 3, T5 4, T6 5, T7 6);
+impl_sparse!(T1 0, T2 1, T3 2, T4 3, T5 4, T6 5, T7 6, T8 7);
+
+#[test]
+fn test_sparse_tuple() {
+    use crate::{from_key, to_key};
+    type Row = SparseTuple<(Option<u32>, Option<String>, Option<bool>)>;
+    let empty: Row = SparseTuple((None, None, None));
+    assert_eq!(to_key(empty.clone()), vec![0]);
+    let only_last: Row = SparseTuple((None, None, Some(true)));
+    assert_eq!(to_key(only_last.clone()), vec![0b0010_0000, 1]);
+
+    let rows: Vec<Row> = vec![
+        SparseTuple((Some(2), None, None)),
+        SparseTuple((Some(1), Some("a".to_owned()), None)),
+        only_last,
+        SparseTuple((Some(1), None, None)),
+        empty,
+        SparseTuple((None, Some("b".to_owned()), Some(false))),
+        SparseTuple((None, Some("a".to_owned()), Some(false))),
+    ];
+    let mut sorted = rows.clone();
+    sorted.sort_by_key(|row| {
+        assert_eq!(from_key::<Row>(to_key(row.clone())).unwrap(), *row);
+        to_key(row.clone())
+    });
+    assert_eq!(
+        sorted,
+        vec![
+            rows[4].clone(),
+            rows[2].clone(),
+            rows[6].clone(),
+            rows[5].clone(),
+            rows[3].clone(),
+            rows[0].clone(),
+            rows[1].clone(),
+        ]
+    );
+    assert!(from_key::<Row>(vec![0b0001_0000]).is_err());
+}
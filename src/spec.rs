@@ -0,0 +1,403 @@
+//! the encoding spec as data.
+//!
+//! [`SPEC`] lists `(type, example value, expected bytes)` rows. [`validate`]
+//! checks the implementation against it, and [`to_json`] dumps it (see the
+//! `index-key spec` command) for implementations in other languages.
+//! [`vectors`] extends it with boundary values into the conformance vectors
+//! the `gen-vectors` binary writes and [`verify_vectors`] checks.
+//!
+//! values are written as text: integers in decimal, floats as rust parses
+//! them (`inf`, `-inf`, `NaN` included), `Vec<u8>` in hex, strings verbatim.
+
+use crate::conformance::{boundaries, json_str};
+use crate::to_key;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SpecEntry {
+    pub type_name: &'static str,
+    pub value: &'static str,
+    pub bytes: &'static [u8],
+}
+
+const fn entry(type_name: &'static str, value: &'static str, bytes: &'static [u8]) -> SpecEntry {
+    SpecEntry {
+        type_name,
+        value,
+        bytes,
+    }
+}
+
+pub const SPEC: &[SpecEntry] = &[
+    // unsigned integers: big-endian.
+    entry("u8", "0", &[0x00]),
+    entry("u8", "255", &[0xff]),
+    entry("u16", "258", &[0x01, 0x02]),
+    entry("u32", "1", &[0, 0, 0, 1]),
+    entry("u64", "18446744073709551615", &[0xff; 8]),
+    entry(
+        "u128",
+        "1",
+        &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+    ),
+    // signed integers: big-endian with the sign bit flipped.
+    entry("i8", "-128", &[0x00]),
+    entry("i8", "-1", &[0x7f]),
+    entry("i8", "0", &[0x80]),
+    entry("i16", "1", &[0x80, 0x01]),
+    entry("i32", "-2", &[0x7f, 0xff, 0xff, 0xfe]),
+    entry("i64", "0", &[0x80, 0, 0, 0, 0, 0, 0, 0]),
+    entry(
+        "i128",
+        "-1",
+        &[
+            0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+            0xff, 0xff,
+        ],
+    ),
+    // floats: ieee bits, big-endian; sign bit flipped when positive, all bits
+    // flipped when negative.
+    entry("f32", "1", &[0xbf, 0x80, 0x00, 0x00]),
+    entry("f32", "-1", &[0x40, 0x7f, 0xff, 0xff]),
+    entry("f32", "0", &[0x80, 0x00, 0x00, 0x00]),
+    entry("f32", "-0", &[0x7f, 0xff, 0xff, 0xff]),
+    entry("f64", "inf", &[0xff, 0xf0, 0, 0, 0, 0, 0, 0]),
+    entry(
+        "f64",
+        "-inf",
+        &[0x00, 0x0f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+    ),
+    entry("f64", "1.5", &[0xbf, 0xf8, 0, 0, 0, 0, 0, 0]),
+    // bool: one byte.
+    entry("bool", "false", &[0x00]),
+    entry("bool", "true", &[0x01]),
+    // byte strings: 0x00 and 0x01 are prefixed with 0x01, then a 0x00 terminator.
+    entry("Vec<u8>", "", &[0x00]),
+    entry("Vec<u8>", "00", &[0x01, 0x00, 0x00]),
+    entry("Vec<u8>", "01ff", &[0x01, 0x01, 0xff, 0x00]),
+    entry("Vec<u8>", "0201", &[0x02, 0x01, 0x01, 0x00]),
+    // strings: their utf-8 bytes as a byte string.
+    entry("String", "", &[0x00]),
+    entry("String", "ab", &[b'a', b'b', 0x00]),
+    entry("String", "a\0b", &[b'a', 0x01, 0x00, b'b', 0x00]),
+    entry("String", "é", &[0xc3, 0xa9, 0x00]),
+];
+
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// the key this implementation produces for an entry's value.
+pub fn encode_entry(entry: &SpecEntry) -> Result<Vec<u8>, Error> {
+    encode_value(entry.type_name, entry.value)
+}
+
+/// the key for `value`, written as in [`SPEC`], of the type named `type_name`.
+pub fn encode_value(type_name: &str, value: &str) -> Result<Vec<u8>, Error> {
+    let invalid = || {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid spec value {:?} for {}", value, type_name),
+        )
+    };
+    macro_rules! parsed {
+        ($t:ty) => {
+            value.parse::<$t>().map(to_key).map_err(|_| invalid())
+        };
+    }
+    match type_name {
+        "u8" => parsed!(u8),
+        "u16" => parsed!(u16),
+        "u32" => parsed!(u32),
+        "u64" => parsed!(u64),
+        "u128" => parsed!(u128),
+        "i8" => parsed!(i8),
+        "i16" => parsed!(i16),
+        "i32" => parsed!(i32),
+        "i64" => parsed!(i64),
+        "i128" => parsed!(i128),
+        "f32" => parsed!(f32),
+        "f64" => parsed!(f64),
+        "bool" => parsed!(bool),
+        "Vec<u8>" => parse_hex(value).map(to_key).ok_or_else(invalid),
+        "String" => Ok(to_key(value.to_owned())),
+        _ => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("unknown spec type {}", type_name),
+        )),
+    }
+}
+
+/// one line per entry the implementation disagrees with.
+pub fn validate() -> Result<(), Vec<String>> {
+    let problems: Vec<String> = SPEC
+        .iter()
+        .filter_map(|entry| match encode_entry(entry) {
+            Ok(bytes) if bytes == entry.bytes => None,
+            Ok(bytes) => Some(format!(
+                "{} {:?}: expected {:02x?}, got {:02x?}",
+                entry.type_name, entry.value, entry.bytes, bytes
+            )),
+            Err(e) => Some(e.to_string()),
+        })
+        .collect();
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems)
+    }
+}
+
+/// `[{"type":..,"value":..,"hex":..},..]`, one entry per line.
+pub fn to_json() -> String {
+    let rows: Vec<String> = SPEC
+        .iter()
+        .map(|entry| {
+            format!(
+                "{{\"type\":{},\"value\":{},\"hex\":{}}}",
+                json_str(entry.type_name),
+                json_str(entry.value),
+                json_str(&hex(entry.bytes))
+            )
+        })
+        .collect();
+    format!("[\n{}\n]\n", rows.join(",\n"))
+}
+
+/// one conformance vector: a value and its key in lowercase hex.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Vector {
+    pub type_name: String,
+    pub value: String,
+    pub hex: String,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// every [`SPEC`] row plus the [`Boundaries`] of each spec type, encoded by
+/// this implementation. values that have no text form (NaN sign and payload)
+/// are left out.
+pub fn vectors() -> Vec<Vector> {
+    let mut rows: Vec<(&str, String)> = SPEC
+        .iter()
+        .map(|entry| (entry.type_name, entry.value.to_owned()))
+        .collect();
+    macro_rules! boundary_rows {
+        ($($t:ty),+) => {
+            $(
+                for value in boundaries::<$t>() {
+                    rows.push((stringify!($t), value.to_string()));
+                }
+            )+
+        };
+    }
+    boundary_rows!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, bool, String);
+    for value in boundaries::<f32>() {
+        if !value.is_nan() {
+            rows.push(("f32", format!("{:?}", value)));
+        }
+    }
+    for value in boundaries::<f64>() {
+        if !value.is_nan() {
+            rows.push(("f64", format!("{:?}", value)));
+        }
+    }
+    for value in boundaries::<Vec<u8>>() {
+        rows.push(("Vec<u8>", hex(&value)));
+    }
+    rows.into_iter()
+        .map(|(type_name, value)| Vector {
+            hex: hex(&encode_value(type_name, &value).unwrap()),
+            type_name: type_name.to_owned(),
+            value,
+        })
+        .collect()
+}
+
+/// `[{"type":..,"value":..,"hex":..},..]`, one vector per line.
+pub fn vectors_json(vectors: &[Vector]) -> String {
+    let rows: Vec<String> = vectors
+        .iter()
+        .map(|v| {
+            format!(
+                "{{\"type\":{},\"value\":{},\"hex\":{}}}",
+                json_str(&v.type_name),
+                json_str(&v.value),
+                json_str(&v.hex)
+            )
+        })
+        .collect();
+    format!("[\n{}\n]\n", rows.join(",\n"))
+}
+
+struct JsonReader<'a> {
+    rest: std::str::Chars<'a>,
+}
+
+impl JsonReader<'_> {
+    fn invalid() -> Error {
+        Error::new(ErrorKind::InvalidData, "invalid vectors json")
+    }
+
+    /// next char that is not whitespace.
+    fn token(&mut self) -> Result<char, Error> {
+        self.rest
+            .find(|c| !c.is_whitespace())
+            .ok_or_else(JsonReader::invalid)
+    }
+
+    fn expect(&mut self, want: char) -> Result<(), Error> {
+        match self.token()? {
+            c if c == want => Ok(()),
+            _ => Err(JsonReader::invalid()),
+        }
+    }
+
+    /// a string whose opening quote was already read.
+    fn string(&mut self) -> Result<String, Error> {
+        let mut out = String::new();
+        loop {
+            match self.rest.next().ok_or_else(JsonReader::invalid)? {
+                '"' => return Ok(out),
+                '\\' => match self.rest.next().ok_or_else(JsonReader::invalid)? {
+                    'u' => {
+                        let code: String = self.rest.by_ref().take(4).collect();
+                        let c = u32::from_str_radix(&code, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(JsonReader::invalid)?;
+                        out.push(c);
+                    }
+                    'n' => out.push('\n'),
+                    't' => out.push('\t'),
+                    'r' => out.push('\r'),
+                    c @ ('"' | '\\' | '/') => out.push(c),
+                    _ => return Err(JsonReader::invalid()),
+                },
+                c => out.push(c),
+            }
+        }
+    }
+}
+
+/// reads the output of [`vectors_json`]: an array of flat objects with string
+/// `type`, `value` and `hex` members.
+pub fn parse_vectors(json: &str) -> Result<Vec<Vector>, Error> {
+    let mut reader = JsonReader { rest: json.chars() };
+    let mut vectors = vec![];
+    reader.expect('[')?;
+    let mut next = reader.token()?;
+    while next == '{' {
+        let (mut type_name, mut value, mut hex) = (None, None, None);
+        loop {
+            reader.expect('"')?;
+            let name = reader.string()?;
+            reader.expect(':')?;
+            reader.expect('"')?;
+            let member = Some(reader.string()?);
+            match &name[..] {
+                "type" => type_name = member,
+                "value" => value = member,
+                "hex" => hex = member,
+                _ => (),
+            }
+            match reader.token()? {
+                ',' => continue,
+                '}' => break,
+                _ => return Err(JsonReader::invalid()),
+            }
+        }
+        match (type_name, value, hex) {
+            (Some(type_name), Some(value), Some(hex)) => vectors.push(Vector {
+                type_name,
+                value,
+                hex,
+            }),
+            _ => return Err(JsonReader::invalid()),
+        }
+        next = match reader.token()? {
+            ',' => reader.token()?,
+            c => c,
+        };
+    }
+    if next != ']' {
+        return Err(JsonReader::invalid());
+    }
+    Ok(vectors)
+}
+
+/// checks every vector in the json file at `path` against this
+/// implementation, returning how many were checked.
+pub fn verify_vectors<P: AsRef<Path>>(path: P) -> Result<usize, Error> {
+    let vectors = parse_vectors(&std::fs::read_to_string(path)?)?;
+    let problems: Vec<String> = vectors
+        .iter()
+        .filter_map(|v| match encode_value(&v.type_name, &v.value) {
+            Ok(bytes) if hex(&bytes) == v.hex.to_ascii_lowercase() => None,
+            Ok(bytes) => Some(format!(
+                "{} {:?}: expected {}, got {}",
+                v.type_name,
+                v.value,
+                v.hex,
+                hex(&bytes)
+            )),
+            Err(e) => Some(e.to_string()),
+        })
+        .collect();
+    if problems.is_empty() {
+        Ok(vectors.len())
+    } else {
+        Err(Error::new(ErrorKind::InvalidData, problems.join("\n")))
+    }
+}
+
+#[test]
+fn test_spec() {
+    if let Err(problems) = validate() {
+        panic!("spec mismatch:\n{}", problems.join("\n"));
+    }
+    let wrong = entry("u16", "1", &[0, 2]);
+    assert_eq!(encode_entry(&wrong).unwrap(), vec![0, 1]);
+    assert!(encode_entry(&entry("u8", "256", &[])).is_err());
+    assert!(encode_entry(&entry("Vec<u8>", "0", &[])).is_err());
+    assert!(encode_entry(&entry("char", "a", &[])).is_err());
+
+    let json = to_json();
+    assert!(json.starts_with("[\n{\"type\":\"u8\",\"value\":\"0\",\"hex\":\"00\"},\n"));
+    assert!(json.contains("{\"type\":\"String\",\"value\":\"a\\u0000b\",\"hex\":\"6101006200\"}"));
+    assert_eq!(json.lines().count(), SPEC.len() + 2);
+}
+
+#[test]
+fn test_vectors() {
+    let vectors = vectors();
+    assert!(vectors.len() > SPEC.len() + 100);
+    let json = vectors_json(&vectors);
+    assert_eq!(parse_vectors(&json).unwrap(), vectors);
+
+    let path = std::env::temp_dir().join(format!("index_key_vectors_{}.json", std::process::id()));
+    std::fs::write(&path, &json).unwrap();
+    assert_eq!(verify_vectors(&path).unwrap(), vectors.len());
+    std::fs::write(
+        &path,
+        "[{\"type\":\"u16\",\"value\":\"1\",\"hex\":\"0002\"},\n {\"hex\":\"00\",\"value\":\"\\u0000\",\"type\":\"String\"}]",
+    )
+    .unwrap();
+    let err = verify_vectors(&path).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "u16 \"1\": expected 0002, got 0001\nString \"\\0\": expected 00, got 010000"
+    );
+    std::fs::remove_file(&path).unwrap();
+    assert!(parse_vectors("[{\"type\":\"u8\"]").is_err());
+    assert_eq!(parse_vectors(" [ ] ").unwrap(), vec![]);
+}
@@ -0,0 +1,152 @@
+//! migration to and from `storekey` encoded keys.
+//!
+//! `storekey` writes integers, floats and bools in the same fixed-width,
+//! order-preserving layout as this crate, and `Option` with the same `0`/`1`
+//! tag, but terminates strings with a bare `0` instead of escaping them. a
+//! [`Storekey`] type reads and writes that layout, and [`from_storekey_bytes`]
+//! and [`to_storekey_bytes`] translate one key at a time. strings holding a
+//! `0` byte have no `storekey` form and are rejected.
+
+use crate::key::{read_fixed, write_fixed};
+use crate::{from_key, try_to_key, IndexKey};
+use std::io::{Cursor, Error, ErrorKind, Read, Write};
+
+pub trait Storekey: IndexKey {
+    fn write_storekey<W: Write>(self, result: &mut W) -> Result<&mut W, Error>;
+    fn read_storekey<R: Read>(key: &mut R) -> Result<Self, Error>;
+}
+
+macro_rules! impl_fixed {
+    ($($t:ty),+) => {
+        $(
+            impl Storekey for $t {
+                fn write_storekey<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+                    write_fixed(self, result)
+                }
+                fn read_storekey<R: Read>(key: &mut R) -> Result<Self, Error> {
+                    read_fixed(key)
+                }
+            }
+        )+
+    };
+}
+
+impl_fixed!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64, bool);
+
+impl Storekey for String {
+    fn write_storekey<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        if self.as_bytes().contains(&0) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "storekey strings cannot hold a 0 byte",
+            ));
+        }
+        result.write_all(self.as_bytes())?;
+        result.write_all(&[0])?;
+        Ok(result)
+    }
+    fn read_storekey<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let mut bytes = vec![];
+        let mut buf = [0u8];
+        loop {
+            key.read_exact(&mut buf)?;
+            if buf[0] == 0 {
+                break;
+            }
+            bytes.push(buf[0]);
+        }
+        String::from_utf8(bytes).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+}
+
+impl<T: Storekey> Storekey for Option<T> {
+    fn write_storekey<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        match self {
+            None => {
+                result.write_all(&[0])?;
+                Ok(result)
+            }
+            Some(value) => {
+                result.write_all(&[1])?;
+                value.write_storekey(result)
+            }
+        }
+    }
+    fn read_storekey<R: Read>(key: &mut R) -> Result<Self, Error> {
+        match u8::read_storekey(key)? {
+            0 => Ok(None),
+            1 => T::read_storekey(key).map(Some),
+            _ => Err(Error::new(ErrorKind::InvalidData, "invalid option tag")),
+        }
+    }
+}
+
+macro_rules! impl_tuple {
+    ($($t:ident),+) => {
+        impl<$($t: Storekey),+> Storekey for ($($t,)+) {
+            #[allow(non_snake_case)]
+            fn write_storekey<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+                let ($($t,)+) = self;
+                $($t.write_storekey(result)?;)+
+                Ok(result)
+            }
+            fn read_storekey<R: Read>(key: &mut R) -> Result<Self, Error> {
+                Ok(($($t::read_storekey(key)?,)+))
+            }
+        }
+    };
+}
+
+impl_tuple!(T1, T2);
+impl_tuple!(T1, T2, T3);
+impl_tuple!(T1, T2, T3, T4);
+impl_tuple!(T1, T2, T3, T4, T5);
+impl_tuple!(T1, T2, T3, T4, T5, T6);
+
+/// a `storekey` encoded `T` re-encoded in this crate's format.
+pub fn from_storekey_bytes<T: Storekey>(bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut cursor = Cursor::new(bytes);
+    let value = T::read_storekey(&mut cursor)?;
+    if cursor.position() as usize != bytes.len() {
+        return Err(Error::new(ErrorKind::InvalidData, "trailing bytes"));
+    }
+    try_to_key(value)
+}
+
+/// a key of this crate's format re-encoded as `storekey` would encode `T`.
+pub fn to_storekey_bytes<T: Storekey>(key: &[u8]) -> Result<Vec<u8>, Error> {
+    let value: T = from_key(key.to_vec())?;
+    let mut result = vec![];
+    value.write_storekey(&mut result)?;
+    Ok(result)
+}
+
+#[test]
+fn test_storekey() {
+    use crate::to_key;
+    type Row = (String, i32, Option<u64>, bool);
+    let rows: Vec<Row> = vec![
+        ("".to_owned(), -1, None, false),
+        ("a".to_owned(), 5, Some(3), true),
+        ("a\u{1}b".to_owned(), i32::MIN, Some(0), false),
+        ("ab".to_owned(), 0, None, true),
+    ];
+    for row in rows {
+        let ours = to_key(row.clone());
+        let theirs = to_storekey_bytes::<Row>(&ours).unwrap();
+        assert_eq!(from_storekey_bytes::<Row>(&theirs).unwrap(), ours);
+        assert_eq!(from_key::<Row>(ours).unwrap(), row);
+    }
+    assert_eq!(
+        to_storekey_bytes::<(String, u16)>(&to_key(("ab".to_owned(), 258u16))).unwrap(),
+        vec![b'a', b'b', 0, 1, 2]
+    );
+    assert_eq!(
+        to_storekey_bytes::<String>(&to_key("a\0".to_owned()))
+            .unwrap_err()
+            .kind(),
+        ErrorKind::InvalidInput
+    );
+    assert!(from_storekey_bytes::<u8>(&[1, 2]).is_err());
+    assert!(from_storekey_bytes::<String>(b"abc").is_err());
+}
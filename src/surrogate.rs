@@ -0,0 +1,192 @@
+//! fixed 8-byte surrogates standing in for long natural keys.
+//!
+//! a `SurrogateOf<T>` encodes as a big-endian `u64`, so composite indexes that
+//! reference a long url or path pay 8 bytes instead of the full key. surrogates
+//! are either assigned in sequence by a [`SurrogateRegistry`] or derived from a
+//! hash of the natural key's encoding; the registry maps both ways. surrogate
+//! order is unrelated to the natural key order.
+
+use crate::digest::sha256;
+use crate::{from_key, to_key, try_to_key, IndexKey};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::{Error, ErrorKind, Read, Write};
+use std::marker::PhantomData;
+
+pub(crate) fn hash_id(bytes: &[u8]) -> u64 {
+    let digest = sha256(bytes);
+    let mut id = [0; 8];
+    id.copy_from_slice(&digest[..8]);
+    u64::from_be_bytes(id)
+}
+
+pub struct SurrogateOf<T> {
+    id: u64,
+    natural: PhantomData<fn() -> T>,
+}
+
+impl<T> SurrogateOf<T> {
+    pub fn from_id(id: u64) -> SurrogateOf<T> {
+        SurrogateOf {
+            id,
+            natural: PhantomData,
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+impl<T: IndexKey> SurrogateOf<T> {
+    /// first 8 bytes of the sha-256 of the natural key; stable across processes
+    /// without a registry, at the cost of possible collisions.
+    pub fn hashed(natural: T) -> SurrogateOf<T> {
+        SurrogateOf::from_id(hash_id(&to_key(natural)))
+    }
+}
+
+impl<T> Clone for SurrogateOf<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for SurrogateOf<T> {}
+
+impl<T> PartialEq for SurrogateOf<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T> Eq for SurrogateOf<T> {}
+
+impl<T> Hash for SurrogateOf<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state)
+    }
+}
+
+impl<T> fmt::Debug for SurrogateOf<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SurrogateOf({})", self.id)
+    }
+}
+
+impl<T> IndexKey for SurrogateOf<T> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        self.id.to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        u64::from_key(key).map(SurrogateOf::from_id)
+    }
+}
+
+/// two-way map between natural keys and their surrogates.
+pub struct SurrogateRegistry<T> {
+    hashed: bool,
+    next: u64,
+    ids: HashMap<Vec<u8>, u64>,
+    naturals: HashMap<u64, Vec<u8>>,
+    natural: PhantomData<fn() -> T>,
+}
+
+impl<T: IndexKey> SurrogateRegistry<T> {
+    /// assigns surrogates 1, 2, 3, ... in first-seen order.
+    pub fn new() -> SurrogateRegistry<T> {
+        SurrogateRegistry::with_mode(false)
+    }
+
+    /// assigns [`SurrogateOf::hashed`] surrogates, rejecting collisions.
+    pub fn hashed() -> SurrogateRegistry<T> {
+        SurrogateRegistry::with_mode(true)
+    }
+
+    fn with_mode(hashed: bool) -> SurrogateRegistry<T> {
+        SurrogateRegistry {
+            hashed,
+            next: 1,
+            ids: HashMap::new(),
+            naturals: HashMap::new(),
+            natural: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// the surrogate for `natural`, assigning one if it is new.
+    pub fn intern(&mut self, natural: T) -> Result<SurrogateOf<T>, Error> {
+        let bytes = try_to_key(natural)?;
+        if let Some(id) = self.ids.get(&bytes) {
+            return Ok(SurrogateOf::from_id(*id));
+        }
+        let id = if self.hashed {
+            let id = hash_id(&bytes);
+            if self.naturals.contains_key(&id) {
+                return Err(Error::new(ErrorKind::AlreadyExists, "surrogate collision"));
+            }
+            id
+        } else {
+            let id = self.next;
+            self.next += 1;
+            id
+        };
+        self.naturals.insert(id, bytes.clone());
+        self.ids.insert(bytes, id);
+        Ok(SurrogateOf::from_id(id))
+    }
+
+    /// the surrogate already assigned to `natural`, if any.
+    pub fn get(&self, natural: T) -> Option<SurrogateOf<T>> {
+        self.ids
+            .get(&to_key(natural))
+            .map(|id| SurrogateOf::from_id(*id))
+    }
+
+    /// the natural key behind a surrogate.
+    pub fn resolve(&self, surrogate: SurrogateOf<T>) -> Result<T, Error> {
+        match self.naturals.get(&surrogate.id) {
+            Some(bytes) => from_key(bytes.clone()),
+            None => Err(Error::new(ErrorKind::NotFound, "unknown surrogate")),
+        }
+    }
+}
+
+impl<T: IndexKey> Default for SurrogateRegistry<T> {
+    fn default() -> SurrogateRegistry<T> {
+        SurrogateRegistry::new()
+    }
+}
+
+#[test]
+fn test_surrogate() {
+    let long = "https://example.com/".to_owned() + &"a/".repeat(200);
+    let mut registry = SurrogateRegistry::<String>::new();
+    let a = registry.intern(long.clone()).unwrap();
+    let b = registry.intern("b".to_owned()).unwrap();
+    assert_eq!((a.id(), b.id()), (1, 2));
+    assert_eq!(registry.intern(long.clone()).unwrap(), a);
+    assert_eq!(registry.get("b".to_owned()), Some(b));
+    assert_eq!(registry.get("c".to_owned()), None);
+    assert_eq!(registry.resolve(a).unwrap(), long);
+    assert!(registry.resolve(SurrogateOf::from_id(9)).is_err());
+    assert_eq!(registry.len(), 2);
+
+    let key = to_key((a, 7u32));
+    assert_eq!(key.len(), 12);
+    assert_eq!(from_key::<(SurrogateOf<String>, u32)>(key).unwrap(), (a, 7));
+
+    let mut hashed = SurrogateRegistry::<String>::hashed();
+    let h = hashed.intern(long.clone()).unwrap();
+    assert_eq!(h, SurrogateOf::hashed(long.clone()));
+    assert_ne!(h, SurrogateOf::hashed("b".to_owned()));
+    assert_eq!(hashed.resolve(h).unwrap(), long);
+}
@@ -0,0 +1,172 @@
+//! io wrappers that inject faults at chosen byte offsets, for testing that
+//! encoders and decoders cope with partial io.
+//!
+//! each fault fires once the stream position reaches its offset. a call that
+//! would cross a pending offset is cut short there first, so every fault is hit
+//! exactly at its byte.
+
+use std::io::{Error, ErrorKind, Read, Write};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Fault {
+    /// the call transfers a single byte.
+    Short,
+    /// the call fails with `ErrorKind::Interrupted` without transferring anything.
+    Interrupted,
+    /// the stream ends: writes return `Ok(0)`, reads return `Ok(0)`, from here on.
+    Eof,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Faults {
+    pos: u64,
+    pending: Vec<(u64, Fault)>,
+    eof: bool,
+}
+
+impl Faults {
+    fn add(&mut self, at: u64, fault: Fault) {
+        let i = self.pending.partition_point(|(p, _)| *p <= at);
+        self.pending.insert(i, (at, fault));
+    }
+
+    /// how many of `len` bytes the next call may transfer.
+    fn limit(&mut self, len: usize) -> Result<usize, Error> {
+        if self.eof {
+            return Ok(0);
+        }
+        match self.pending.first().copied() {
+            Some((at, fault)) if at <= self.pos => {
+                self.pending.remove(0);
+                match fault {
+                    Fault::Short => Ok(len.min(1)),
+                    Fault::Interrupted => Err(Error::new(ErrorKind::Interrupted, "injected fault")),
+                    Fault::Eof => {
+                        self.eof = true;
+                        Ok(0)
+                    }
+                }
+            }
+            Some((at, _)) => Ok(len.min((at - self.pos) as usize)),
+            None => Ok(len),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FaultyWriter<W> {
+    inner: W,
+    faults: Faults,
+}
+
+impl<W: Write> FaultyWriter<W> {
+    pub fn new(inner: W) -> FaultyWriter<W> {
+        FaultyWriter {
+            inner,
+            faults: Faults::default(),
+        }
+    }
+
+    /// injects `fault` when `at` bytes have been written.
+    pub fn fault(mut self, at: u64, fault: Fault) -> FaultyWriter<W> {
+        self.faults.add(at, fault);
+        self
+    }
+
+    /// bytes written so far.
+    pub fn position(&self) -> u64 {
+        self.faults.pos
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for FaultyWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        let limit = self.faults.limit(buf.len())?;
+        let written = self.inner.write(&buf[..limit])?;
+        self.faults.pos += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.inner.flush()
+    }
+}
+
+#[derive(Debug)]
+pub struct FaultyReader<R> {
+    inner: R,
+    faults: Faults,
+}
+
+impl<R: Read> FaultyReader<R> {
+    pub fn new(inner: R) -> FaultyReader<R> {
+        FaultyReader {
+            inner,
+            faults: Faults::default(),
+        }
+    }
+
+    /// injects `fault` when `at` bytes have been read.
+    pub fn fault(mut self, at: u64, fault: Fault) -> FaultyReader<R> {
+        self.faults.add(at, fault);
+        self
+    }
+
+    /// bytes read so far.
+    pub fn position(&self) -> u64 {
+        self.faults.pos
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for FaultyReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let limit = self.faults.limit(buf.len())?;
+        let read = self.inner.read(&mut buf[..limit])?;
+        self.faults.pos += read as u64;
+        Ok(read)
+    }
+}
+
+#[test]
+fn test_faulty_io() {
+    use crate::{to_key, IndexKey};
+    type Row = (String, u64, Vec<u8>, i32);
+    let row: Row = ("key\0with\x01escapes".to_owned(), 42, vec![0, 1, 2], -7);
+    let expected = to_key(row.clone());
+
+    for at in 0..expected.len() as u64 {
+        for fault in [Fault::Short, Fault::Interrupted] {
+            let mut writer = FaultyWriter::new(vec![])
+                .fault(at, fault)
+                .fault(at + 3, fault);
+            row.clone().to_key(&mut writer).unwrap();
+            assert_eq!(writer.into_inner(), expected);
+
+            let mut reader = FaultyReader::new(&expected[..])
+                .fault(at, fault)
+                .fault(at + 3, fault);
+            assert_eq!(Row::from_key(&mut reader).unwrap(), row);
+            assert_eq!(reader.position(), expected.len() as u64);
+        }
+
+        let mut writer = FaultyWriter::new(vec![]).fault(at, Fault::Eof);
+        let err = row.clone().to_key(&mut writer).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WriteZero);
+        assert_eq!(writer.position(), at);
+    }
+
+    // a truncated fixed-width field must not decode.
+    let fixed = to_key((7u64, 9u64));
+    for at in 0..fixed.len() as u64 {
+        let mut reader = FaultyReader::new(&fixed[..]).fault(at, Fault::Eof);
+        assert!(<(u64, u64)>::from_key(&mut reader).is_err());
+    }
+}
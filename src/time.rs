@@ -0,0 +1,133 @@
+//! keys for the `time` crate's dates and times.
+//!
+//! a `Date` encodes as `(year: i32, month: u8, day: u8)` and a `Time` as
+//! `(seconds since midnight: u32, nanos: u32)`, so both sort chronologically
+//! without converting to integers first; a `PrimitiveDateTime` is the pair of
+//! them. an `OffsetDateTime` is normalized to UTC, so instants sort
+//! chronologically whatever offset they were recorded in.
+
+use crate::IndexKey;
+use ::time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind, Read, Write};
+
+impl IndexKey for Date {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        (self.year(), self.month() as u8, self.day()).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let invalid = || Error::new(ErrorKind::InvalidData, "invalid date");
+        let (year, month, day) = <(i32, u8, u8)>::from_key(key)?;
+        let month = Month::try_from(month).map_err(|_| invalid())?;
+        Date::from_calendar_date(year, month, day).map_err(|_| invalid())
+    }
+}
+
+impl IndexKey for Time {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        let (hour, minute, second, nanos) = self.as_hms_nano();
+        let secs = u32::from(hour) * 3600 + u32::from(minute) * 60 + u32::from(second);
+        (secs, nanos).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let invalid = || Error::new(ErrorKind::InvalidData, "invalid time of day");
+        let (secs, nanos) = <(u32, u32)>::from_key(key)?;
+        if secs >= 86_400 {
+            return Err(invalid());
+        }
+        let (hour, minute, second) = (secs / 3600, secs / 60 % 60, secs % 60);
+        Time::from_hms_nano(hour as u8, minute as u8, second as u8, nanos).map_err(|_| invalid())
+    }
+}
+
+impl IndexKey for PrimitiveDateTime {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        (self.date(), self.time()).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let (date, time) = <(Date, Time)>::from_key(key)?;
+        Ok(PrimitiveDateTime::new(date, time))
+    }
+}
+
+/// the UTC date and time. decoding gives the instant at offset zero.
+impl IndexKey for OffsetDateTime {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        let utc = self
+            .checked_to_offset(UtcOffset::UTC)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "date out of range"))?;
+        PrimitiveDateTime::new(utc.date(), utc.time()).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        Ok(PrimitiveDateTime::from_key(key)?.assume_utc())
+    }
+}
+
+#[test]
+fn test_date() {
+    use crate::{from_key, to_key};
+    let d = |y, m, d| Date::from_calendar_date(y, m, d).unwrap();
+    let dates = [
+        d(-1, Month::December, 31),
+        d(0, Month::February, 29),
+        d(1969, Month::December, 31),
+        d(1970, Month::January, 1),
+        d(2000, Month::February, 29),
+        d(2024, Month::October, 5),
+    ];
+    let keys: Vec<_> = dates.iter().map(|d| to_key(*d)).collect();
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    for date in dates {
+        assert_eq!(from_key::<Date>(to_key(date)).unwrap(), date);
+    }
+    assert_eq!(
+        to_key(d(2024, Month::February, 29)),
+        to_key((2024i32, 2u8, 29u8))
+    );
+    assert!(from_key::<Date>(to_key((2023i32, 4u8, 31u8))).is_err());
+    assert!(from_key::<Date>(to_key((2023i32, 13u8, 1u8))).is_err());
+    assert!(from_key::<Date>(to_key((i32::MAX, 1u8, 1u8))).is_err());
+}
+
+#[test]
+fn test_time() {
+    use crate::{from_key, to_key};
+    let t = |h, m, s, n| Time::from_hms_nano(h, m, s, n).unwrap();
+    let noon = t(12, 0, 0, 0);
+    let later = t(12, 0, 0, 1);
+    let last = t(23, 59, 59, 999_999_999);
+    assert!(to_key(Time::MIDNIGHT) < to_key(noon));
+    assert!(to_key(noon) < to_key(later));
+    assert!(to_key(later) < to_key(last));
+    assert_eq!(to_key(noon), to_key((43_200u32, 0u32)));
+    assert_eq!(from_key::<Time>(to_key(last)).unwrap(), last);
+    assert!(from_key::<Time>(to_key((86_400u32, 0u32))).is_err());
+    assert!(from_key::<Time>(to_key((0u32, 1_000_000_000u32))).is_err());
+}
+
+#[test]
+fn test_offset_date_time() {
+    use crate::{from_key, to_key};
+    let day = Date::from_calendar_date(2024, Month::March, 1).unwrap();
+    let time = Time::from_hms_nano(1, 30, 0, 5).unwrap();
+    // 01:30 at +02:00 is 23:30 UTC the day before.
+    let early =
+        PrimitiveDateTime::new(day, time).assume_offset(UtcOffset::from_hms(2, 0, 0).unwrap());
+    let utc = PrimitiveDateTime::new(
+        Date::from_calendar_date(2024, Month::February, 29).unwrap(),
+        Time::from_hms_nano(23, 30, 0, 5).unwrap(),
+    );
+    assert_eq!(to_key(early), to_key(utc));
+    assert_eq!(from_key::<PrimitiveDateTime>(to_key(utc)).unwrap(), utc);
+    // 00:00 at -05:00 is later than 01:30 at +02:00.
+    let later = PrimitiveDateTime::new(day, Time::MIDNIGHT)
+        .assume_offset(UtcOffset::from_hms(-5, 0, 0).unwrap());
+    assert!(to_key(early) < to_key(later));
+    let decoded = from_key::<OffsetDateTime>(to_key(early)).unwrap();
+    assert_eq!(decoded, early);
+    assert_eq!(decoded.offset(), UtcOffset::UTC);
+    assert!(
+        to_key(OffsetDateTime::UNIX_EPOCH - ::time::Duration::SECOND)
+            < to_key(OffsetDateTime::UNIX_EPOCH)
+    );
+}
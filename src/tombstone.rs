@@ -0,0 +1,193 @@
+//! tombstones.
+//!
+//! soft deletes keep a liveness byte as the last byte of the key, so scans can
+//! drop them by looking at that byte without decoding. range tombstones delete
+//! whole `[start, end)` spans up to a sequence number.
+
+use crate::range::KeyRange;
+use crate::{to_key, IndexKey};
+use std::io::{Error, ErrorKind, Read, Write};
+
+pub const LIVE: u8 = 0;
+pub const DELETED: u8 = 1;
+
+/// encodes as `(value, flag)`; the tombstone of a value sorts right after it.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MaybeDeleted<T> {
+    pub value: T,
+    pub deleted: bool,
+}
+
+impl<T: IndexKey> IndexKey for MaybeDeleted<T> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        self.value.to_key(result)?;
+        result.write_all(&[if self.deleted { DELETED } else { LIVE }])?;
+        Ok(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let value = T::from_key(key)?;
+        let mut flag = [0];
+        key.read_exact(&mut flag)?;
+        let deleted = match flag[0] {
+            LIVE => false,
+            DELETED => true,
+            _ => return Err(Error::new(ErrorKind::InvalidData, "invalid liveness flag")),
+        };
+        Ok(MaybeDeleted { value, deleted })
+    }
+}
+
+impl<T: IndexKey> MaybeDeleted<T> {
+    pub fn live(value: T) -> MaybeDeleted<T> {
+        MaybeDeleted {
+            value,
+            deleted: false,
+        }
+    }
+
+    pub fn deleted(value: T) -> MaybeDeleted<T> {
+        MaybeDeleted {
+            value,
+            deleted: true,
+        }
+    }
+
+    /// the live entry and the tombstone of `value`.
+    pub fn purge_range(value: T) -> KeyRange {
+        KeyRange::prefix(to_key(value))
+    }
+
+    /// only the tombstone of `value`.
+    pub fn tombstone_range(value: T) -> KeyRange {
+        KeyRange::prefix(to_key(MaybeDeleted::deleted(value)))
+    }
+}
+
+pub fn is_deleted(key: &[u8]) -> bool {
+    key.last() == Some(&DELETED)
+}
+
+/// drop tombstones from a scan.
+pub fn live_only<K: AsRef<[u8]>, I: IntoIterator<Item = K>>(keys: I) -> impl Iterator<Item = K> {
+    keys.into_iter().filter(|key| !is_deleted(key.as_ref()))
+}
+
+#[test]
+fn test_maybe_deleted() {
+    use crate::from_key;
+    let mut keys = vec![
+        to_key(MaybeDeleted::live((1u8, "a".to_owned()))),
+        to_key(MaybeDeleted::deleted((1u8, "b".to_owned()))),
+        to_key(MaybeDeleted::live((1u8, "b".to_owned()))),
+        to_key(MaybeDeleted::deleted((2u8, "\u{1}".to_owned()))),
+    ];
+    keys.sort();
+    assert_eq!(
+        from_key::<MaybeDeleted<(u8, String)>>(keys[2].clone()).unwrap(),
+        MaybeDeleted::deleted((1, "b".to_owned()))
+    );
+    let live: Vec<_> = live_only(&keys)
+        .map(|k| from_key::<MaybeDeleted<(u8, String)>>(k.clone()).unwrap())
+        .collect();
+    assert_eq!(
+        live,
+        vec![
+            MaybeDeleted::live((1, "a".to_owned())),
+            MaybeDeleted::live((1, "b".to_owned()))
+        ]
+    );
+
+    let purge = MaybeDeleted::purge_range((1u8, "b".to_owned()));
+    assert_eq!(keys.iter().filter(|k| purge.contains(k)).count(), 2);
+    let tombstone = MaybeDeleted::tombstone_range((1u8, "b".to_owned()));
+    assert!(tombstone.contains(&keys[2]));
+    assert!(!tombstone.contains(&keys[1]));
+}
+
+/// deletes every key in `[start, end)` written at or before `seq`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RangeTombstone {
+    pub start: Vec<u8>,
+    pub end: Vec<u8>,
+    pub seq: u64,
+}
+
+impl RangeTombstone {
+    pub fn new(start: Vec<u8>, end: Vec<u8>, seq: u64) -> RangeTombstone {
+        RangeTombstone { start, end, seq }
+    }
+
+    pub fn range(&self) -> KeyRange {
+        KeyRange::new(self.start.clone(), self.end.clone())
+    }
+
+    pub fn shadows(&self, key: &[u8], seq: u64) -> bool {
+        seq <= self.seq && key >= &self.start[..] && key < &self.end[..]
+    }
+}
+
+/// split overlapping tombstones into sorted, disjoint fragments carrying the
+/// highest covering seq; touching fragments with equal seq are joined.
+pub fn merge_tombstones<I: IntoIterator<Item = RangeTombstone>>(
+    tombstones: I,
+) -> Vec<RangeTombstone> {
+    use std::collections::BTreeMap;
+    let mut events: BTreeMap<Vec<u8>, Vec<(bool, u64)>> = BTreeMap::new();
+    for t in tombstones.into_iter().filter(|t| t.start < t.end) {
+        events.entry(t.start).or_default().push((true, t.seq));
+        events.entry(t.end).or_default().push((false, t.seq));
+    }
+    let mut active: BTreeMap<u64, usize> = BTreeMap::new();
+    let mut result: Vec<RangeTombstone> = vec![];
+    let mut prev: Option<Vec<u8>> = None;
+    for (point, changes) in events {
+        if let (Some(start), Some((&seq, _))) = (prev.take(), active.iter().next_back()) {
+            match result.last_mut() {
+                Some(last) if last.end == start && last.seq == seq => last.end = point.clone(),
+                _ => result.push(RangeTombstone::new(start, point.clone(), seq)),
+            }
+        }
+        for (is_start, seq) in changes {
+            let count = active.entry(seq).or_insert(0);
+            if is_start {
+                *count += 1;
+            } else {
+                *count -= 1;
+                if *count == 0 {
+                    active.remove(&seq);
+                }
+            }
+        }
+        prev = Some(point);
+    }
+    result
+}
+
+/// `merged` must come from [`merge_tombstones`].
+pub fn is_shadowed(merged: &[RangeTombstone], key: &[u8], seq: u64) -> bool {
+    let index = merged.partition_point(|t| &t.end[..] <= key);
+    merged.get(index).is_some_and(|t| t.shadows(key, seq))
+}
+
+#[test]
+fn test_range_tombstone() {
+    let t = |s: u8, e: u8, seq: u64| RangeTombstone::new(vec![s], vec![e], seq);
+    let merged = merge_tombstones(vec![
+        t(5, 9, 2),
+        t(1, 4, 1),
+        t(3, 7, 3),
+        t(9, 10, 2),
+        t(2, 2, 9),
+    ]);
+    assert_eq!(merged, vec![t(1, 3, 1), t(3, 7, 3), t(7, 10, 2)]);
+
+    assert!(!is_shadowed(&merged, &[0], 0));
+    assert!(is_shadowed(&merged, &[1], 1));
+    assert!(!is_shadowed(&merged, &[1], 2));
+    assert!(is_shadowed(&merged, &[3], 3));
+    assert!(is_shadowed(&merged, &[6, 255], 3));
+    assert!(!is_shadowed(&merged, &[7], 3));
+    assert!(is_shadowed(&merged, &[9, 1], 2));
+    assert!(!is_shadowed(&merged, &[10], 0));
+    assert!(merged[1].range().contains(&[4]));
+}
@@ -0,0 +1,691 @@
+//! alternative layouts for keys with a known [`Schema`].
+//!
+//! keys are produced in the canonical, escaped format by [`to_key`] and
+//! re-encoded field by field into another layout; decoding goes back to the
+//! canonical format for [`from_key`]. each layout is a [`KeyCodec`], so code
+//! generic over the codec switches formats by type parameter, and [`Codec`]
+//! picks one at runtime. a type with a [`KeySchema`], such as one declared
+//! with [`key_struct!`](crate::key_struct), needs no schema at the call site:
+//! [`to_key_with`] and [`from_key_with`].
+//!
+//! [`DoubleEnded`] writes every field's bytes unescaped, which saves escaping
+//! blob-heavy keys. a byte string's length would then have to go at the back
+//! of the key, as `ordcode` does, and keys with a byte string before another
+//! field would compare by raw bytes instead of field order; so the codec takes
+//! only schemas whose one byte string is the last field, whose length follows
+//! from the total, and rejects others with `InvalidInput`.
+
+use crate::codec::{self, FieldWidth};
+use crate::range::KeyRange;
+use crate::schema::{Field, KeySchema, Schema};
+use crate::{from_key, to_key, try_to_key, IndexKey};
+use std::io::{Error, ErrorKind};
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+/// the payload of every field of a canonical key: fixed fields as encoded,
+/// byte strings unescaped. escapes must be canonical.
+fn split(key: &[u8], widths: &[FieldWidth]) -> Result<Vec<Vec<u8>>, Error> {
+    let mut fields = Vec::with_capacity(widths.len());
+    let mut used = 0;
+    for (raw, width) in codec::raw_fields(key, widths).zip(widths) {
+        let field = match width {
+            FieldWidth::Fixed(n) if raw.len() == *n => raw.to_vec(),
+            FieldWidth::Fixed(_) => return Err(invalid("truncated field")),
+            FieldWidth::Escaped => {
+                let mut field = vec![];
+                codec::unescape::<(), _>(raw, |b| {
+                    field.push(b);
+                    Ok(())
+                })
+                .unwrap();
+                if codec::escaped_len(&field) != raw.len() {
+                    return Err(invalid("unterminated field"));
+                }
+                field
+            }
+        };
+        used += raw.len();
+        fields.push(field);
+    }
+    if fields.len() < widths.len() {
+        return Err(invalid("truncated field"));
+    }
+    if used != key.len() {
+        return Err(invalid("trailing bytes"));
+    }
+    Ok(fields)
+}
+
+/// inverse of [`split`].
+fn join(fields: &[Vec<u8>], widths: &[FieldWidth]) -> Vec<u8> {
+    let mut key = vec![];
+    for (field, width) in fields.iter().zip(widths) {
+        match width {
+            FieldWidth::Fixed(_) => key.extend_from_slice(field),
+            FieldWidth::Escaped => {
+                codec::escape::<(), _>(field, |chunk| {
+                    key.extend_from_slice(chunk);
+                    Ok(())
+                })
+                .unwrap();
+            }
+        }
+    }
+    key
+}
+
+/// a key layout selected by type parameter. codecs work on the fields of a
+/// [`Schema`]: the typed helpers encode with [`to_key`], split the canonical
+/// key into fields and hand them to [`encode_fields`](KeyCodec::encode_fields).
+pub trait KeyCodec {
+    /// the fields of a canonical key, fixed fields as encoded and byte
+    /// strings unescaped, in this layout.
+    fn encode_fields(fields: &[Vec<u8>], types: &[Field]) -> Result<Vec<u8>, Error>;
+    /// inverse of [`encode_fields`](KeyCodec::encode_fields).
+    fn decode_fields(bytes: &[u8], types: &[Field]) -> Result<Vec<Vec<u8>>, Error>;
+
+    fn encode<T: IndexKey>(value: T, schema: &Schema) -> Result<Vec<u8>, Error> {
+        let types = types(schema);
+        let fields = split(&to_key(value), &widths(&types))?;
+        Self::encode_fields(&fields, &types)
+    }
+
+    fn decode<T: IndexKey>(bytes: &[u8], schema: &Schema) -> Result<T, Error> {
+        let types = types(schema);
+        let fields = Self::decode_fields(bytes, &types)?;
+        from_key(join(&fields, &widths(&types)))
+    }
+
+    fn validate(bytes: &[u8], schema: &Schema) -> Result<(), Error> {
+        Self::decode_fields(bytes, &types(schema)).map(|_| ())
+    }
+
+    /// keys whose leading fields equal `prefix`, a tuple of the first fields
+    /// of `schema`.
+    fn prefix_range<T: IndexKey>(prefix: T, schema: &Schema) -> Result<KeyRange, Error> {
+        let types = types(schema);
+        let key = try_to_key(prefix)?;
+        let (used, fields) = (0..=types.len())
+            .find_map(|used| Some((used, split(&key, &widths(&types[..used])).ok()?)))
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "prefix does not match schema"))?;
+        Ok(KeyRange::prefix(Self::encode_fields(
+            &fields,
+            &types[..used],
+        )?))
+    }
+}
+
+fn types(schema: &Schema) -> Vec<Field> {
+    schema.fields().iter().map(|(_, field)| *field).collect()
+}
+
+fn widths(types: &[Field]) -> Vec<FieldWidth> {
+    types.iter().map(|field| field.field_width()).collect()
+}
+
+/// the canonical format of [`to_key`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Escaped;
+
+impl KeyCodec for Escaped {
+    fn encode_fields(fields: &[Vec<u8>], types: &[Field]) -> Result<Vec<u8>, Error> {
+        Ok(join(fields, &widths(types)))
+    }
+    fn decode_fields(bytes: &[u8], types: &[Field]) -> Result<Vec<Vec<u8>>, Error> {
+        split(bytes, &widths(types))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DoubleEnded;
+
+/// an error unless the schema's only byte string, if any, is its last field.
+fn check_double_ended(types: &[Field]) -> Result<(), Error> {
+    match types
+        .iter()
+        .position(|f| f.field_width() == FieldWidth::Escaped)
+    {
+        Some(i) if i + 1 != types.len() => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "double-ended keys keep their order only with one byte string, last",
+        )),
+        _ => Ok(()),
+    }
+}
+
+impl KeyCodec for DoubleEnded {
+    fn encode_fields(fields: &[Vec<u8>], types: &[Field]) -> Result<Vec<u8>, Error> {
+        check_double_ended(types)?;
+        Ok(fields.concat())
+    }
+
+    fn decode_fields(bytes: &[u8], types: &[Field]) -> Result<Vec<Vec<u8>>, Error> {
+        check_double_ended(types)?;
+        let mut rest = bytes;
+        let mut fields = Vec::with_capacity(types.len());
+        for field in types {
+            let len = match field.field_width() {
+                FieldWidth::Fixed(n) => n,
+                FieldWidth::Escaped => rest.len(),
+            };
+            if rest.len() < len {
+                return Err(invalid("truncated field"));
+            }
+            let (field, after) = rest.split_at(len);
+            fields.push(field.to_vec());
+            rest = after;
+        }
+        if !rest.is_empty() {
+            return Err(invalid("trailing bytes"));
+        }
+        Ok(fields)
+    }
+
+    /// a byte string in the prefix would match every longer string it starts,
+    /// so only fixed-width prefixes are accepted.
+    fn prefix_range<T: IndexKey>(prefix: T, schema: &Schema) -> Result<KeyRange, Error> {
+        let range = Escaped::prefix_range(prefix, schema)?;
+        let types = types(schema);
+        let fixed = types
+            .iter()
+            .take_while(|f| f.field_width() != FieldWidth::Escaped);
+        if range.start.len() > fixed.map(|f| f.width()).sum() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "double-ended prefixes cannot hold byte strings",
+            ));
+        }
+        Ok(range)
+    }
+}
+
+/// the `memcomparable` layout: fixed fields as in the canonical format, byte
+/// strings in groups of 8 zero-padded bytes, each followed by `255` minus its
+/// padding, so a group marked `255` is followed by more.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Memcomparable;
+
+impl KeyCodec for Memcomparable {
+    fn encode_fields(fields: &[Vec<u8>], types: &[Field]) -> Result<Vec<u8>, Error> {
+        let mut result = vec![];
+        for (field, width) in fields.iter().zip(widths(types)) {
+            if let FieldWidth::Fixed(_) = width {
+                result.extend_from_slice(field);
+                continue;
+            }
+            let mut chunks = field.chunks_exact(8);
+            for chunk in &mut chunks {
+                result.extend_from_slice(chunk);
+                result.push(255);
+            }
+            let rest = chunks.remainder();
+            result.extend_from_slice(rest);
+            result.extend(std::iter::repeat_n(0, 8 - rest.len()));
+            result.push(255 - (8 - rest.len()) as u8);
+        }
+        Ok(result)
+    }
+
+    fn decode_fields(bytes: &[u8], types: &[Field]) -> Result<Vec<Vec<u8>>, Error> {
+        let mut rest = bytes;
+        let mut fields = vec![];
+        for width in widths(types) {
+            let mut field = vec![];
+            match width {
+                FieldWidth::Fixed(n) => {
+                    if rest.len() < n {
+                        return Err(invalid("truncated field"));
+                    }
+                    field.extend_from_slice(&rest[..n]);
+                    rest = &rest[n..];
+                }
+                FieldWidth::Escaped => loop {
+                    if rest.len() < 9 {
+                        return Err(invalid("truncated field"));
+                    }
+                    let pad = 255 - rest[8] as usize;
+                    if pad > 8 || rest[8 - pad..8].iter().any(|b| *b != 0) {
+                        return Err(invalid("invalid memcomparable group"));
+                    }
+                    field.extend_from_slice(&rest[..8 - pad]);
+                    rest = &rest[9..];
+                    if pad > 0 {
+                        break;
+                    }
+                },
+            }
+            fields.push(field);
+        }
+        if !rest.is_empty() {
+            return Err(invalid("trailing bytes"));
+        }
+        Ok(fields)
+    }
+}
+
+/// the foundationdb tuple layer: a type code before every field, byte strings
+/// with `0` escaped as `0 255` and closed by `0`, and integers in the fewest
+/// bytes, so that integer fields of any width compare with each other.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Tuple;
+
+const TUPLE_BYTES: u8 = 0x01;
+const TUPLE_STRING: u8 = 0x02;
+const TUPLE_NEG_BIG: u8 = 0x0b;
+const TUPLE_ZERO: u8 = 0x14;
+const TUPLE_POS_BIG: u8 = 0x1d;
+const TUPLE_F32: u8 = 0x20;
+const TUPLE_F64: u8 = 0x21;
+const TUPLE_FALSE: u8 = 0x26;
+const TUPLE_TRUE: u8 = 0x27;
+
+fn is_signed(field: Field) -> bool {
+    matches!(
+        field,
+        Field::I8 | Field::I16 | Field::I32 | Field::I64 | Field::I128
+    )
+}
+
+/// sign and magnitude of a canonical integer payload.
+fn int_from_payload(payload: &[u8], signed: bool) -> (bool, u128) {
+    let mut bytes = [0u8; 16];
+    bytes[16 - payload.len()..].copy_from_slice(payload);
+    let raw = u128::from_be_bytes(bytes);
+    if !signed {
+        return (false, raw);
+    }
+    let bits = payload.len() as u32 * 8;
+    let shift = 128 - bits;
+    let value = (((raw ^ (1 << (bits - 1))) << shift) as i128) >> shift;
+    (value < 0, value.unsigned_abs())
+}
+
+fn int_to_payload(negative: bool, magnitude: u128, field: Field) -> Result<Vec<u8>, Error> {
+    let width = field.width();
+    let bits = width as u32 * 8;
+    let out_of_range = || invalid("integer out of range");
+    let raw = if is_signed(field) {
+        let limit = 1u128 << (bits - 1);
+        if magnitude > limit || (!negative && magnitude == limit) {
+            return Err(out_of_range());
+        }
+        let value = if negative {
+            0u128.wrapping_sub(magnitude)
+        } else {
+            magnitude
+        };
+        value ^ limit
+    } else {
+        if negative || (bits < 128 && magnitude >> bits != 0) {
+            return Err(out_of_range());
+        }
+        magnitude
+    };
+    Ok(raw.to_be_bytes()[16 - width..].to_vec())
+}
+
+fn take(n: usize, rest: &mut &[u8]) -> Result<Vec<u8>, Error> {
+    if rest.len() < n {
+        return Err(invalid("truncated field"));
+    }
+    let (field, tail) = rest.split_at(n);
+    *rest = tail;
+    Ok(field.to_vec())
+}
+
+fn tuple_escape(field: &[u8], result: &mut Vec<u8>) {
+    for byte in field {
+        result.push(*byte);
+        if *byte == 0 {
+            result.push(255);
+        }
+    }
+    result.push(0);
+}
+
+impl KeyCodec for Tuple {
+    fn encode_fields(fields: &[Vec<u8>], types: &[Field]) -> Result<Vec<u8>, Error> {
+        let mut result = vec![];
+        for (payload, field) in fields.iter().zip(types) {
+            match field {
+                Field::Bytes => {
+                    result.push(TUPLE_BYTES);
+                    tuple_escape(payload, &mut result);
+                }
+                Field::String => {
+                    result.push(TUPLE_STRING);
+                    tuple_escape(payload, &mut result);
+                }
+                Field::Bool => result.push(if payload[0] == 0 {
+                    TUPLE_FALSE
+                } else {
+                    TUPLE_TRUE
+                }),
+                Field::F32 | Field::F64 => {
+                    result.push(if *field == Field::F32 {
+                        TUPLE_F32
+                    } else {
+                        TUPLE_F64
+                    });
+                    result.extend_from_slice(payload);
+                }
+                _ => {
+                    let (negative, magnitude) = int_from_payload(payload, is_signed(*field));
+                    let len = 16 - magnitude.leading_zeros() as usize / 8;
+                    let mut bytes = magnitude.to_be_bytes()[16 - len..].to_vec();
+                    match (negative, len) {
+                        (false, 0..=8) => result.push(TUPLE_ZERO + len as u8),
+                        (false, _) => result.extend_from_slice(&[TUPLE_POS_BIG, len as u8]),
+                        (true, 0..=8) => result.push(TUPLE_ZERO - len as u8),
+                        (true, _) => result.extend_from_slice(&[TUPLE_NEG_BIG, len as u8 ^ 255]),
+                    }
+                    if negative {
+                        bytes.iter_mut().for_each(|b| *b = !*b);
+                    }
+                    result.extend_from_slice(&bytes);
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    fn decode_fields(bytes: &[u8], types: &[Field]) -> Result<Vec<Vec<u8>>, Error> {
+        let mut rest = bytes;
+        let mut fields = vec![];
+        for field in types {
+            let code = take(1, &mut rest)?[0];
+            let payload = match (field, code) {
+                (Field::Bytes, TUPLE_BYTES) | (Field::String, TUPLE_STRING) => {
+                    let mut payload = vec![];
+                    loop {
+                        match take(1, &mut rest)?[0] {
+                            0 if rest.first() == Some(&255) => {
+                                rest = &rest[1..];
+                                payload.push(0);
+                            }
+                            0 => break,
+                            byte => payload.push(byte),
+                        }
+                    }
+                    payload
+                }
+                (Field::Bool, TUPLE_FALSE) => vec![0],
+                (Field::Bool, TUPLE_TRUE) => vec![1],
+                (Field::F32, TUPLE_F32) => take(4, &mut rest)?,
+                (Field::F64, TUPLE_F64) => take(8, &mut rest)?,
+                (Field::Bytes, _)
+                | (Field::String, _)
+                | (Field::Bool, _)
+                | (Field::F32, _)
+                | (Field::F64, _) => return Err(invalid("unexpected tuple type code")),
+                (_, TUPLE_NEG_BIG..=TUPLE_POS_BIG) => {
+                    let (negative, len) = match code {
+                        TUPLE_POS_BIG => (false, take(1, &mut rest)?[0] as usize),
+                        TUPLE_NEG_BIG => (true, (take(1, &mut rest)?[0] ^ 255) as usize),
+                        _ if code >= TUPLE_ZERO => (false, (code - TUPLE_ZERO) as usize),
+                        _ => (true, (TUPLE_ZERO - code) as usize),
+                    };
+                    if len > 16 {
+                        return Err(invalid("integer out of range"));
+                    }
+                    let mut bytes = take(len, &mut rest)?;
+                    if negative {
+                        bytes.iter_mut().for_each(|b| *b = !*b);
+                    }
+                    let mut magnitude = [0u8; 16];
+                    magnitude[16 - len..].copy_from_slice(&bytes);
+                    int_to_payload(negative, u128::from_be_bytes(magnitude), *field)?
+                }
+                _ => return Err(invalid("unexpected tuple type code")),
+            };
+            fields.push(payload);
+        }
+        if !rest.is_empty() {
+            return Err(invalid("trailing bytes"));
+        }
+        Ok(fields)
+    }
+}
+
+/// `value` encoded by `C` with the schema of its type.
+pub fn to_key_with<C: KeyCodec, T: IndexKey + KeySchema>(value: T) -> Result<Vec<u8>, Error> {
+    C::encode(value, &T::schema())
+}
+
+/// a `T` decoded by `C` with the schema of its type.
+pub fn from_key_with<C: KeyCodec, T: IndexKey + KeySchema>(bytes: &[u8]) -> Result<T, Error> {
+    C::decode(bytes, &T::schema())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Codec {
+    /// the canonical format of [`to_key`].
+    Escaped,
+    DoubleEnded,
+    Memcomparable,
+    Tuple,
+}
+
+impl Codec {
+    /// a canonical key of `schema` re-encoded in this layout.
+    pub fn encode(self, key: &[u8], schema: &Schema) -> Result<Vec<u8>, Error> {
+        let types = types(schema);
+        let fields = split(key, &widths(&types))?;
+        match self {
+            Codec::Escaped => Escaped::encode_fields(&fields, &types),
+            Codec::DoubleEnded => DoubleEnded::encode_fields(&fields, &types),
+            Codec::Memcomparable => Memcomparable::encode_fields(&fields, &types),
+            Codec::Tuple => Tuple::encode_fields(&fields, &types),
+        }
+    }
+
+    /// back to the canonical key.
+    pub fn decode(self, bytes: &[u8], schema: &Schema) -> Result<Vec<u8>, Error> {
+        let types = types(schema);
+        let fields = match self {
+            Codec::Escaped => Escaped::decode_fields(bytes, &types)?,
+            Codec::DoubleEnded => DoubleEnded::decode_fields(bytes, &types)?,
+            Codec::Memcomparable => Memcomparable::decode_fields(bytes, &types)?,
+            Codec::Tuple => Tuple::decode_fields(bytes, &types)?,
+        };
+        Ok(join(&fields, &widths(&types)))
+    }
+}
+
+#[test]
+fn test_double_ended() {
+    use crate::schema::Field;
+    use crate::{from_key, to_key};
+    let schema = Schema::new(&[
+        ("tenant", Field::U32),
+        ("version", Field::U16),
+        ("blob", Field::Bytes),
+    ])
+    .unwrap();
+    type Row = (u32, u16, Vec<u8>);
+    let row: Row = (7, 9, vec![0, 1, 2]);
+    let key = to_key(row.clone());
+    let bytes = Codec::DoubleEnded.encode(&key, &schema).unwrap();
+    assert_eq!(bytes, vec![0, 0, 0, 7, 0, 9, 0, 1, 2]);
+    let back = Codec::DoubleEnded.decode(&bytes, &schema).unwrap();
+    assert_eq!(back, key);
+    assert_eq!(from_key::<Row>(back).unwrap(), row);
+    assert_eq!(Codec::Escaped.encode(&key, &schema).unwrap(), key);
+    assert!(Codec::Escaped
+        .encode(&key[..key.len() - 1], &schema)
+        .is_err());
+    assert!(Codec::DoubleEnded.decode(&bytes[..5], &schema).is_err());
+
+    // byte strings of any length keep field order.
+    let mut rows: Vec<Row> = vec![
+        (1, 0, vec![]),
+        (1, 0, vec![0]),
+        (1, 0, vec![0, 0]),
+        (1, 0, vec![1]),
+        (1, 1, vec![]),
+        (2, 0, vec![255; 9]),
+    ];
+    let encoded: Vec<Vec<u8>> = rows
+        .iter()
+        .map(|r| DoubleEnded::encode(r.clone(), &schema).unwrap())
+        .collect();
+    assert!(encoded.windows(2).all(|w| w[0] < w[1]));
+    rows.reverse();
+    for (bytes, row) in encoded.iter().rev().zip(&rows) {
+        assert_eq!(&DoubleEnded::decode::<Row>(bytes, &schema).unwrap(), row);
+    }
+
+    // a byte string before another field would sort by raw bytes.
+    let blob_first = Schema::new(&[("hash", Field::Bytes), ("seq", Field::U8)]).unwrap();
+    let key = to_key((vec![9u8; 4], 1u8));
+    let err = Codec::DoubleEnded.encode(&key, &blob_first).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let err = DoubleEnded::encode((vec![9u8; 4], 1u8), &blob_first).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(Codec::DoubleEnded
+        .decode(&[9, 9, 9, 9, 1], &blob_first)
+        .is_err());
+}
+
+#[test]
+fn test_key_codec() {
+    use crate::schema::Field;
+    let schema = Schema::new(&[
+        ("name", Field::String),
+        ("delta", Field::I64),
+        ("size", Field::U128),
+        ("ok", Field::Bool),
+    ])
+    .unwrap();
+    type Row = (String, i64, u128, bool);
+    let mut rows: Vec<Row> = vec![
+        ("".to_owned(), 0, 0, false),
+        ("a".to_owned(), -1, 1, true),
+        ("a".to_owned(), i64::MIN, u128::MAX, false),
+        ("a\0".to_owned(), 300, 1 << 70, true),
+        ("abcdefgh".to_owned(), -300, 255, false),
+        ("abcdefghi".to_owned(), i64::MAX, 256, true),
+    ];
+
+    fn check<C: KeyCodec>(rows: &[Row], schema: &Schema) -> Vec<Vec<u8>> {
+        let keys: Vec<Vec<u8>> = rows
+            .iter()
+            .map(|row| C::encode(row.clone(), schema).unwrap())
+            .collect();
+        for (key, row) in keys.iter().zip(rows) {
+            C::validate(key, schema).unwrap();
+            assert_eq!(&C::decode::<Row>(key, schema).unwrap(), row);
+        }
+        keys
+    }
+    rows.sort();
+    for keys in [
+        check::<Escaped>(&rows, &schema),
+        check::<Memcomparable>(&rows, &schema),
+        check::<Tuple>(&rows, &schema),
+    ] {
+        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    }
+    let err = DoubleEnded::encode(rows[0].clone(), &schema).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let truncated = |key: Vec<u8>| key[..key.len() - 1].to_vec();
+    let key = truncated(Escaped::encode(rows[1].clone(), &schema).unwrap());
+    assert!(Escaped::validate(&key, &schema).is_err());
+    let key = truncated(Memcomparable::encode(rows[1].clone(), &schema).unwrap());
+    assert!(Memcomparable::validate(&key, &schema).is_err());
+    let key = truncated(Tuple::encode(rows[1].clone(), &schema).unwrap());
+    assert!(Tuple::validate(&key, &schema).is_err());
+
+    assert_eq!(
+        Memcomparable::encode(("abcdefgh".to_owned(), 0i64, 0u128, false), &schema).unwrap()[..18],
+        [b'a', b'b', b'c', b'd', b'e', b'f', b'g', b'h', 255, 0, 0, 0, 0, 0, 0, 0, 0, 247]
+    );
+    assert_eq!(
+        Tuple::encode(("a\0".to_owned(), -300i64, 1u128, true), &schema).unwrap(),
+        [2, b'a', 0, 255, 0, 0x12, !1, !44, 0x15, 1, 0x27]
+    );
+    let wide = Schema::new(&[("v", Field::I8)]).unwrap();
+    assert!(Tuple::decode::<i8>(&[0x15, 200], &wide).is_err());
+    assert_eq!(Tuple::decode::<i8>(&[0x13, !128], &wide).unwrap(), -128);
+
+    let range = Memcomparable::prefix_range("a".to_owned(), &schema).unwrap();
+    let keys = check::<Memcomparable>(&rows, &schema);
+    assert_eq!(keys.iter().filter(|k| range.contains(k)).count(), 2);
+    let range = Tuple::prefix_range(("a".to_owned(), -1i64), &schema).unwrap();
+    let keys = check::<Tuple>(&rows, &schema);
+    assert_eq!(keys.iter().filter(|k| range.contains(k)).count(), 1);
+    assert!(DoubleEnded::prefix_range("a".to_owned(), &schema).is_err());
+    let ids = Schema::new(&[("tenant", Field::U32), ("name", Field::String)]).unwrap();
+    assert!(DoubleEnded::prefix_range(7u32, &ids).is_ok());
+    let key = crate::to_key((7u32, "a\u{1}".to_owned()));
+    assert!(Escaped::validate(&key, &ids).is_ok());
+    assert!(Escaped::validate(&key[..key.len() - 1], &ids).is_err());
+    assert!(Escaped::validate(&[&key[..], &[0]].concat(), &ids).is_err());
+    assert!(Escaped::validate(&key[..3], &ids).is_err());
+    assert!(Tuple::prefix_range(1.5f64, &schema).is_err());
+
+    let key = crate::to_key(rows[3].clone());
+    for codec in [Codec::Memcomparable, Codec::Tuple] {
+        let bytes = codec.encode(&key, &schema).unwrap();
+        assert_eq!(codec.decode(&bytes, &schema).unwrap(), key);
+    }
+}
+
+#[test]
+fn test_key_struct() {
+    #[derive(Clone, Debug, PartialEq)]
+    struct Order {
+        tenant: u32,
+        customer: String,
+        total: i64,
+    }
+    crate::key_struct!(Order {
+        tenant: U32,
+        customer: String,
+        total: I64,
+    });
+
+    fn store<C: KeyCodec>(orders: &[Order]) -> Vec<Vec<u8>> {
+        let mut keys: Vec<_> = orders
+            .iter()
+            .map(|o| to_key_with::<C, _>(o.clone()).unwrap())
+            .collect();
+        keys.sort();
+        keys
+    }
+    let orders = [
+        Order {
+            tenant: 2,
+            customer: "a".to_owned(),
+            total: -5,
+        },
+        Order {
+            tenant: 1,
+            customer: "b\0".to_owned(),
+            total: 7,
+        },
+        Order {
+            tenant: 1,
+            customer: "b".to_owned(),
+            total: 9,
+        },
+    ];
+    let internal = store::<Escaped>(&orders);
+    let fdb = store::<Tuple>(&orders);
+    assert_eq!(internal[0], crate::to_key(orders[2].clone()));
+    let decode = |keys: &[Vec<u8>], tuple: bool| -> Vec<Order> {
+        keys.iter()
+            .map(|k| match tuple {
+                true => from_key_with::<Tuple, _>(k).unwrap(),
+                false => from_key_with::<Escaped, _>(k).unwrap(),
+            })
+            .collect()
+    };
+    let sorted = vec![orders[2].clone(), orders[1].clone(), orders[0].clone()];
+    assert_eq!(decode(&internal, false), sorted);
+    assert_eq!(decode(&fdb, true), sorted);
+    assert_eq!(Order::schema().position("total"), Some(2));
+}
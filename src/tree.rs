@@ -0,0 +1,117 @@
+//! materialized-path keys for trees.
+//!
+//! a [`TreePath`] encodes like a [`Seq`] of its segments: a 1 before every
+//! escaped segment and a closing 0. dropping the closing 0 leaves a prefix
+//! shared by the node and all of its descendants and nothing else, and since
+//! the 0 sorts before the 1 of any child, a scan visits the tree depth-first
+//! with every node before its children.
+//!
+//! siblings sort by their segment keys. to let users reorder siblings without
+//! renaming them, make the segment a `(position, name)` pair, with the
+//! position an [`OrderKey`](crate::order::OrderKey).
+
+use crate::range::KeyRange;
+use crate::{to_key, IndexKey, Seq};
+use std::io::{Error, Read, Write};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TreePath<S = String>(pub Vec<S>);
+
+impl<S: IndexKey + Clone> TreePath<S> {
+    pub fn root() -> TreePath<S> {
+        TreePath(vec![])
+    }
+
+    pub fn depth(&self) -> usize {
+        self.0.len()
+    }
+
+    /// `None` for the root.
+    pub fn parent(&self) -> Option<TreePath<S>> {
+        let (_, parent) = self.0.split_last()?;
+        Some(TreePath(parent.to_vec()))
+    }
+
+    pub fn child(&self, segment: S) -> TreePath<S> {
+        let mut path = self.0.clone();
+        path.push(segment);
+        TreePath(path)
+    }
+
+    /// this node and all its descendants.
+    pub fn subtree_range(&self) -> KeyRange {
+        let mut prefix = to_key(self.clone());
+        prefix.pop();
+        KeyRange::prefix(prefix)
+    }
+
+    /// the descendants only.
+    pub fn descendants_range(&self) -> KeyRange {
+        let mut prefix = to_key(self.clone());
+        *prefix.last_mut().unwrap() = 1;
+        KeyRange::prefix(prefix)
+    }
+}
+
+impl<S: IndexKey> IndexKey for TreePath<S> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        Seq(self.0).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        Ok(TreePath(Seq::from_key(key)?.0))
+    }
+}
+
+#[test]
+fn test_tree_path() {
+    use crate::from_key;
+    let path = |segments: &[&str]| TreePath(segments.iter().map(|s| s.to_string()).collect());
+    let mut keys: Vec<_> = [
+        path(&["b"]),
+        path(&["a", "x", "1"]),
+        path(&[]),
+        path(&["ab"]),
+        path(&["a"]),
+        path(&["a", "x"]),
+        path(&["a\0"]),
+    ]
+    .iter()
+    .map(|p| to_key(p.clone()))
+    .collect();
+    keys.sort();
+    let decoded: Vec<TreePath> = keys.iter().map(|k| from_key(k.clone()).unwrap()).collect();
+    assert_eq!(
+        decoded,
+        vec![
+            path(&[]),
+            path(&["a"]),
+            path(&["a", "x"]),
+            path(&["a", "x", "1"]),
+            path(&["a\0"]),
+            path(&["ab"]),
+            path(&["b"]),
+        ]
+    );
+
+    let a = path(&["a"]);
+    let subtree: Vec<_> = decoded
+        .iter()
+        .filter(|p| a.subtree_range().contains(&to_key((*p).clone())))
+        .collect();
+    assert_eq!(subtree.len(), 3);
+    assert!(!a.descendants_range().contains(&to_key(a.clone())));
+    assert!(a.descendants_range().contains(&to_key(path(&["a", "x"]))));
+    assert!(TreePath::<String>::root()
+        .subtree_range()
+        .contains(&keys[6]));
+
+    let deep = path(&["a", "x", "1"]);
+    assert_eq!(deep.depth(), 3);
+    assert_eq!(deep.parent(), Some(path(&["a", "x"])));
+    assert_eq!(a.child("x".to_owned()), path(&["a", "x"]));
+    assert_eq!(TreePath::<String>::root().parent(), None);
+    assert_eq!(
+        from_key::<(TreePath<u8>, u8)>(to_key((TreePath(vec![1u8, 2]), 3u8))).unwrap(),
+        (TreePath(vec![1, 2]), 3)
+    );
+}
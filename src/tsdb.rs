@@ -0,0 +1,242 @@
+//! time-series keys.
+//!
+//! a series is identified by a `u64` hashed from its metric name and sorted
+//! tags, and its samples are stored in chunks keyed `(series_id, !ts_bucket)`:
+//! one series is contiguous, newest chunk first, so reading the latest values
+//! starts at the front and retention deletes a tail. [`PostingKey`]s index
+//! series by tag for lookups.
+
+use crate::range::{prefix_successor, KeyRange};
+use crate::surrogate::hash_id;
+use crate::{to_key, IndexKey, Seq};
+use std::io::{Error, Read, Write};
+
+/// the tag pairs sorted, with repeated pairs dropped, and encoded as a
+/// [`Seq`] of `(key, value)`: equal tag sets give equal bytes whatever the
+/// input order.
+pub fn canonical_tags(tags: &[(&str, &str)]) -> Vec<u8> {
+    let mut tags: Vec<(String, String)> = tags
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    tags.sort();
+    tags.dedup();
+    to_key(Seq(tags))
+}
+
+/// hashed from the metric and [`canonical_tags`].
+pub fn series_id(metric: &str, tags: &[(&str, &str)]) -> u64 {
+    let mut key = to_key(metric.to_owned());
+    key.extend(canonical_tags(tags));
+    hash_id(&key)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ChunkKey {
+    pub series: u64,
+    /// start of the chunk, a multiple of the chunk width.
+    pub bucket: u64,
+}
+
+impl ChunkKey {
+    /// the chunk of `series` holding a sample at `ts`.
+    pub fn new(series: u64, ts: u64, width: u64) -> ChunkKey {
+        assert!(width > 0, "zero-width chunk");
+        ChunkKey {
+            series,
+            bucket: ts - ts % width,
+        }
+    }
+
+    /// every chunk of `series`, newest first.
+    pub fn series_range(series: u64) -> KeyRange {
+        KeyRange::prefix(to_key(series))
+    }
+
+    /// chunks holding samples in `[from, to]`, newest first.
+    pub fn between(series: u64, from: u64, to: u64, width: u64) -> KeyRange {
+        if from > to {
+            return KeyRange::new(vec![], vec![]);
+        }
+        let newest = ChunkKey::new(series, to, width);
+        let oldest = ChunkKey::new(series, from, width);
+        let end = match (!oldest.bucket).checked_add(1) {
+            Some(after) => Some(to_key((series, after))),
+            None => prefix_successor(&to_key(series)),
+        };
+        KeyRange {
+            start: to_key(newest),
+            end,
+        }
+    }
+
+    /// chunks whose samples are all older than `cutoff`, to delete for
+    /// retention.
+    pub fn expired(series: u64, cutoff: u64, width: u64) -> KeyRange {
+        assert!(width > 0, "zero-width chunk");
+        match cutoff.checked_sub(width) {
+            // the newest expired chunk starts at or before `cutoff - width`.
+            Some(last) => KeyRange {
+                start: to_key(ChunkKey::new(series, last, width)),
+                end: prefix_successor(&to_key(series)),
+            },
+            None => KeyRange::new(vec![], vec![]),
+        }
+    }
+}
+
+impl IndexKey for ChunkKey {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        (self.series, !self.bucket).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let (series, bucket) = <(u64, u64)>::from_key(key)?;
+        Ok(ChunkKey {
+            series,
+            bucket: !bucket,
+        })
+    }
+}
+
+/// an inverted-index entry `(tag_key, tag_value, series_id)`. the series of
+/// one tag pair are contiguous and ascending, so several pairs can be
+/// intersected with [`intersect_by_primary_key`](crate::intersect::intersect_by_primary_key)
+/// using `(String, String)` as the value.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PostingKey {
+    pub tag_key: String,
+    pub tag_value: String,
+    pub series: u64,
+}
+
+impl PostingKey {
+    /// one posting key per distinct tag pair of `series`.
+    pub fn for_series(series: u64, tags: &[(&str, &str)]) -> Vec<Vec<u8>> {
+        let mut tags = tags.to_vec();
+        tags.sort();
+        tags.dedup();
+        tags.iter()
+            .map(|(k, v)| {
+                to_key(PostingKey {
+                    tag_key: k.to_string(),
+                    tag_value: v.to_string(),
+                    series,
+                })
+            })
+            .collect()
+    }
+
+    /// every series tagged `key=value`, by series id.
+    pub fn postings_range(key: &str, value: &str) -> KeyRange {
+        KeyRange::prefix(to_key((key.to_owned(), value.to_owned())))
+    }
+
+    /// every series with tag `key`, by value and then series id.
+    pub fn tag_range(key: &str) -> KeyRange {
+        KeyRange::prefix(to_key(key.to_owned()))
+    }
+}
+
+impl IndexKey for PostingKey {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        (self.tag_key, self.tag_value, self.series).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let (tag_key, tag_value, series) = <(String, String, u64)>::from_key(key)?;
+        Ok(PostingKey {
+            tag_key,
+            tag_value,
+            series,
+        })
+    }
+}
+
+#[test]
+fn test_posting_key() {
+    use crate::from_key;
+    use crate::intersect::intersect_by_primary_key;
+    let series: Vec<(u64, Vec<(&str, &str)>)> = vec![
+        (3, vec![("dc", "eu"), ("host", "a")]),
+        (1, vec![("dc", "eu"), ("host", "b")]),
+        (2, vec![("dc", "us"), ("host", "a")]),
+        (4, vec![("dc", "eu"), ("host", "a"), ("dc", "eu")]),
+    ];
+    let mut index: Vec<Vec<u8>> = series
+        .iter()
+        .flat_map(|(id, tags)| PostingKey::for_series(*id, tags))
+        .collect();
+    index.sort();
+    assert_eq!(index.len(), 8);
+
+    let scan = |k: &str, v: &str| {
+        let range = PostingKey::postings_range(k, v);
+        index
+            .iter()
+            .filter(|key| range.contains(key))
+            .cloned()
+            .collect::<Vec<_>>()
+            .into_iter()
+    };
+    let eu: Vec<u64> = scan("dc", "eu")
+        .map(|k| from_key::<PostingKey>(k).unwrap().series)
+        .collect();
+    assert_eq!(eu, vec![1, 3, 4]);
+    let both: Vec<u64> =
+        intersect_by_primary_key::<(String, String), _>(vec![scan("dc", "eu"), scan("host", "a")])
+            .map(|pk| from_key(pk.unwrap()).unwrap())
+            .collect();
+    assert_eq!(both, vec![3, 4]);
+    let hosts = PostingKey::tag_range("host");
+    assert_eq!(index.iter().filter(|k| hosts.contains(k)).count(), 4);
+}
+
+#[test]
+fn test_tsdb() {
+    use crate::from_key;
+    let cpu = series_id("cpu", &[("host", "a"), ("dc", "eu")]);
+    assert_eq!(cpu, series_id("cpu", &[("dc", "eu"), ("host", "a")]));
+    assert_ne!(cpu, series_id("cpu", &[("dc", "eu"), ("host", "b")]));
+    assert_ne!(cpu, series_id("mem", &[("dc", "eu"), ("host", "a")]));
+    assert_eq!(
+        cpu,
+        series_id("cpu", &[("host", "a"), ("dc", "eu"), ("host", "a")])
+    );
+    assert_eq!(
+        canonical_tags(&[("b", "1"), ("a", "2"), ("b", "1")]),
+        canonical_tags(&[("a", "2"), ("b", "1")])
+    );
+    assert_ne!(
+        canonical_tags(&[("a", "b=c")]),
+        canonical_tags(&[("a=b", "c")])
+    );
+    assert_eq!(
+        from_key::<Seq<(String, String)>>(canonical_tags(&[("k", "v")]))
+            .unwrap()
+            .0,
+        vec![("k".to_owned(), "v".to_owned())]
+    );
+
+    let mut keys: Vec<_> = (0..10u64)
+        .map(|i| to_key(ChunkKey::new(cpu, i * 3600 + 5, 3600)))
+        .collect();
+    keys.push(to_key(ChunkKey::new(cpu.wrapping_add(1), 0, 3600)));
+    keys.sort();
+    let newest = keys
+        .iter()
+        .find(|k| ChunkKey::series_range(cpu).contains(k))
+        .unwrap();
+    assert_eq!(
+        from_key::<ChunkKey>(newest.clone()).unwrap().bucket,
+        9 * 3600
+    );
+
+    let count = |range: &KeyRange| keys.iter().filter(|k| range.contains(k)).count();
+    assert_eq!(count(&ChunkKey::series_range(cpu)), 10);
+    assert_eq!(count(&ChunkKey::between(cpu, 3600, 3 * 3600 + 1, 3600)), 3);
+    assert_eq!(count(&ChunkKey::between(cpu, 0, u64::MAX, 3600)), 10);
+    assert_eq!(count(&ChunkKey::between(cpu, 5, 4, 3600)), 0);
+    // samples before 4h: chunks 0h..3h expired, chunk 3h ends at 4h.
+    assert_eq!(count(&ChunkKey::expired(cpu, 4 * 3600, 3600)), 4);
+    assert_eq!(count(&ChunkKey::expired(cpu, 4 * 3600 - 1, 3600)), 3);
+    assert_eq!(count(&ChunkKey::expired(cpu, 10, 3600)), 0);
+}
@@ -0,0 +1,237 @@
+//! web-crawl style url keys.
+//!
+//! a url encodes as scheme, host labels in reverse (`com`, `example`, `www`),
+//! port, path segments and query, each label and segment escaped on its own.
+//! lists are written as `1 item` repeated and closed by `0`, so a shorter list
+//! sorts first and an unclosed list is a prefix of every longer one: all pages
+//! of a site and all subdomains of a domain are contiguous ranges.
+
+use crate::range::KeyRange;
+use crate::{to_key, IndexKey};
+use std::fmt;
+use std::io::{Error, ErrorKind, Read, Write};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct UrlKey {
+    pub scheme: String,
+    pub host: String,
+    pub port: u16,
+    pub path: Vec<String>,
+    pub query: String,
+}
+
+fn default_port(scheme: &str) -> u16 {
+    match scheme {
+        "http" | "ws" => 80,
+        "https" | "wss" => 443,
+        "ftp" => 21,
+        _ => 0,
+    }
+}
+
+fn write_list<'a, W: Write, I: Iterator<Item = &'a str>>(
+    items: I,
+    close: bool,
+    result: &mut W,
+) -> Result<(), Error> {
+    for item in items {
+        result.write_all(&[1])?;
+        item.to_owned().to_key(result)?;
+    }
+    if close {
+        result.write_all(&[0])?;
+    }
+    Ok(())
+}
+
+fn read_list<R: Read>(key: &mut R) -> Result<Vec<String>, Error> {
+    let mut items = vec![];
+    loop {
+        let mut marker = [0];
+        key.read_exact(&mut marker)?;
+        match marker[0] {
+            0 => return Ok(items),
+            1 => items.push(String::from_key(key)?),
+            _ => return Err(Error::new(ErrorKind::InvalidData, "invalid list marker")),
+        }
+    }
+}
+
+impl UrlKey {
+    /// `scheme://host[:port][/path][?query][#fragment]`; scheme and host are
+    /// lowercased, the default port is filled in and the fragment dropped.
+    pub fn parse(url: &str) -> Result<UrlKey, Error> {
+        let invalid = |msg: &str| Error::new(ErrorKind::InvalidInput, msg.to_owned());
+        let (scheme, rest) = url
+            .split_once("://")
+            .ok_or_else(|| invalid("missing scheme"))?;
+        let rest = rest.split('#').next().unwrap_or("");
+        let (rest, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i + 1..]),
+            None => (rest, ""),
+        };
+        let authority = authority.rsplit('@').next().unwrap_or("");
+        let scheme = scheme.to_ascii_lowercase();
+        // an ipv6 host is bracketed and may itself contain ':'.
+        let split = match authority.rfind(']') {
+            Some(end) => authority[end..].find(':').map(|i| end + i),
+            None => authority.rfind(':'),
+        };
+        let (host, port) = match split {
+            Some(i) => (
+                &authority[..i],
+                authority[i + 1..]
+                    .parse()
+                    .map_err(|_| invalid("invalid port"))?,
+            ),
+            None => (authority, default_port(&scheme)),
+        };
+        if scheme.is_empty() || host.is_empty() {
+            return Err(invalid("missing host"));
+        }
+        Ok(UrlKey {
+            scheme,
+            host: host.to_ascii_lowercase(),
+            port,
+            path: path
+                .split('/')
+                .filter(|s| !s.is_empty())
+                .map(str::to_owned)
+                .collect(),
+            query: query.to_owned(),
+        })
+    }
+
+    fn write_prefix<W: Write>(&self, result: &mut W) -> Result<(), Error> {
+        self.scheme.clone().to_key(result)?;
+        write_list(self.host.rsplit('.'), true, result)?;
+        self.port.to_key(result)?;
+        Ok(())
+    }
+
+    /// `domain` and every subdomain of it, under `scheme`.
+    pub fn domain_range(scheme: &str, domain: &str) -> KeyRange {
+        let mut prefix = to_key(scheme.to_ascii_lowercase());
+        let domain = domain.to_ascii_lowercase();
+        write_list(domain.rsplit('.'), false, &mut prefix).expect("vec write");
+        KeyRange::prefix(prefix)
+    }
+
+    /// every page of this url's scheme, host and port.
+    pub fn site_range(&self) -> KeyRange {
+        let mut prefix = vec![];
+        self.write_prefix(&mut prefix).expect("vec write");
+        KeyRange::prefix(prefix)
+    }
+
+    /// this path and everything below it.
+    pub fn subtree_range(&self) -> KeyRange {
+        let mut prefix = vec![];
+        self.write_prefix(&mut prefix).expect("vec write");
+        write_list(self.path.iter().map(|s| &s[..]), false, &mut prefix).expect("vec write");
+        KeyRange::prefix(prefix)
+    }
+}
+
+impl fmt::Display for UrlKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}://{}", self.scheme, self.host)?;
+        if self.port != default_port(&self.scheme) {
+            write!(f, ":{}", self.port)?;
+        }
+        write!(f, "/{}", self.path.join("/"))?;
+        if !self.query.is_empty() {
+            write!(f, "?{}", self.query)?;
+        }
+        Ok(())
+    }
+}
+
+impl IndexKey for UrlKey {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        self.write_prefix(result)?;
+        write_list(self.path.iter().map(|s| &s[..]), true, result)?;
+        self.query.to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        let scheme = String::from_key(key)?;
+        let mut labels = read_list(key)?;
+        labels.reverse();
+        Ok(UrlKey {
+            scheme,
+            host: labels.join("."),
+            port: u16::from_key(key)?,
+            path: read_list(key)?,
+            query: String::from_key(key)?,
+        })
+    }
+}
+
+#[test]
+fn test_url_key() {
+    use crate::from_key;
+    let url = UrlKey::parse("HTTPS://user@WWW.Example.com/a/b/?q=1#top").unwrap();
+    assert_eq!(url.host, "www.example.com");
+    assert_eq!(url.port, 443);
+    assert_eq!(url.path, vec!["a", "b"]);
+    assert_eq!(url.to_string(), "https://www.example.com/a/b?q=1");
+    assert_eq!(from_key::<UrlKey>(to_key(url.clone())).unwrap(), url);
+    assert_eq!(UrlKey::parse("http://h:8080").unwrap().port, 8080);
+    assert_eq!(UrlKey::parse("http://[::1]/").unwrap().host, "[::1]");
+    assert_eq!(UrlKey::parse("http://[::1]:81/").unwrap().port, 81);
+    assert!(UrlKey::parse("example.com/a").is_err());
+    assert!(UrlKey::parse("http://h:port/").is_err());
+
+    let keys = |urls: &[&str]| -> Vec<Vec<u8>> {
+        urls.iter()
+            .map(|u| to_key(UrlKey::parse(u).unwrap()))
+            .collect()
+    };
+    let domain = UrlKey::domain_range("https", "example.com");
+    for key in keys(&[
+        "https://example.com/",
+        "https://www.example.com/x",
+        "https://a.b.example.com/",
+    ]) {
+        assert!(domain.contains(&key));
+    }
+    for key in keys(&[
+        "http://example.com/",
+        "https://example.co/",
+        "https://notexample.com/",
+        "https://example.com.evil.org/",
+    ]) {
+        assert!(!domain.contains(&key));
+    }
+
+    let page = UrlKey::parse("https://example.com/docs").unwrap();
+    let site = page.site_range();
+    let subtree = page.subtree_range();
+    let inside = keys(&["https://example.com/docs/a", "https://example.com/docs?x"]);
+    assert!(inside
+        .iter()
+        .all(|k| site.contains(k) && subtree.contains(k)));
+    let other = keys(&["https://example.com/docsx", "https://example.com:444/docs"]);
+    assert!(!subtree.contains(&other[0]) && site.contains(&other[0]));
+    assert!(!site.contains(&other[1]));
+
+    let mut sorted = keys(&[
+        "https://example.com/b",
+        "https://example.com/a/z",
+        "https://example.com/",
+    ]);
+    sorted.sort();
+    let order: Vec<String> = sorted
+        .into_iter()
+        .map(|k| from_key::<UrlKey>(k).unwrap().to_string())
+        .collect();
+    assert_eq!(
+        order,
+        vec![
+            "https://example.com/",
+            "https://example.com/a/z",
+            "https://example.com/b"
+        ]
+    );
+}
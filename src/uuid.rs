@@ -0,0 +1,46 @@
+//! keys for `uuid::Uuid`.
+//!
+//! a uuid encodes as its 16 bytes in RFC 4122 order, as given by
+//! `Uuid::as_bytes`, so it embeds in composite keys at a fixed width and
+//! time-ordered versions (6 and 7) sort by creation time.
+
+use crate::IndexKey;
+use ::uuid::Uuid;
+use std::io::{Error, Read, Write};
+
+impl IndexKey for Uuid {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        (*self.as_bytes()).to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        <[u8; 16]>::from_key(key).map(Uuid::from_bytes)
+    }
+}
+
+#[test]
+fn test_uuid() {
+    use crate::{from_key, to_key};
+    let id = Uuid::parse_str("00112233-4455-6677-8899-AABBCCDDEEFF").unwrap();
+    // byte order is fixed: changing it would reorder stored keys.
+    assert_eq!(
+        to_key(id),
+        vec![
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff
+        ]
+    );
+    assert_eq!(from_key::<Uuid>(to_key(id)).unwrap(), id);
+    assert_eq!(from_key::<(Uuid, u8)>(to_key((id, 9u8))).unwrap(), (id, 9));
+    assert!(to_key(id) < to_key(Uuid::from_u128(id.as_u128() + 1)));
+
+    // version 7: 48-bit milliseconds first, so later ids sort after.
+    let v7 = |ms: u64, rand: u8| {
+        let mut bytes = [rand; 16];
+        bytes[..6].copy_from_slice(&ms.to_be_bytes()[2..]);
+        bytes[6] = 0x70 | (rand & 0x0f);
+        Uuid::from_bytes(bytes)
+    };
+    let ids = [v7(1, 255), v7(2, 0), v7(1 << 40, 7)];
+    assert!(ids.windows(2).all(|w| to_key(w[0]) < to_key(w[1])));
+    assert_eq!(ids[1].get_version_num(), 7);
+}
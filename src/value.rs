@@ -0,0 +1,176 @@
+//! length-prefixed encoding for the non-key part of a record.
+//!
+//! [`to_value`] / [`from_value`] mirror [`to_key`](crate::to_key) /
+//! [`from_key`](crate::from_key) but skip the order-preserving escaping: byte
+//! strings are written as a LEB128 length and the raw bytes. the output does not
+//! sort meaningfully, so never use it as a key.
+
+use crate::codec::FixedWidth;
+use std::io::{Cursor, Error, ErrorKind, Read, Write};
+
+pub trait IndexValue: Sized {
+    fn to_value<W: Write>(self, result: &mut W) -> Result<&mut W, Error>;
+    fn from_value<R: Read>(value: &mut R) -> Result<Self, Error>;
+}
+
+impl<T: FixedWidth> IndexValue for T {
+    fn to_value<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        result.write_all(self.encode().as_ref())?;
+        Ok(result)
+    }
+    fn from_value<R: Read>(value: &mut R) -> Result<Self, Error> {
+        let mut bytes = T::Bytes::default();
+        value.read_exact(bytes.as_mut())?;
+        Ok(T::decode(bytes))
+    }
+}
+
+pub(crate) fn write_len<W: Write>(mut len: u64, result: &mut W) -> Result<(), Error> {
+    loop {
+        let byte = (len & 0x7f) as u8;
+        len >>= 7;
+        if len == 0 {
+            return result.write_all(&[byte]);
+        }
+        result.write_all(&[byte | 0x80])?;
+    }
+}
+
+/// rejects lengths past `u64::MAX` and trailing zero groups, so every length
+/// has exactly one encoding.
+pub(crate) fn read_len<R: Read>(value: &mut R) -> Result<u64, Error> {
+    let mut len = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0u8];
+        value.read_exact(&mut byte)?;
+        let bits = u64::from(byte[0] & 0x7f);
+        if shift == 63 && bits > 1 {
+            return Err(Error::new(ErrorKind::InvalidData, "length prefix overflow"));
+        }
+        len |= bits << shift;
+        if byte[0] & 0x80 == 0 {
+            if byte[0] == 0 && shift > 0 {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "redundant length prefix byte",
+                ));
+            }
+            return Ok(len);
+        }
+    }
+    Err(Error::new(ErrorKind::InvalidData, "length prefix too long"))
+}
+
+impl IndexValue for Vec<u8> {
+    fn to_value<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        write_len(self.len() as u64, result)?;
+        result.write_all(&self)?;
+        Ok(result)
+    }
+    fn from_value<R: Read>(value: &mut R) -> Result<Self, Error> {
+        let len = read_len(value)?;
+        let mut bytes = vec![];
+        // `take` keeps a corrupt length from allocating up front.
+        value.take(len).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != len {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "truncated value"));
+        }
+        Ok(bytes)
+    }
+}
+
+impl IndexValue for String {
+    fn to_value<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        self.into_bytes().to_value(result)
+    }
+    fn from_value<R: Read>(value: &mut R) -> Result<Self, Error> {
+        String::from_utf8(Vec::<u8>::from_value(value)?)
+            .map_err(|_| Error::new(ErrorKind::InvalidData, "invalid utf-8 string"))
+    }
+}
+
+macro_rules! impl_value_tuple {
+    ( $( $v:ident ),+ ) => {
+        impl< $( $v ),+ > IndexValue for ( $($v),+ )
+        where
+            $( $v : IndexValue ,)+
+        {
+            #[allow(non_snake_case)]
+            fn to_value<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+                let ($( $v,)+) = self;
+                $(
+                    $v.to_value(result)?;
+                )+
+                Ok(result)
+            }
+            fn from_value<R: Read>(value: &mut R) -> Result<( $($v),+ ), Error> {
+                Ok(( $(
+                    $v::from_value(value)?,
+                )+ ))
+            }
+        }
+    }
+}
+
+impl_value_tuple!(T1, T2);
+impl_value_tuple!(T1, T2, T3);
+impl_value_tuple!(T1, T2, T3, T4);
+impl_value_tuple!(T1, T2, T3, T4, T5);
+impl_value_tuple!(T1, T2, T3, T4, T5, T6);
+impl_value_tuple!(T1, T2, T3, T4, T5, T6, T7);
+impl_value_tuple!(T1, T2, T3, T4, T5, T6, T7, T8);
+impl_value_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9);
+impl_value_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
+impl_value_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11);
+impl_value_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12);
+impl_value_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13);
+impl_value_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14);
+impl_value_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15);
+impl_value_tuple!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15, T16);
+
+/// panics if `i` cannot be encoded; see [`try_to_value`].
+pub fn to_value<I: IndexValue>(i: I) -> Vec<u8> {
+    try_to_value(i).expect("value encoding failed")
+}
+
+pub fn try_to_value<I: IndexValue>(i: I) -> Result<Vec<u8>, Error> {
+    let mut result = vec![];
+    i.to_value(&mut result)?;
+    Ok(result)
+}
+
+pub fn from_value<I: IndexValue>(src: Vec<u8>) -> Result<I, Error> {
+    I::from_value(&mut Cursor::new(src))
+}
+
+#[test]
+fn test_value() {
+    let record = (vec![0u8, 1, 2], "päyload".to_owned(), 7u32, -1.5f64, true);
+    let value = to_value(record.clone());
+    assert_eq!(&value[..4], &[3, 0, 1, 2]);
+    assert_eq!(
+        from_value::<(Vec<u8>, String, u32, f64, bool)>(value).unwrap(),
+        record
+    );
+
+    let long = vec![0u8; 300];
+    let value = to_value(long.clone());
+    assert_eq!(&value[..2], &[0xac, 0x02]);
+    assert_eq!(value.len(), 302);
+    assert_eq!(from_value::<Vec<u8>>(value).unwrap(), long);
+    assert!(crate::to_key(long).len() > 600);
+
+    assert!(from_value::<Vec<u8>>(vec![5, 1, 2]).is_err());
+    assert!(from_value::<Vec<u8>>(vec![0xff; 11]).is_err());
+    let mut max = vec![0xff; 9];
+    max.push(0x01);
+    let mut cur = Cursor::new(&max[..]);
+    assert_eq!(read_len(&mut cur).unwrap(), u64::MAX);
+    max[9] = 0x02;
+    let err = read_len(&mut Cursor::new(&max[..])).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    let err = read_len(&mut Cursor::new(&[0x81u8, 0x00][..])).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert_eq!(read_len(&mut Cursor::new(&[0u8][..])).unwrap(), 0);
+    assert!(from_value::<String>(vec![1, 0xff]).is_err());
+}
@@ -0,0 +1,101 @@
+//! append-only versions: every update is a new `(key, version)` entry.
+
+use crate::range::KeyRange;
+use crate::{from_key, to_key, IndexKey};
+use std::io::{Error, Read, Write};
+
+/// encodes as `(key, version)`, so versions of one key are adjacent and ascending.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Versioned<T> {
+    pub key: T,
+    pub version: u64,
+}
+
+impl<T: IndexKey> IndexKey for Versioned<T> {
+    fn to_key<W: Write>(self, result: &mut W) -> Result<&mut W, Error> {
+        self.key.to_key(result)?;
+        self.version.to_key(result)
+    }
+    fn from_key<R: Read>(key: &mut R) -> Result<Self, Error> {
+        Ok(Versioned {
+            key: T::from_key(key)?,
+            version: u64::from_key(key)?,
+        })
+    }
+}
+
+impl<T: IndexKey + Clone + PartialEq> Versioned<T> {
+    pub fn new(key: T, version: u64) -> Versioned<T> {
+        Versioned { key, version }
+    }
+
+    /// the entry to append for the next update of the same key; `None` once
+    /// the version is `u64::MAX`, as a wrapped version would sort as the oldest.
+    pub fn next(&self) -> Option<Versioned<T>> {
+        let version = self.version.checked_add(1)?;
+        Some(Versioned::new(self.key.clone(), version))
+    }
+
+    /// every version of `key`.
+    pub fn history_range(key: T) -> KeyRange {
+        KeyRange::prefix(to_key(key))
+    }
+
+    /// newest version of each logical key in a sorted scan.
+    pub fn latest<K: AsRef<[u8]>, I: IntoIterator<Item = K>>(
+        keys: I,
+    ) -> Result<Vec<Versioned<T>>, Error> {
+        let mut result: Vec<Versioned<T>> = vec![];
+        for key in keys {
+            let entry: Versioned<T> = from_key(key.as_ref().to_vec())?;
+            match result.last_mut() {
+                Some(last) if last.key == entry.key => *last = entry,
+                _ => result.push(entry),
+            }
+        }
+        Ok(result)
+    }
+
+    /// all versions of each logical key in a sorted scan, oldest first.
+    pub fn history<K: AsRef<[u8]>, I: IntoIterator<Item = K>>(
+        keys: I,
+    ) -> Result<Vec<(T, Vec<u64>)>, Error> {
+        let mut result: Vec<(T, Vec<u64>)> = vec![];
+        for key in keys {
+            let entry: Versioned<T> = from_key(key.as_ref().to_vec())?;
+            match result.last_mut() {
+                Some((last, versions)) if *last == entry.key => versions.push(entry.version),
+                _ => result.push((entry.key, vec![entry.version])),
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[test]
+fn test_versioned() {
+    let mut keys: Vec<Vec<u8>> = vec![
+        to_key(Versioned::new("b".to_owned(), 1)),
+        to_key(Versioned::new("a".to_owned(), 2)),
+        to_key(Versioned::new("ab".to_owned(), 7)),
+        to_key(Versioned::new("a".to_owned(), 1)),
+        to_key(Versioned::new("a".to_owned(), 256)),
+    ];
+    keys.sort();
+
+    let latest = Versioned::<String>::latest(&keys).unwrap();
+    assert_eq!(
+        latest,
+        vec![
+            Versioned::new("a".to_owned(), 256),
+            Versioned::new("ab".to_owned(), 7),
+            Versioned::new("b".to_owned(), 1),
+        ]
+    );
+    assert_eq!(latest[2].next(), Some(Versioned::new("b".to_owned(), 2)));
+    assert_eq!(Versioned::new("b".to_owned(), u64::MAX).next(), None);
+
+    let range = Versioned::history_range("a".to_owned());
+    let history = Versioned::<String>::history(keys.iter().filter(|k| range.contains(k))).unwrap();
+    assert_eq!(history, vec![("a".to_owned(), vec![1, 2, 256])]);
+}
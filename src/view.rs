@@ -0,0 +1,138 @@
+//! deriving materialized-view keys from base records.
+//!
+//! a [`Derive`] maps one base record to the view keys it should appear under.
+//! on update, [`delta`] diffs the keys derived from the old and new record, so
+//! callers only delete what disappeared and insert what is new.
+
+use crate::IndexKey;
+use std::collections::BTreeSet;
+
+pub trait Derive<R: ?Sized> {
+    /// the view keys of `record`, in any order; duplicates are ignored.
+    fn derive(&self, record: &R) -> Vec<Vec<u8>>;
+
+    /// keys from both `self` and `other`, e.g. two views kept in one keyspace.
+    fn and<D: Derive<R>>(self, other: D) -> And<Self, D>
+    where
+        Self: Sized,
+    {
+        And(self, other)
+    }
+
+    /// derives nothing for records failing `keep`.
+    fn when<P: Fn(&R) -> bool>(self, keep: P) -> When<Self, P>
+    where
+        Self: Sized,
+    {
+        When(self, keep)
+    }
+}
+
+impl<R: ?Sized, F: Fn(&R) -> Vec<Vec<u8>>> Derive<R> for F {
+    fn derive(&self, record: &R) -> Vec<Vec<u8>> {
+        self(record)
+    }
+}
+
+pub struct And<A, B>(A, B);
+
+impl<R: ?Sized, A: Derive<R>, B: Derive<R>> Derive<R> for And<A, B> {
+    fn derive(&self, record: &R) -> Vec<Vec<u8>> {
+        let mut keys = self.0.derive(record);
+        keys.extend(self.1.derive(record));
+        keys
+    }
+}
+
+pub struct When<D, P>(D, P);
+
+impl<R: ?Sized, D: Derive<R>, P: Fn(&R) -> bool> Derive<R> for When<D, P> {
+    fn derive(&self, record: &R) -> Vec<Vec<u8>> {
+        if (self.1)(record) {
+            self.0.derive(record)
+        } else {
+            vec![]
+        }
+    }
+}
+
+/// one view key per record.
+pub fn one<R: ?Sized, K: IndexKey, F: Fn(&R) -> K>(f: F) -> impl Derive<R> {
+    move |record: &R| vec![crate::to_key(f(record))]
+}
+
+/// any number of view keys per record, e.g. one per tag.
+pub fn many<R: ?Sized, K: IndexKey, F: Fn(&R) -> Vec<K>>(f: F) -> impl Derive<R> {
+    move |record: &R| f(record).into_iter().map(crate::to_key).collect()
+}
+
+/// view writes for one base-record change, both sorted.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Delta {
+    pub delete: Vec<Vec<u8>>,
+    pub insert: Vec<Vec<u8>>,
+}
+
+impl Delta {
+    pub fn is_empty(&self) -> bool {
+        self.delete.is_empty() && self.insert.is_empty()
+    }
+}
+
+/// `None` for a record that did not exist before, or was deleted. keys
+/// derived from both records are left alone.
+pub fn delta<R: ?Sized, D: Derive<R>>(view: &D, old: Option<&R>, new: Option<&R>) -> Delta {
+    let keys = |record: Option<&R>| -> BTreeSet<Vec<u8>> {
+        record.map_or_else(BTreeSet::new, |r| view.derive(r).into_iter().collect())
+    };
+    let (old, new) = (keys(old), keys(new));
+    Delta {
+        delete: old.difference(&new).cloned().collect(),
+        insert: new.difference(&old).cloned().collect(),
+    }
+}
+
+#[test]
+fn test_delta() {
+    use crate::to_key;
+    struct User {
+        id: u32,
+        email: String,
+        tags: Vec<String>,
+        active: bool,
+    }
+    let view = one(|u: &User| (u.email.clone(), u.id))
+        .when(|u: &User| u.active)
+        .and(many(|u: &User| {
+            u.tags.iter().map(|t| (t.clone(), u.id)).collect()
+        }));
+    let old = User {
+        id: 1,
+        email: "a@x".to_owned(),
+        tags: vec!["red".to_owned(), "big".to_owned()],
+        active: true,
+    };
+    let new = User {
+        email: "b@x".to_owned(),
+        tags: vec!["big".to_owned(), "new".to_owned()],
+        ..old
+    };
+    let key = |s: &str| to_key((s.to_owned(), 1u32));
+
+    let d = delta(&view, Some(&old), Some(&new));
+    let mut delete = vec![key("a@x"), key("red")];
+    let mut insert = vec![key("b@x"), key("new")];
+    delete.sort();
+    insert.sort();
+    assert_eq!(d, Delta { delete, insert });
+
+    let d = delta(&view, None, Some(&new));
+    assert_eq!((d.delete.len(), d.insert.len()), (0, 3));
+    let inactive = User {
+        active: false,
+        tags: vec![],
+        ..new
+    };
+    assert_eq!(delta(&view, Some(&inactive), None).insert.len(), 0);
+    assert!(delta(&view, Some(&inactive), Some(&inactive)).is_empty());
+}
@@ -0,0 +1,108 @@
+//! per-partition progress markers stored as `(namespace, partition, !ts)`.
+//!
+//! the inverted timestamp puts the newest marker of a partition first.
+
+use crate::range::KeyRange;
+use crate::{from_key, to_key, IndexKey};
+use std::io::{Error, ErrorKind};
+
+pub const WATERMARK_NAMESPACE: &str = "__watermark";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Watermark<P> {
+    pub partition: P,
+    pub ts: u64,
+}
+
+impl<P: IndexKey + Clone> Watermark<P> {
+    pub fn new(partition: P, ts: u64) -> Watermark<P> {
+        Watermark { partition, ts }
+    }
+
+    pub fn key(&self) -> Vec<u8> {
+        to_key((
+            WATERMARK_NAMESPACE.to_owned(),
+            self.partition.clone(),
+            !self.ts,
+        ))
+    }
+
+    pub fn from_key(key: Vec<u8>) -> Result<Watermark<P>, Error> {
+        let (namespace, partition, ts): (String, P, u64) = from_key(key)?;
+        if namespace != WATERMARK_NAMESPACE {
+            return Err(Error::new(ErrorKind::InvalidData, "not a watermark key"));
+        }
+        Ok(Watermark { partition, ts: !ts })
+    }
+
+    /// every marker of `partition`, newest first.
+    pub fn range(partition: P) -> KeyRange {
+        KeyRange::prefix(to_key((WATERMARK_NAMESPACE.to_owned(), partition)))
+    }
+
+    /// every marker of every partition.
+    pub fn namespace() -> KeyRange {
+        KeyRange::prefix(to_key(WATERMARK_NAMESPACE.to_owned()))
+    }
+
+    /// decode the first key of a scan over [`Watermark::range`].
+    pub fn latest<K: AsRef<[u8]>, I: IntoIterator<Item = K>>(
+        keys: I,
+    ) -> Result<Option<Watermark<P>>, Error> {
+        keys.into_iter()
+            .next()
+            .map(|key| Watermark::from_key(key.as_ref().to_vec()))
+            .transpose()
+    }
+
+    /// the marker to write for `ts`, or `None` if it would move backwards.
+    pub fn advance(&self, ts: u64) -> Option<Watermark<P>> {
+        if ts > self.ts {
+            Some(Watermark::new(self.partition.clone(), ts))
+        } else {
+            None
+        }
+    }
+
+    /// older markers of the same partition, safe to delete once this one is written.
+    pub fn superseded_range(&self) -> KeyRange {
+        let mut start = self.key();
+        start.push(0);
+        KeyRange {
+            start,
+            end: Watermark::range(self.partition.clone()).end,
+        }
+    }
+}
+
+#[test]
+fn test_watermark() {
+    let mut keys: Vec<Vec<u8>> = [(1u32, 10u64), (1, 30), (1, 20), (2, 5), (0, 50)]
+        .iter()
+        .map(|(p, ts)| Watermark::new(*p, *ts).key())
+        .collect();
+    keys.push(to_key(("data".to_owned(), 1u32, 1u64)));
+    keys.sort();
+
+    let range = Watermark::range(1u32);
+    let in_range: Vec<_> = keys.iter().filter(|k| range.contains(k)).collect();
+    assert_eq!(in_range.len(), 3);
+    let latest = Watermark::<u32>::latest(in_range).unwrap().unwrap();
+    assert_eq!(latest, Watermark::new(1, 30));
+
+    assert_eq!(latest.advance(25), None);
+    let next = latest.advance(40).unwrap();
+    let superseded = next.superseded_range();
+    assert!(!superseded.contains(&next.key()));
+    assert!(superseded.contains(&latest.key()));
+    assert!(superseded.contains(&Watermark::new(1u32, 0).key()));
+    assert!(!superseded.contains(&Watermark::new(2u32, 0).key()));
+
+    assert_eq!(
+        keys.iter()
+            .filter(|k| Watermark::<u32>::namespace().contains(k))
+            .count(),
+        5
+    );
+    assert!(Watermark::<u32>::from_key(to_key(("data".to_owned(), 1u32, 1u64))).is_err());
+}